@@ -0,0 +1,91 @@
+// Derives for the conversion traits of `r_piece::evaluator::convert`.
+// Structs with named fields map to script hashes keyed by field name.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, Generics, Ident};
+
+#[proc_macro_derive(IntoObject)]
+pub fn derive_into_object(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match named_fields(&input) {
+        Ok(fields) => fields,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let name = &input.ident;
+    let generics = with_bound(
+        input.generics.clone(),
+        quote!(::r_piece::evaluator::convert::IntoObject),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let keys = fields.iter().map(|f| f.to_string());
+
+    quote! {
+        impl #impl_generics ::r_piece::evaluator::convert::IntoObject for #name #ty_generics #where_clause {
+            fn into_object(self) -> ::r_piece::evaluator::object::Object {
+                ::r_piece::evaluator::convert::hash_object(vec![
+                    #((#keys, ::r_piece::evaluator::convert::IntoObject::into_object(self.#fields)),)*
+                ])
+            }
+        }
+    }
+    .into()
+}
+
+#[proc_macro_derive(FromObject)]
+pub fn derive_from_object(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match named_fields(&input) {
+        Ok(fields) => fields,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let name = &input.ident;
+    let generics = with_bound(
+        input.generics.clone(),
+        quote!(::r_piece::evaluator::convert::FromObject),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let keys = fields.iter().map(|f| f.to_string());
+
+    quote! {
+        impl #impl_generics ::r_piece::evaluator::convert::FromObject for #name #ty_generics #where_clause {
+            fn from_object(
+                obj: &::r_piece::evaluator::object::Object,
+            ) -> ::std::result::Result<Self, ::r_piece::errors::eval_errs::EvalErr> {
+                let hash = ::r_piece::evaluator::convert::expect_hash(obj)?.borrow();
+                ::std::result::Result::Ok(Self {
+                    #(#fields: ::r_piece::evaluator::convert::hash_field(&hash, #keys)?,)*
+                })
+            }
+        }
+    }
+    .into()
+}
+
+fn named_fields(input: &DeriveInput) -> Result<Vec<Ident>, syn::Error> {
+    let unsupported = || {
+        syn::Error::new_spanned(
+            &input.ident,
+            "object conversions can only be derived for structs with named fields",
+        )
+    };
+    let Data::Struct(data) = &input.data else {
+        return Err(unsupported());
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(unsupported());
+    };
+    Ok(fields
+        .named
+        .iter()
+        .map(|f| f.ident.clone().unwrap())
+        .collect())
+}
+
+// Every type parameter has to be convertible itself
+fn with_bound(mut generics: Generics, bound: TokenStream2) -> Generics {
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
@@ -2,6 +2,28 @@ use std::fmt::Debug;
 
 use crate::{errors::coerce_errs::CoerceErr, lexer::token::TOKEN};
 
+// Byte offsets of a token or node in the source, `end` is exclusive. Nodes
+// built outside of the parser have an empty span at 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+    pub fn text<'a>(&self, src: &'a str) -> &'a str {
+        &src[self.start..self.end]
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum NodeType {
     Program,
@@ -16,12 +38,14 @@ pub enum NodeType {
     IfExpression,
     CallExpression,
     IndexExpression,
+    MemberExpression,
     //
     Identifier,
     String,
     Number,
     Bool,
     ArrayLiteral,
+    HashLiteral,
 }
 
 pub trait NodeTrait: Debug {
@@ -71,14 +95,16 @@ impl NodeTrait for Node {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
     Identifier(Identifier),
     String(String),
     Number(Number),
     Bool(Boolean),
     ArrayLiteral(ArrayLiteral),
+    HashLiteral(HashLiteral),
     Index(Box<IndexExpression>),
+    Member(Box<MemberExpression>),
     Prefix(Box<PrefixExpression>),
     Infix(Box<InfixExpression>),
     If(Box<IfExpression>),
@@ -116,12 +142,24 @@ impl Expression {
             anything => Err(CoerceErr::ToArrayLiteral(anything.token_literal())),
         }
     }
+    pub fn to_hash_literal(&self) -> Result<HashLiteral, CoerceErr> {
+        match self {
+            Expression::HashLiteral(x) => Ok(x.clone()),
+            anything => Err(CoerceErr::ToHashLiteral(anything.token_literal())),
+        }
+    }
     pub fn to_index(&self) -> Result<IndexExpression, CoerceErr> {
         match self {
             Expression::Index(x) => Ok(x.as_ref().clone()),
             anything => Err(CoerceErr::ToCall(anything.token_literal())),
         }
     }
+    pub fn to_member(&self) -> Result<MemberExpression, CoerceErr> {
+        match self {
+            Expression::Member(x) => Ok(x.as_ref().clone()),
+            anything => Err(CoerceErr::ToMember(anything.token_literal())),
+        }
+    }
     pub fn to_prefix(&self) -> Result<PrefixExpression, CoerceErr> {
         match self {
             Expression::Prefix(x) => Ok(x.as_ref().clone()),
@@ -161,7 +199,9 @@ impl NodeTrait for Expression {
             Expression::Number(_) => NodeType::Number,
             Expression::Bool(_) => NodeType::Bool,
             Expression::ArrayLiteral(_) => NodeType::ArrayLiteral,
+            Expression::HashLiteral(_) => NodeType::HashLiteral,
             Expression::Index(_) => NodeType::IndexExpression,
+            Expression::Member(_) => NodeType::MemberExpression,
             Expression::Prefix(_) => NodeType::PrefixExpression,
             Expression::Infix(_) => NodeType::InfixExpression,
             Expression::If(_) => NodeType::IfExpression,
@@ -176,7 +216,9 @@ impl NodeTrait for Expression {
             Expression::Number(x) => x.token_literal(),
             Expression::Bool(x) => x.token_literal(),
             Expression::ArrayLiteral(x) => x.token_literal(),
+            Expression::HashLiteral(x) => x.token_literal(),
             Expression::Index(x) => x.token_literal(),
+            Expression::Member(x) => x.token_literal(),
             Expression::Prefix(x) => x.token_literal(),
             Expression::Infix(x) => x.token_literal(),
             Expression::If(x) => x.token_literal(),
@@ -191,7 +233,9 @@ impl NodeTrait for Expression {
             Expression::Number(x) => x.to_str(),
             Expression::Bool(x) => x.to_str(),
             Expression::ArrayLiteral(x) => x.to_str(),
+            Expression::HashLiteral(x) => x.to_str(),
             Expression::Index(x) => x.to_str(),
+            Expression::Member(x) => x.to_str(),
             Expression::Prefix(x) => x.to_str(),
             Expression::Infix(x) => x.to_str(),
             Expression::If(x) => x.to_str(),
@@ -201,7 +245,9 @@ impl NodeTrait for Expression {
     }
 }
 
-#[derive(Debug, Clone)]
+// Statements parsed from source carry their span, it is ignored when
+// comparing statements
+#[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
     Let(LetStatement),
     Reassign(ReassignStatement),
@@ -248,6 +294,26 @@ impl Statement {
             anything => Err(CoerceErr::ToBlock(anything.token_literal())),
         }
     }
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Let(x) => x.span,
+            Statement::Reassign(x) => x.span,
+            Statement::Return(x) => x.span,
+            Statement::Expression(x) => x.span,
+            Statement::Program(_) => Span::default(),
+            Statement::Block(x) => x.span,
+        }
+    }
+    pub fn set_span(&mut self, span: Span) {
+        match self {
+            Statement::Let(x) => x.span = span,
+            Statement::Reassign(x) => x.span = span,
+            Statement::Return(x) => x.span = span,
+            Statement::Expression(x) => x.span = span,
+            Statement::Program(_) => {}
+            Statement::Block(x) => x.span = span,
+        }
+    }
 }
 impl NodeTrait for Statement {
     fn node_type(&self) -> NodeType {
@@ -282,7 +348,7 @@ impl NodeTrait for Statement {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Program {
     pub statements: Vec<Statement>,
 }
@@ -307,10 +373,20 @@ impl NodeTrait for Program {
 pub struct LetStatement {
     pub name: Identifier, // if name is IDENT(string) => Some(String) else None
     pub value: Expression,
+    pub span: Span,
 }
 impl LetStatement {
     pub fn new(name: Identifier, value: Expression) -> Self {
-        Self { name, value }
+        Self {
+            name,
+            value,
+            span: Span::default(),
+        }
+    }
+}
+impl PartialEq for LetStatement {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.value == other.value
     }
 }
 
@@ -335,10 +411,20 @@ impl NodeTrait for LetStatement {
 pub struct ReassignStatement {
     pub lhs: Expression, // if name is IDENT(string) => Some(String) else None
     pub value: Expression,
+    pub span: Span,
 }
 impl ReassignStatement {
     pub fn new(lhs: Expression, value: Expression) -> Self {
-        Self { lhs, value }
+        Self {
+            lhs,
+            value,
+            span: Span::default(),
+        }
+    }
+}
+impl PartialEq for ReassignStatement {
+    fn eq(&self, other: &Self) -> bool {
+        self.lhs == other.lhs && self.value == other.value
     }
 }
 
@@ -347,10 +433,10 @@ impl NodeTrait for ReassignStatement {
         NodeType::ReassignStatement
     }
     fn token_literal(&self) -> String {
-        return "let".to_string();
+        TOKEN::ASSIGN.literal()
     }
     fn to_str(&self) -> String {
-        let mut str = String::from("let ");
+        let mut str = String::new();
         str.push_str(&self.lhs.to_str());
         str.push_str(" = ");
         str.push_str(&self.value.to_str());
@@ -363,10 +449,19 @@ impl NodeTrait for ReassignStatement {
 #[derive(Debug, Clone)]
 pub struct ReturnStatement {
     pub expression: Option<Expression>,
+    pub span: Span,
 }
 impl ReturnStatement {
     pub fn new() -> Self {
-        Self { expression: None }
+        Self {
+            expression: None,
+            span: Span::default(),
+        }
+    }
+}
+impl PartialEq for ReturnStatement {
+    fn eq(&self, other: &Self) -> bool {
+        self.expression == other.expression
     }
 }
 
@@ -393,10 +488,21 @@ impl NodeTrait for ReturnStatement {
 pub struct ExpressionStatement {
     pub token: TOKEN,
     pub expression: Option<Expression>,
+    pub span: Span,
 }
 impl ExpressionStatement {
     pub fn new(token: TOKEN, expression: Option<Expression>) -> Self {
-        Self { token, expression }
+        Self {
+            token,
+            expression,
+            span: Span::default(),
+        }
+    }
+}
+// `token` is the first token of the source, `(a)` and `a` are equal
+impl PartialEq for ExpressionStatement {
+    fn eq(&self, other: &Self) -> bool {
+        self.expression == other.expression
     }
 }
 
@@ -422,10 +528,19 @@ impl NodeTrait for ExpressionStatement {
 #[derive(Debug, Clone)]
 pub struct BlockStatement {
     pub statements: Vec<Statement>,
+    pub span: Span, // from `{` to `}`
 }
 impl BlockStatement {
     pub fn new(statements: Vec<Statement>) -> Self {
-        Self { statements }
+        Self {
+            statements,
+            span: Span::default(),
+        }
+    }
+}
+impl PartialEq for BlockStatement {
+    fn eq(&self, other: &Self) -> bool {
+        self.statements == other.statements
     }
 }
 impl NodeTrait for BlockStatement {
@@ -447,8 +562,21 @@ impl NodeTrait for BlockStatement {
 
 // -------------- EXPRESSION TYPE ----------------------
 //PRIMITIVE
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Identifier(pub String);
+// A name and where it appears in the source, equal names are equal
+// identifiers wherever they are
+#[derive(Debug, Clone)]
+pub struct Identifier(pub String, pub Span);
+impl Identifier {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into(), Span::default())
+    }
+}
+impl PartialEq for Identifier {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+impl Eq for Identifier {}
 impl NodeTrait for Identifier {
     fn node_type(&self) -> NodeType {
         NodeType::Identifier
@@ -500,7 +628,7 @@ impl NodeTrait for Boolean {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ArrayLiteral {
     pub elements: Vec<Expression>,
 }
@@ -532,7 +660,34 @@ impl NodeTrait for ArrayLiteral {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashLiteral {
+    pub pairs: Vec<(Expression, Expression)>,
+}
+impl HashLiteral {
+    pub fn new(pairs: Vec<(Expression, Expression)>) -> Self {
+        Self { pairs }
+    }
+}
+
+impl NodeTrait for HashLiteral {
+    fn node_type(&self) -> NodeType {
+        NodeType::HashLiteral
+    }
+    fn token_literal(&self) -> String {
+        "HASH".to_string()
+    }
+    fn to_str(&self) -> String {
+        let pairs = self
+            .pairs
+            .iter()
+            .map(|(k, v)| format!("{}: {}", k.to_str(), v.to_str()))
+            .collect::<Vec<String>>();
+        format!("{{{}}}", pairs.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct FunctionLiteral {
     pub parameters: Vec<Identifier>,
     pub body: BlockStatement,
@@ -542,7 +697,7 @@ impl FunctionLiteral {
     pub fn new(parameters: Vec<Identifier>) -> Self {
         Self {
             parameters: parameters,
-            body: BlockStatement { statements: vec![] },
+            body: BlockStatement::new(vec![]),
             // name: None,
         }
     }
@@ -572,7 +727,7 @@ impl NodeTrait for FunctionLiteral {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PrefixExpression {
     pub token: TOKEN,
     pub right: Expression,
@@ -605,6 +760,7 @@ pub struct InfixExpression {
     pub operator: TOKEN,
     pub left: Expression,
     pub right: Expression,
+    pub span: Span,
 }
 impl InfixExpression {
     pub fn new(left: Expression, operator: TOKEN, right: Expression) -> Self {
@@ -612,9 +768,15 @@ impl InfixExpression {
             left: left,
             operator,
             right,
+            span: Span::default(),
         }
     }
 }
+impl PartialEq for InfixExpression {
+    fn eq(&self, other: &Self) -> bool {
+        self.operator == other.operator && self.left == other.left && self.right == other.right
+    }
+}
 
 impl NodeTrait for InfixExpression {
     fn node_type(&self) -> NodeType {
@@ -636,7 +798,7 @@ impl NodeTrait for InfixExpression {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct IfExpression {
     pub condition: Expression,
     pub consequence: BlockStatement,
@@ -646,7 +808,7 @@ impl IfExpression {
     pub fn new(condition: Expression) -> Self {
         IfExpression {
             condition,
-            consequence: BlockStatement { statements: vec![] },
+            consequence: BlockStatement::new(vec![]),
             alternative: None,
         }
     }
@@ -676,15 +838,22 @@ impl NodeTrait for IfExpression {
 pub struct CallExpression {
     pub function: Expression, // Identifier or FunctionLiteral
     pub arguments: Vec<Expression>,
+    pub span: Span,
 }
 impl CallExpression {
     pub fn new(function: Expression) -> Self {
         Self {
             function,
             arguments: vec![],
+            span: Span::default(),
         }
     }
 }
+impl PartialEq for CallExpression {
+    fn eq(&self, other: &Self) -> bool {
+        self.function == other.function && self.arguments == other.arguments
+    }
+}
 
 impl NodeTrait for CallExpression {
     fn node_type(&self) -> NodeType {
@@ -710,7 +879,7 @@ impl NodeTrait for CallExpression {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct IndexExpression {
     pub left: Expression,
     pub index: Expression,
@@ -736,6 +905,28 @@ impl NodeTrait for IndexExpression {
         return str;
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberExpression {
+    pub object: Expression,
+    pub property: Identifier,
+}
+impl MemberExpression {
+    pub fn new(object: Expression, property: Identifier) -> Self {
+        Self { object, property }
+    }
+}
+impl NodeTrait for MemberExpression {
+    fn node_type(&self) -> NodeType {
+        NodeType::MemberExpression
+    }
+    fn token_literal(&self) -> String {
+        TOKEN::DOT.literal()
+    }
+    fn to_str(&self) -> String {
+        format!("{}.{}", self.object.to_str(), self.property.0)
+    }
+}
 // -------------- EXPRESSION TYPE ----------------------
 
 pub fn stringnify_stmt(stmts: &Vec<Statement>) -> String {
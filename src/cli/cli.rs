@@ -0,0 +1,208 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Read},
+    path::Path,
+};
+
+use clap::{Parser, Subcommand};
+use r_piece::{
+    errors::eval_errs::EvalErr, evaluator::host::ProcessHost, formatter::formatter::FormatOptions,
+    lsp, Interpreter, InterpreterErr,
+};
+
+use crate::repl::repl::run_repl;
+
+use super::{
+    debug::run_debug,
+    fmt::run_fmt,
+    lint::{lint_config, run_lint, LintFormat},
+};
+
+// Exit codes, following sysexits.h
+pub const EXIT_USAGE: u8 = 64; // EX_USAGE
+pub const EXIT_PARSE: u8 = 65; // EX_DATAERR
+pub const EXIT_NO_INPUT: u8 = 66; // EX_NOINPUT
+pub const EXIT_RUNTIME: u8 = 70; // EX_SOFTWARE
+pub const EXIT_IO: u8 = 74; // EX_IOERR
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "r-piece",
+    version,
+    about = "The r-piece interpreter",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Evaluate EXPR and print its value, any positional arguments are passed to it
+    #[arg(short = 'e', long = "eval", value_name = "EXPR")]
+    pub expr: Option<String>,
+
+    /// A script to run, `-` reads it from stdin, followed by its arguments.
+    /// Without it a piped stdin is run as the program, otherwise the REPL starts.
+    #[arg(
+        value_name = "SCRIPT [ARGS]",
+        trailing_var_arg = true,
+        allow_hyphen_values = true
+    )]
+    pub rest: Vec<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run a script file
+    Run {
+        /// Path to the script, `-` reads it from stdin
+        script: String,
+        /// Arguments passed to the script
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Start the interactive REPL
+    Repl,
+    /// Format scripts in place, or stdin to stdout when no file is given
+    Fmt {
+        /// Scripts to format, `-` is stdin
+        files: Vec<String>,
+        /// Write nothing, list the files that are not formatted and exit with 1
+        #[arg(long)]
+        check: bool,
+        /// Spaces per indentation level
+        #[arg(long, default_value_t = FormatOptions::default().indent)]
+        indent: usize,
+        /// Line width to fit code in
+        #[arg(long, default_value_t = FormatOptions::default().width)]
+        width: usize,
+    },
+    /// Check scripts for likely mistakes without running them
+    Lint {
+        /// Scripts to check, `-` is stdin
+        files: Vec<String>,
+        /// Check only these rules, comma separated
+        #[arg(long, value_delimiter = ',', value_name = "RULES")]
+        enable: Vec<String>,
+        /// Skip these rules, comma separated
+        #[arg(long, value_delimiter = ',', value_name = "RULES")]
+        disable: Vec<String>,
+        /// How diagnostics are printed
+        #[arg(long, value_enum, default_value_t = LintFormat::Human)]
+        format: LintFormat,
+    },
+    /// Serve the Language Server Protocol over stdio
+    Lsp,
+    /// Run a script under the interactive debugger, `help` lists its commands
+    Debug {
+        /// Path to the script
+        script: String,
+        /// Run to this line instead of stopping at the first one, may be repeated
+        #[arg(short, long = "break", value_name = "LINE")]
+        breakpoints: Vec<usize>,
+        /// Arguments passed to the script
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+pub fn run(cli: Cli) -> u8 {
+    if let Some(command) = cli.command {
+        return match command {
+            Command::Run { script, args } => run_script(&script, args),
+            Command::Repl => run_repl(),
+            Command::Fmt {
+                files,
+                check,
+                indent,
+                width,
+            } => run_fmt(files, check, FormatOptions { indent, width }),
+            Command::Lint {
+                files,
+                enable,
+                disable,
+                format,
+            } => match lint_config(&enable, &disable) {
+                Ok(config) => run_lint(files, config, format),
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    EXIT_USAGE
+                }
+            },
+            Command::Lsp => lsp::server::run(&mut io::stdin().lock(), &mut io::stdout().lock()),
+            Command::Debug {
+                script,
+                breakpoints,
+                args,
+            } => run_debug(&script, breakpoints, args),
+        };
+    }
+
+    if let Some(expr) = cli.expr {
+        return exec(&mut process_interpreter(cli.rest), &expr, true);
+    }
+    let mut rest = cli.rest.into_iter();
+    match rest.next() {
+        Some(script) => run_script(&script, rest.collect()),
+        None if !io::stdin().is_terminal() => run_script("-", vec![]),
+        None => run_repl(),
+    }
+}
+
+fn run_script(script: &str, args: Vec<String>) -> u8 {
+    let src = match read_source(script) {
+        Ok(src) => src,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return EXIT_NO_INPUT;
+        }
+    };
+    exec(&mut process_interpreter(args), &src, false)
+}
+
+// Scripts run from the command line see the real arguments and environment
+pub fn process_interpreter(args: Vec<String>) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_host(ProcessHost::new(args));
+    interpreter
+}
+
+pub fn read_source(script: &str) -> Result<String, InterpreterErr> {
+    let read = match script {
+        "-" => {
+            let mut src = String::new();
+            io::stdin().read_to_string(&mut src).map(|_| src)
+        }
+        path => fs::read_to_string(Path::new(path)),
+    };
+    read.map_err(|err| InterpreterErr::Io(script.to_string(), err))
+}
+
+// Runs a whole program and reports how it ended as an exit code. `-e`
+// expressions print their value like the REPL does, scripts do not.
+pub fn exec(interpreter: &mut Interpreter, src: &str, print_value: bool) -> u8 {
+    match interpreter.eval_str(src) {
+        Ok(value) => {
+            let str = value.to_string();
+            if print_value && str != "Null" {
+                println!("{}", str);
+            }
+            0
+        }
+        Err(err) => {
+            if !matches!(err, InterpreterErr::Eval(EvalErr::Exit(_) | EvalErr::Quit)) {
+                eprintln!("error: {}", err);
+            }
+            exit_code(&err)
+        }
+    }
+}
+
+pub fn exit_code(err: &InterpreterErr) -> u8 {
+    match err {
+        InterpreterErr::Parse(_) | InterpreterErr::Eval(EvalErr::ParseErr(_)) => EXIT_PARSE,
+        InterpreterErr::Eval(EvalErr::Exit(code)) => *code,
+        InterpreterErr::Eval(EvalErr::Quit) => 0,
+        InterpreterErr::Eval(_) => EXIT_RUNTIME,
+        InterpreterErr::Io(..) => EXIT_NO_INPUT,
+    }
+}
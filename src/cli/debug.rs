@@ -0,0 +1,27 @@
+use r_piece::{debugger::debugger::Debugger, Interpreter};
+
+use super::cli::{exec, process_interpreter, read_source, EXIT_NO_INPUT, EXIT_PARSE};
+
+// Runs the script under the debugger, which reads its commands from stdin.
+// With breakpoints it runs to the first one, otherwise it stops right away.
+pub fn run_debug(script: &str, breakpoints: Vec<usize>, args: Vec<String>) -> u8 {
+    let src = match read_source(script) {
+        Ok(src) => src,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return EXIT_NO_INPUT;
+        }
+    };
+    // parse errors are reported before the session starts
+    if let Err(err) = Interpreter::parse(&src) {
+        eprintln!("error: {}", err);
+        return EXIT_PARSE;
+    }
+    let mut debugger = Debugger::new(&src).with_breakpoints(&breakpoints);
+    if !breakpoints.is_empty() {
+        debugger = debugger.run_to_breakpoint();
+    }
+    let mut interpreter = process_interpreter(args);
+    interpreter.set_hook(debugger);
+    exec(&mut interpreter, &src, false)
+}
@@ -0,0 +1,65 @@
+use std::{fs, io::Write};
+
+use r_piece::formatter::formatter::{format, FormatOptions};
+
+use super::cli::{read_source, EXIT_IO, EXIT_NO_INPUT, EXIT_PARSE};
+
+// Files that are not formatted, with `--check`
+pub const EXIT_UNFORMATTED: u8 = 1;
+
+// Formats the files in place, or stdin to stdout when there is none. With
+// `check` nothing is written, the unformatted files are listed instead.
+pub fn run_fmt(files: Vec<String>, check: bool, options: FormatOptions) -> u8 {
+    let files = match files.is_empty() {
+        true => vec!["-".to_string()],
+        false => files,
+    };
+    let mut code = 0;
+    for file in files.iter() {
+        let status = fmt_file(file, check, options);
+        code = code.max(status);
+    }
+    code
+}
+
+fn fmt_file(file: &str, check: bool, options: FormatOptions) -> u8 {
+    let src = match read_source(file) {
+        Ok(src) => src,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return EXIT_NO_INPUT;
+        }
+    };
+    let formatted = match format(&src, options) {
+        Ok(formatted) => formatted,
+        Err(err) => {
+            eprintln!("error: {}: {}", display_name(file), err);
+            return EXIT_PARSE;
+        }
+    };
+
+    if check {
+        if formatted == src {
+            return 0;
+        }
+        println!("{} is not formatted", display_name(file));
+        return EXIT_UNFORMATTED;
+    }
+    let written = match file {
+        "-" => std::io::stdout().write_all(formatted.as_bytes()),
+        path if formatted != src => fs::write(path, formatted),
+        _ => Ok(()),
+    };
+    if let Err(err) = written {
+        eprintln!("error: Cannot write {}: {}", display_name(file), err);
+        return EXIT_IO;
+    }
+    0
+}
+
+fn display_name(file: &str) -> &str {
+    match file {
+        "-" => "<stdin>",
+        path => path,
+    }
+}
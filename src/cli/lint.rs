@@ -0,0 +1,114 @@
+use clap::ValueEnum;
+use r_piece::{
+    evaluator::builtin::BuiltinRegistry,
+    lexer::lexer::Lexer,
+    linter::linter::{line_col, lint, Diagnostic, LintConfig, Rule},
+    parser::parser::Parser,
+};
+use serde_json::{json, Value};
+
+use super::cli::{read_source, EXIT_NO_INPUT, EXIT_PARSE};
+
+// Some file has diagnostics
+pub const EXIT_DIAGNOSTICS: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LintFormat {
+    Human,
+    Json,
+}
+
+// The default rules, with `enable` added and `disable` removed. Unknown rule
+// names are reported as errors.
+pub fn lint_config(enable: &[String], disable: &[String]) -> Result<LintConfig, String> {
+    let rule = |name: &String| Rule::from_name(name).ok_or(format!("Unknown lint rule {}", name));
+    let mut config = LintConfig::default();
+    if !enable.is_empty() {
+        config.rules.clear();
+    }
+    for name in enable.iter() {
+        config.rules.insert(rule(name)?);
+    }
+    for name in disable.iter() {
+        config.rules.remove(&rule(name)?);
+    }
+    Ok(config)
+}
+
+pub fn run_lint(files: Vec<String>, config: LintConfig, format: LintFormat) -> u8 {
+    let builtins = BuiltinRegistry::with_defaults();
+    let files = match files.is_empty() {
+        true => vec!["-".to_string()],
+        false => files,
+    };
+    let mut code = 0;
+    let mut json = vec![];
+    for file in files.iter() {
+        let name = match file.as_str() {
+            "-" => "<stdin>",
+            path => path,
+        };
+        let src = match read_source(file) {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                code = code.max(EXIT_NO_INPUT);
+                continue;
+            }
+        };
+        let mut parser = Parser::new(Lexer::new(&src));
+        let program = match parser.parse_program() {
+            Ok(program) => program,
+            Err(err) => {
+                let (line, col) = line_col(&src, parser.cur_token.span.start);
+                eprintln!("error: {}:{}:{}: {}", name, line, col, err);
+                code = code.max(EXIT_PARSE);
+                continue;
+            }
+        };
+        let diagnostics = lint(&program, &builtins, &config);
+        if !diagnostics.is_empty() {
+            code = code.max(EXIT_DIAGNOSTICS);
+        }
+        for diagnostic in diagnostics.iter() {
+            match format {
+                LintFormat::Human => println!("{}", human(name, &src, diagnostic)),
+                LintFormat::Json => json.push(to_json(name, &src, diagnostic)),
+            }
+        }
+    }
+    if format == LintFormat::Json {
+        println!("{}", Value::Array(json));
+    }
+    code
+}
+
+// `file:line:col: severity[rule]: message`
+pub fn human(file: &str, src: &str, diagnostic: &Diagnostic) -> String {
+    let (line, col) = line_col(src, diagnostic.span.start);
+    let rule = diagnostic.rule;
+    format!(
+        "{}:{}:{}: {}[{}]: {}",
+        file,
+        line,
+        col,
+        rule.severity().name(),
+        rule.name(),
+        diagnostic.message
+    )
+}
+
+pub fn to_json(file: &str, src: &str, diagnostic: &Diagnostic) -> Value {
+    let (line, column) = line_col(src, diagnostic.span.start);
+    let (end_line, end_column) = line_col(src, diagnostic.span.end);
+    json!({
+        "file": file,
+        "line": line,
+        "column": column,
+        "end_line": end_line,
+        "end_column": end_column,
+        "severity": diagnostic.rule.severity().name(),
+        "rule": diagnostic.rule.name(),
+        "message": diagnostic.message,
+    })
+}
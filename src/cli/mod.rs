@@ -0,0 +1,5 @@
+pub mod cli;
+pub mod debug;
+pub mod fmt;
+pub mod lint;
+mod test;
@@ -0,0 +1,149 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use clap::Parser;
+    use r_piece::{
+        debugger::debugger::Debugger, evaluator::io::BufferIo, formatter::formatter::FormatOptions,
+        Interpreter,
+    };
+
+    use crate::cli::{
+        cli::{exec, Cli, Command, EXIT_PARSE, EXIT_RUNTIME},
+        fmt::{run_fmt, EXIT_UNFORMATTED},
+        lint::{lint_config, run_lint, LintFormat, EXIT_DIAGNOSTICS},
+    };
+
+    #[test]
+    fn test_args() {
+        let cli = Cli::parse_from(["r-piece", "run", "a.rp", "x", "-v"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Run { script, args }) if script == "a.rp" && args == ["x", "-v"]
+        ));
+
+        let cli = Cli::parse_from(["r-piece", "a.rp", "--flag"]);
+        assert!(cli.command.is_none());
+        assert_eq!(cli.rest, ["a.rp", "--flag"]);
+
+        let cli = Cli::parse_from(["r-piece", "lsp"]);
+        assert!(matches!(cli.command, Some(Command::Lsp)));
+
+        let cli = Cli::parse_from(["r-piece", "-e", "1 + 1"]);
+        assert_eq!(cli.expr.as_deref(), Some("1 + 1"));
+        assert!(cli.rest.is_empty());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let tests = vec![
+            ("let x = 1; print(x)", 0),
+            ("#!/usr/bin/env r-piece\nprint(1)", 0),
+            ("let = 1", EXIT_PARSE),
+            ("let x = 99999999999999999999;", EXIT_PARSE),
+            ("print(-9223372036854775809)", EXIT_PARSE),
+            ("1 / 0", EXIT_RUNTIME),
+            ("undefined_name", EXIT_RUNTIME),
+            ("exit(7); print(1)", 7),
+            ("let f = fn() { exit(0) }; f(); 1 / 0", 0),
+        ];
+        for (src, expected) in tests.into_iter() {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_io(BufferIo::new());
+            assert_eq!(exec(&mut interpreter, src, false), expected, "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_fmt() {
+        let cli = Cli::parse_from(["r-piece", "fmt", "--check", "--width", "80", "a.rp"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Fmt { files, check: true, indent: 4, width: 80 }) if files == ["a.rp"]
+        ));
+
+        let path = std::env::temp_dir().join(format!("r-piece-fmt-{}.rp", std::process::id()));
+        let file = path.to_string_lossy().to_string();
+        fs::write(&path, "let x=1").unwrap();
+        let options = FormatOptions::default();
+        assert_eq!(run_fmt(vec![file.clone()], true, options), EXIT_UNFORMATTED);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "let x=1");
+        assert_eq!(run_fmt(vec![file.clone()], false, options), 0);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "let x = 1;\n");
+        assert_eq!(run_fmt(vec![file.clone()], true, options), 0);
+        fs::write(&path, "let = 1").unwrap();
+        assert_eq!(run_fmt(vec![file], true, options), EXIT_PARSE);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_lint() {
+        let cli = Cli::parse_from([
+            "r-piece",
+            "lint",
+            "--enable",
+            "unused-let,shadowed-name",
+            "a.rp",
+        ]);
+        let Some(Command::Lint {
+            files,
+            enable,
+            disable,
+            format,
+        }) = cli.command
+        else {
+            panic!("not a lint command");
+        };
+        assert_eq!(files, ["a.rp"]);
+        assert_eq!(format, LintFormat::Human);
+        let config = lint_config(&enable, &disable).unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(
+            lint_config(&[], &["unused-let".into()])
+                .unwrap()
+                .rules
+                .len(),
+            6
+        );
+        assert!(lint_config(&["nope".into()], &[]).is_err());
+
+        let path = std::env::temp_dir().join(format!("r-piece-lint-{}.rp", std::process::id()));
+        let file = path.to_string_lossy().to_string();
+        fs::write(&path, "let x = 1;").unwrap();
+        assert_eq!(
+            run_lint(vec![file.clone()], config.clone(), LintFormat::Json),
+            EXIT_DIAGNOSTICS
+        );
+        fs::write(&path, "let x = 1; print(x)").unwrap();
+        assert_eq!(
+            run_lint(vec![file.clone()], config.clone(), LintFormat::Human),
+            0
+        );
+        fs::write(&path, "let = 1").unwrap();
+        assert_eq!(run_lint(vec![file], config, LintFormat::Human), EXIT_PARSE);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_debug() {
+        let cli = Cli::parse_from(["r-piece", "debug", "-b", "3", "--break", "7", "a.rp", "x"]);
+        let Some(Command::Debug {
+            script,
+            breakpoints,
+            args,
+        }) = cli.command
+        else {
+            panic!("not a debug command");
+        };
+        assert_eq!(script, "a.rp");
+        assert_eq!(breakpoints, [3, 7]);
+        assert_eq!(args, ["x"]);
+
+        // quitting the debugger is not a runtime error
+        let src = "print(1)";
+        let mut interpreter = Interpreter::new();
+        interpreter.set_io(BufferIo::with_input("q"));
+        interpreter.set_hook(Debugger::new(src));
+        assert_eq!(exec(&mut interpreter, src, false), 0);
+    }
+}
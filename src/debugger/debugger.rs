@@ -0,0 +1,375 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    ast::ast::{Node, Statement},
+    errors::eval_errs::EvalErr,
+    evaluator::{
+        environment::Env, eval::eval, hook::EvalHook, io::Stream, object::Object, runtime::Runtime,
+    },
+    lexer::lexer::Lexer,
+    linter::linter::line_col,
+    parser::parser::Parser,
+    utils::shared::Rc,
+};
+
+const HELP: &str = "\
+c, continue       run to the next breakpoint
+s, step           run to the next line, into calls
+n, next           run to the next line of this call
+o, out            run until this call returns
+b, break LINE     stop at LINE, without LINE list the breakpoints
+d, delete LINE    remove the breakpoint at LINE
+bt, backtrace     show the call stack
+f, frame N        inspect the Nth frame of the call stack
+l, locals         show the bindings of the frame, scope by scope
+p, print EXPR     evaluate EXPR in the frame
+w, watch EXPR     evaluate EXPR at every stop
+u, unwatch N      remove the Nth watch expression
+list              show the source around the line
+q, quit           stop the program
+";
+
+// Where execution goes until the next stop
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Continue,
+    Step,
+    Next { depth: usize, line: usize },
+    Out { depth: usize },
+}
+
+// A call being evaluated. Builtins calling back into the script get a frame
+// without a line or scope of their own.
+#[derive(Debug, Clone)]
+struct Frame {
+    name: String,
+    line: Option<usize>,
+    env: Option<Env>,
+}
+
+// An interactive debugger, installed with `Interpreter::set_hook`. It stops
+// before the first statement, then takes commands from the script's input
+// and answers on its stderr.
+#[derive(Debug)]
+pub struct Debugger {
+    source: String,
+    breakpoints: BTreeSet<usize>,
+    watches: Vec<String>,
+    mode: Mode,
+    frames: Vec<Frame>,   // outermost first
+    selected: usize,      // frame inspected by locals and print, 0 is the innermost
+    last: (usize, usize), // depth and line of the previous statement
+}
+
+impl Debugger {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            breakpoints: BTreeSet::new(),
+            watches: vec![],
+            mode: Mode::Step,
+            frames: vec![],
+            selected: 0,
+            last: (usize::MAX, 0),
+        }
+    }
+
+    // Breakpoints on 1-based lines
+    pub fn with_breakpoints(mut self, lines: &[usize]) -> Self {
+        self.breakpoints.extend(lines);
+        self
+    }
+
+    // Runs to the first breakpoint instead of stopping at the first statement
+    pub fn run_to_breakpoint(mut self) -> Self {
+        self.mode = Mode::Continue;
+        self
+    }
+
+    fn track(&mut self, depth: usize, line: usize, env: &Env, rt: &Runtime) {
+        let calls = rt.call_stack();
+        self.frames.truncate(depth + 1);
+        while self.frames.len() <= depth {
+            let name = match self.frames.len() {
+                0 => "<main>".to_string(),
+                idx => calls.get(idx - 1).cloned().unwrap_or_default(),
+            };
+            self.frames.push(Frame {
+                name,
+                line: None,
+                env: None,
+            });
+        }
+        let frame = &mut self.frames[depth];
+        frame.line = Some(line);
+        frame.env = Some(Rc::clone(env));
+    }
+
+    fn should_stop(&self, depth: usize, line: usize) -> bool {
+        if (depth, line) == self.last {
+            return false;
+        }
+        let stepped = match self.mode {
+            Mode::Continue => false,
+            Mode::Step => true,
+            Mode::Next { depth: d, line: l } => depth < d || (depth == d && line != l),
+            Mode::Out { depth: d } => depth < d,
+        };
+        stepped || self.breakpoints.contains(&line)
+    }
+
+    fn pause(&mut self, rt: &mut Runtime) -> Result<(), EvalErr> {
+        self.selected = 0;
+        self.show_location(rt)?;
+        for idx in 0..self.watches.len() {
+            let value = self.evaluate(&self.watches[idx].clone(), rt);
+            write(
+                rt,
+                &format!("watch {}: {} = {}\n", idx + 1, self.watches[idx], value),
+            )?;
+        }
+        loop {
+            write(rt, "(debug) ")?;
+            let line = rt
+                .io
+                .read_line()
+                .map_err(|err| EvalErr::Io(err.to_string()))?;
+            // without input the program runs to its end
+            let Some(line) = line else {
+                write(rt, "\n")?;
+                self.breakpoints.clear();
+                self.mode = Mode::Continue;
+                return Ok(());
+            };
+            let line = line.trim();
+            let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+            let arg = arg.trim();
+            let (depth, current) = self.last;
+            match command {
+                "" => {}
+                "c" | "continue" => self.mode = Mode::Continue,
+                "s" | "step" => self.mode = Mode::Step,
+                "n" | "next" => {
+                    self.mode = Mode::Next {
+                        depth,
+                        line: current,
+                    }
+                }
+                "o" | "out" => self.mode = Mode::Out { depth },
+                "q" | "quit" => return Err(EvalErr::Quit),
+                command => {
+                    let output = self.command(command, arg, rt);
+                    write(rt, &output)?;
+                    continue;
+                }
+            }
+            return Ok(());
+        }
+    }
+
+    // Commands that inspect the program, their output
+    fn command(&mut self, command: &str, arg: &str, rt: &mut Runtime) -> String {
+        match command {
+            "b" | "break" if arg.is_empty() => match self.breakpoints.is_empty() {
+                true => "no breakpoints\n".into(),
+                false => self
+                    .breakpoints
+                    .iter()
+                    .map(|x| format!("breakpoint at line {}\n", x))
+                    .collect(),
+            },
+            "b" | "break" => match self.parse_line(arg) {
+                Ok(line) => {
+                    self.breakpoints.insert(line);
+                    format!("breakpoint at line {}\n", line)
+                }
+                Err(err) => err,
+            },
+            "d" | "delete" => match self.parse_line(arg) {
+                Ok(line) if self.breakpoints.remove(&line) => {
+                    format!("deleted breakpoint at line {}\n", line)
+                }
+                Ok(line) => format!("no breakpoint at line {}\n", line),
+                Err(err) => err,
+            },
+            "bt" | "backtrace" => self
+                .frames
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(idx, frame)| {
+                    let marker = if idx == self.selected { "*" } else { " " };
+                    match frame.line {
+                        Some(line) => {
+                            format!("{}#{} {} at line {}\n", marker, idx, frame.name, line)
+                        }
+                        None => format!("{}#{} {}\n", marker, idx, frame.name),
+                    }
+                })
+                .collect(),
+            "f" | "frame" => match arg.parse::<usize>() {
+                Ok(idx) if idx < self.frames.len() => {
+                    self.selected = idx;
+                    let frame = &self.frames[self.frames.len() - 1 - idx];
+                    match frame.line {
+                        Some(line) => format!("#{} {} at line {}\n", idx, frame.name, line),
+                        None => format!("#{} {}\n", idx, frame.name),
+                    }
+                }
+                _ => format!("no frame {}\n", arg),
+            },
+            "l" | "locals" => match self.frame_env() {
+                Some(env) => locals(&env),
+                None => "no scope in this frame\n".into(),
+            },
+            "p" | "print" => format!("{}\n", self.evaluate(arg, rt)),
+            "w" | "watch" if !arg.is_empty() => {
+                self.watches.push(arg.to_string());
+                let value = self.evaluate(arg, rt);
+                format!("watch {}: {} = {}\n", self.watches.len(), arg, value)
+            }
+            "u" | "unwatch" => match arg.parse::<usize>() {
+                Ok(idx) if idx >= 1 && idx <= self.watches.len() => {
+                    format!("removed watch {}: {}\n", idx, self.watches.remove(idx - 1))
+                }
+                _ => format!("no watch {}\n", arg),
+            },
+            "list" => self.listing(),
+            "h" | "help" => HELP.into(),
+            command => format!("unknown command {}, try help\n", command),
+        }
+    }
+
+    fn parse_line(&self, arg: &str) -> Result<usize, String> {
+        let lines = self.source.lines().count();
+        match arg.parse::<usize>() {
+            Ok(line) if line >= 1 && line <= lines => Ok(line),
+            _ => Err(format!("no line {}, the script has {} lines\n", arg, lines)),
+        }
+    }
+
+    fn frame_env(&self) -> Option<Env> {
+        let idx = self.frames.len().checked_sub(1 + self.selected)?;
+        self.frames[idx].env.clone()
+    }
+
+    // The value of `src` in the selected frame, or why there is none
+    fn evaluate(&self, src: &str, rt: &mut Runtime) -> String {
+        let Some(env) = self.frame_env() else {
+            return "no scope in this frame".into();
+        };
+        let program = match Parser::new(Lexer::new(src)).parse_program() {
+            Ok(program) => program,
+            Err(err) => return format!("error: {}", err),
+        };
+        match eval(Node::Statement(Statement::Program(program)), env, rt) {
+            Ok(Object::Return(value)) => describe(&value),
+            Ok(value) => describe(&value),
+            Err(err) => format!("error: {}", err),
+        }
+    }
+
+    fn show_location(&self, rt: &mut Runtime) -> Result<(), EvalErr> {
+        let (_, line) = self.last;
+        let name = self.frames.last().map_or("<main>", |x| x.name.as_str());
+        let text = self.source.lines().nth(line - 1).unwrap_or_default();
+        write(
+            rt,
+            &format!(
+                "stopped at line {} in {}\n{:>5} | {}\n",
+                line, name, line, text
+            ),
+        )
+    }
+
+    // The lines around the current one
+    fn listing(&self) -> String {
+        let (_, current) = self.last;
+        let first = current.saturating_sub(3).max(1);
+        self.source
+            .lines()
+            .enumerate()
+            .skip(first - 1)
+            .take(7)
+            .map(|(idx, text)| {
+                let line = idx + 1;
+                let marker = match (line == current, self.breakpoints.contains(&line)) {
+                    (true, _) => ">",
+                    (false, true) => "*",
+                    _ => " ",
+                };
+                format!("{}{:>4} | {}\n", marker, line, text)
+            })
+            .collect()
+    }
+}
+
+impl EvalHook for Debugger {
+    fn statement(&mut self, stmt: &Statement, env: &Env, rt: &mut Runtime) -> Result<(), EvalErr> {
+        let (line, _) = line_col(&self.source, stmt.span().start);
+        let depth = rt.depth();
+        // a new call at the same depth and line, e.g. the next callback of a
+        // map, is a new place to stop
+        let same_call = self
+            .frames
+            .get(depth)
+            .and_then(|x| x.env.as_ref())
+            .is_some_and(|x| Rc::ptr_eq(x, env));
+        if !same_call {
+            self.last = (usize::MAX, 0);
+        }
+        self.track(depth, line, env, rt);
+        let stop = self.should_stop(depth, line);
+        self.last = (depth, line);
+        if stop {
+            self.pause(rt)?;
+        }
+        Ok(())
+    }
+}
+
+fn write(rt: &mut Runtime, text: &str) -> Result<(), EvalErr> {
+    rt.io
+        .write(Stream::Stderr, text)
+        .map_err(|err| EvalErr::Io(err.to_string()))
+}
+
+// Functions show their parameters only, the body can be long
+fn describe(value: &Object) -> String {
+    match value {
+        Object::Function(function) => {
+            let params = function
+                .params
+                .iter()
+                .map(|x| x.0.as_str())
+                .collect::<Vec<&str>>();
+            format!("fn({})", params.join(", "))
+        }
+        Object::String(str) => format!("{:?}", str),
+        value => value.to_string(),
+    }
+}
+
+// Bindings of each scope of the chain, innermost first
+fn locals(env: &Env) -> String {
+    let mut output = String::new();
+    let mut scope = Some(Rc::clone(env));
+    let mut level = 0;
+    while let Some(env) = scope {
+        let env = env.borrow();
+        let title = match (level, env.outer.is_none()) {
+            (_, true) => "globals".to_string(),
+            (0, false) => "locals".to_string(),
+            (_, false) => format!("closure scope {}", level),
+        };
+        output.push_str(&format!("{}:\n", title));
+        let mut names = env.store.keys().collect::<Vec<&String>>();
+        names.sort();
+        for name in names {
+            output.push_str(&format!("  {} = {}\n", name, describe(&env.store[name])));
+        }
+        scope = env.outer.clone();
+        level += 1;
+    }
+    output
+}
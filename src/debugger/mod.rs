@@ -0,0 +1,2 @@
+pub mod debugger;
+mod test;
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        debugger::debugger::Debugger, errors::eval_errs::EvalErr, evaluator::io::BufferIo,
+        Interpreter, InterpreterErr,
+    };
+
+    const SRC: &str = "\
+let add = fn(a, b) {
+    let sum = a + b;
+    sum
+};
+let twice = fn(x) { add(x, x) };
+let n = twice(3);
+let m = map([1, 2], fn(x) { x * 10 });
+print(n);";
+
+    // Runs SRC under a debugger fed with `commands`, returns the debugger's
+    // output and the script's
+    fn debug(debugger: Debugger, commands: &str) -> (Result<(), InterpreterErr>, String, String) {
+        let io = BufferIo::with_input(commands);
+        let mut interpreter = Interpreter::new();
+        interpreter.set_io(io.clone());
+        interpreter.set_hook(debugger);
+        let result = interpreter.eval_str(SRC).map(|_| ());
+        (result, io.stderr(), io.stdout())
+    }
+
+    // Where the debugger stopped, in order
+    fn stops(output: &str) -> Vec<String> {
+        output
+            .lines()
+            .filter_map(|x| x.split("stopped at ").nth(1))
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_stepping() {
+        let (result, output, stdout) = debug(Debugger::new(SRC), "n\nn\ns\ns\ns\nn\ns\no\nc");
+        assert!(result.is_ok());
+        assert_eq!(stdout, "6 \n");
+        assert_eq!(
+            stops(&output),
+            vec![
+                "line 1 in <main>",
+                "line 5 in <main>",
+                "line 6 in <main>",
+                "line 5 in twice", // step into
+                "line 2 in add",
+                "line 3 in add",
+                "line 7 in <main>", // next at the end of a call returns from it
+                "line 7 in <map callback>", // step into a callback through map
+                "line 8 in <main>", // out
+            ]
+        );
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let debugger = Debugger::new(SRC)
+            .with_breakpoints(&[3, 7])
+            .run_to_breakpoint();
+        let (result, output, _) = debug(debugger, "b\nd 3\nb 99\nc\nc\nc\nc");
+        assert!(result.is_ok());
+        // the breakpoint on line 7 also stops in each call of the callback
+        assert_eq!(
+            stops(&output),
+            vec![
+                "line 3 in add",
+                "line 7 in <main>",
+                "line 7 in <map callback>",
+                "line 7 in <map callback>",
+            ]
+        );
+        assert!(output.contains("breakpoint at line 3\nbreakpoint at line 7\n"));
+        assert!(output.contains("deleted breakpoint at line 3\n"));
+        assert!(output.contains("no line 99, the script has 8 lines\n"));
+
+        // the end of input lets the program finish
+        let debugger = Debugger::new(SRC).with_breakpoints(&[2]);
+        let (result, output, stdout) = debug(debugger, "");
+        assert!(result.is_ok());
+        assert_eq!(stops(&output), vec!["line 1 in <main>"]);
+        assert_eq!(stdout, "6 \n");
+    }
+
+    #[test]
+    fn test_inspect() {
+        let debugger = Debugger::new(SRC)
+            .with_breakpoints(&[3])
+            .run_to_breakpoint();
+        let commands = "bt\nlocals\np sum * 2\np nope\nw a + b\nf 1\nlocals\np x\nf 5\nc";
+        let (result, output, _) = debug(debugger, commands);
+        assert!(result.is_ok());
+        let expected = "\
+stopped at line 3 in add
+    3 |     sum
+(debug) *#0 add at line 3
+ #1 twice at line 5
+ #2 <main> at line 6
+(debug) locals:
+  a = 3
+  b = 3
+  sum = 6
+globals:
+  add = fn(a, b)
+  twice = fn(x)
+(debug) 12
+(debug) error: Identifier nope not found
+(debug) watch 1: a + b = 6
+(debug) #1 twice at line 5
+(debug) locals:
+  x = 3
+globals:
+  add = fn(a, b)
+  twice = fn(x)
+(debug) 3
+(debug) no frame 5
+(debug) ";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_builtin_frames() {
+        let debugger = Debugger::new(SRC)
+            .with_breakpoints(&[7])
+            .run_to_breakpoint();
+        let (_, output, _) = debug(debugger, "s\nbt\nf 1\nlocals\nc");
+        assert!(output.contains("*#0 <map callback> at line 7\n #1 map\n #2 <main> at line 7\n"));
+        assert!(output.contains("(debug) #1 map\n(debug) no scope in this frame\n"));
+    }
+
+    #[test]
+    fn test_quit() {
+        let (result, output, stdout) = debug(Debugger::new(SRC), "help\nq");
+        assert!(matches!(result, Err(InterpreterErr::Eval(EvalErr::Quit))));
+        assert!(output.contains("bt, backtrace"));
+        assert_eq!(stdout, "");
+    }
+}
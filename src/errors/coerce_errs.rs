@@ -22,12 +22,16 @@ pub enum CoerceErr {
     ToBool(String),
     #[error("Literal Erro got  Cant be coerce to Array {0}")]
     ToArrayLiteral(String),
+    #[error("Error: {0} Cant be coerce to Hash ")]
+    ToHashLiteral(String),
     #[error("Error: {0} Cant be coerce to Prefix ")]
     ToPrefix(String),
     #[error("Error: {0} Cant be coerce to Infix ")]
     ToInfix(String),
     #[error("Error: {0} Cant be coerce to Call ")]
     ToCall(String),
+    #[error("Error: {0} Cant be coerce to Member ")]
+    ToMember(String),
     #[error("Error: {0} Cant be coerce to If ")]
     ToIf(String),
     #[error("Error: {0} Cant be coerce to Function ")]
@@ -1,4 +1,4 @@
-use std::mem::discriminant;
+use std::{mem::discriminant, time::Duration};
 
 use thiserror::Error;
 
@@ -42,10 +42,16 @@ pub enum EvalErr {
     #[error("Variable {0} is already initialized")]
     AlreadyInitialized(String),
     //
-    #[error("Indexing is only supported for array, got {0}")]
+    #[error("Indexing is only supported for array and hash, got {0}")]
     IndexArray(String),
+    #[error("Hash keys must be String, got {0}")]
+    HashKey(String),
     #[error("Index out of bounds, index {0} is out of bounds for array of size {1}")]
     IndexOutOfBounds(i64, usize),
+    #[error("{0} has no member {1}")]
+    NoMember(String, String),
+    #[error("Method {1} of {0} must be called")]
+    UncalledMethod(String, String),
     //
     #[error("Object mismatch, expected {0}, got {1}")]
     CoerceObject(String, String),
@@ -54,6 +60,29 @@ pub enum EvalErr {
     ArgsCount(usize, usize),
     #[error("Builtin({0}) expects {1} arguments, got {2}")]
     BuiltinArgsType(String, String, String),
+    #[error("I/O error: {0}")]
+    Io(String),
+    #[error("Capability {0} is denied by the host")]
+    Denied(String),
+    //runtime
+    #[error("Stack overflow: call depth exceeded {0}, innermost calls: {}", .1.join(" <- "))]
+    StackOverflow(usize, Vec<String>),
+    #[error("Step limit of {0} evaluation steps exceeded")]
+    StepLimit(u64),
+    #[error("Evaluation timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("Memory limit of {0} bytes exceeded")]
+    MemoryLimit(usize),
+    #[error("Evaluation interrupted")]
+    Interrupted,
+    // Raised by `exit(code)`, unwinds the whole program
+    #[error("Script exited with code {0}")]
+    Exit(u8),
+    #[error("Exit code must be between 0 and 255, got {0}")]
+    ExitCode(i64),
+    // Raised when the user quits the debugger, not a failure of the script
+    #[error("Quit from the debugger")]
+    Quit,
 }
 
 impl EvalErr {
@@ -0,0 +1,22 @@
+use std::{io, mem::discriminant};
+
+use thiserror::Error;
+
+use super::{eval_errs::EvalErr, parser_errs::ParseErr};
+
+// Every error the public Interpreter API can return
+#[derive(Debug, Error)]
+pub enum InterpreterErr {
+    #[error("{0}")]
+    Parse(#[from] ParseErr),
+    #[error("{0}")]
+    Eval(#[from] EvalErr),
+    #[error("Cannot read {0}: {1}")]
+    Io(String, #[source] io::Error),
+}
+
+impl InterpreterErr {
+    pub fn match_err(&self, err: InterpreterErr) -> bool {
+        return discriminant(self) == discriminant(&err);
+    }
+}
@@ -0,0 +1,39 @@
+use std::io;
+
+use thiserror::Error;
+
+// Failures of a language server request, sent back as JSON-RPC errors
+#[derive(Debug, Error)]
+pub enum LspErr {
+    #[error("Parse error: {0}")]
+    Parse(String),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Method not found: {0}")]
+    MethodNotFound(String),
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+    #[error("Unknown document {0}")]
+    UnknownDocument(String),
+    #[error("Cannot rename: {0}")]
+    Rename(String),
+    #[error("Server is not initialized")]
+    NotInitialized,
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+impl LspErr {
+    // Error codes from the JSON-RPC and LSP specifications
+    pub fn code(&self) -> i64 {
+        match self {
+            LspErr::Parse(_) => -32700,
+            LspErr::InvalidRequest(_) => -32600,
+            LspErr::MethodNotFound(_) => -32601,
+            LspErr::InvalidParams(_) | LspErr::UnknownDocument(_) => -32602,
+            LspErr::Io(_) => -32603,
+            LspErr::NotInitialized => -32002,
+            LspErr::Rename(_) => -32803, // RequestFailed
+        }
+    }
+}
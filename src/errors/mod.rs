@@ -1,3 +1,5 @@
 pub mod coerce_errs;
 pub mod eval_errs;
+pub mod interpreter_errs;
+pub mod lsp_errs;
 pub mod parser_errs;
@@ -2,7 +2,7 @@ use std::mem::discriminant;
 
 use thiserror::Error;
 
-use crate::lexer::token::TOKEN;
+use crate::{ast::ast::Span, lexer::token::TOKEN};
 
 use super::coerce_errs::CoerceErr;
 
@@ -14,6 +14,8 @@ pub enum ParseErr {
     //
     #[error("Array Error: Expected: {0} | got {1:?}")]
     ARRAY(String, TOKEN),
+    #[error("Hash Error: Expected: {0} | got {1:?}")]
+    HASH(String, TOKEN),
     #[error("Let Error: Expected: {0} | got {1:?}")]
     LET(String, TOKEN),
     #[error("Infix Error: Expected: {0} | got {1:?}")]
@@ -22,6 +24,8 @@ pub enum ParseErr {
     CALL(String, TOKEN),
     #[error("ArrayIndex Error: Expected: {0} | got {1:?}")]
     INDEX(String, TOKEN),
+    #[error("Member Error: Expected: {0} | got {1:?}")]
+    MEMBER(String, TOKEN),
     #[error("Group Error: Expected: {0} | got {1:?}")]
     GROUP(String, TOKEN),
     #[error("If Error: Expected: {0} | got {1:?}")]
@@ -32,7 +36,9 @@ pub enum ParseErr {
     FN(String, TOKEN),
     #[error("Block Error: Expected: {0} | got {1:?}")]
     BLOCK(String, TOKEN),
-    #[error("None")]
+    #[error("Number {0} does not fit in 64 bits")]
+    NumberOverflow(String, Span),
+    #[error("Expected an expression")]
     None,
 }
 
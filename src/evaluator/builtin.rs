@@ -1,30 +1,87 @@
-use crate::errors::eval_errs::EvalErr;
+use crate::{
+    errors::eval_errs::EvalErr,
+    utils::shared::{MaybeSend, MaybeSync, Rc},
+};
 
-use super::object::Object;
+use super::{
+    builtin_iter,
+    convert::{FromObject, IntoObject},
+    eval::apply_function,
+    io::Stream,
+    object::Object,
+    runtime::Runtime,
+};
 
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    mem::size_of,
+};
+
+pub type BuiltinFn = fn(&mut Context, &[Object]) -> Result<Object, EvalErr>;
+// Registered builtins may be closures capturing host state
+#[cfg(not(feature = "sync"))]
+pub type HostFn = dyn Fn(&mut Context, &[Object]) -> Result<Object, EvalErr>;
+#[cfg(feature = "sync")]
+pub type HostFn = dyn Fn(&mut Context, &[Object]) -> Result<Object, EvalErr> + Send + Sync;
+
+// The interpreter as seen by a running builtin
+pub struct Context<'r> {
+    name: &'r str,
+    rt: &'r mut Runtime,
+}
+
+impl<'r> Context<'r> {
+    pub fn new(name: &'r str, rt: &'r mut Runtime) -> Self {
+        Self { name, rt }
+    }
+
+    // Name the builtin was called by
+    pub fn name(&self) -> &str {
+        self.name
+    }
 
-static GET_LEN: for<'a> fn(&Vec<Object<'a>>) -> Result<Object<'a>, EvalErr> = |arg| {
-    if arg.len() != 1 {
-        return Err(EvalErr::ArgsCount(1, arg.len()));
+    pub fn runtime(&mut self) -> &mut Runtime {
+        self.rt
     }
 
+    pub fn allocate(&mut self, bytes: usize) -> Result<(), EvalErr> {
+        self.rt.allocate(bytes)
+    }
+
+    pub fn write(&mut self, stream: Stream, text: &str) -> Result<(), EvalErr> {
+        self.rt
+            .io
+            .write(stream, text)
+            .map_err(|err| EvalErr::Io(err.to_string()))
+    }
+
+    // Invoke a function value passed to the builtin, with the same depth
+    // and step budgets as a call from the script
+    pub fn call(&mut self, function: &Object, args: Vec<Object>) -> Result<Object, EvalErr> {
+        let name = match function {
+            Object::Builtin(s) => s.clone(),
+            _ => format!("<{} callback>", self.name),
+        };
+        apply_function(name, function.clone(), args, self.rt)
+    }
+}
+
+static GET_LEN: BuiltinFn = |_, arg| {
     return match arg[0].clone() {
         Object::String(s) => Ok(Object::Number(s.len() as i64)),
         Object::Array(a) => Ok(Object::Number(a.as_ref().borrow().len() as i64)),
+        Object::Hash(h) => Ok(Object::Number(h.borrow().len() as i64)),
         _ => Err(EvalErr::BuiltinArgsType(
             "len".to_string(),
-            "String|Array".to_string(),
+            "String|Array|Hash".to_string(),
             arg[0].get_type(),
         )),
     };
 };
 
-static POP: for<'a> fn(&Vec<Object<'a>>) -> Result<Object<'a>, EvalErr> = |arg| {
-    if arg.len() != 1 {
-        return Err(EvalErr::ArgsCount(1, arg.len()));
-    }
+static POP: BuiltinFn = |_, arg| {
     let arr = arg[0].to_arr(EvalErr::BuiltinArgsType(
         "pop".to_string(),
         "Array".to_string(),
@@ -37,22 +94,17 @@ static POP: for<'a> fn(&Vec<Object<'a>>) -> Result<Object<'a>, EvalErr> = |arg|
         return Ok(arr.borrow_mut().pop().unwrap());
     }
 };
-static PUSH: for<'a> fn(&Vec<Object<'a>>) -> Result<Object<'a>, EvalErr> = |arg| {
-    if arg.len() != 2 {
-        return Err(EvalErr::ArgsCount(2, arg.len()));
-    }
+static PUSH: BuiltinFn = |ctx, arg| {
     let arr = arg[0].to_arr(EvalErr::BuiltinArgsType(
         "push".to_string(),
         "Array".to_string(),
         arg[0].get_type(),
     ))?;
+    ctx.allocate(size_of::<Object>())?;
     arr.borrow_mut().push(arg[1].clone());
     Ok(arg[1].clone())
 };
-static POP_LEFT: for<'a> fn(&Vec<Object<'a>>) -> Result<Object<'a>, EvalErr> = |arg| {
-    if arg.len() != 1 {
-        return Err(EvalErr::ArgsCount(1, arg.len()));
-    }
+static POP_LEFT: BuiltinFn = |_, arg| {
     let arr = arg[0].to_arr(EvalErr::BuiltinArgsType(
         "pop".to_string(),
         "Array".to_string(),
@@ -65,20 +117,319 @@ static POP_LEFT: for<'a> fn(&Vec<Object<'a>>) -> Result<Object<'a>, EvalErr> = |
         return Ok(arr.borrow_mut().remove(0));
     }
 };
-static PRINT: for<'a> fn(&Vec<Object<'a>>) -> Result<Object<'a>, EvalErr> = |arg| {
-    arg.iter()
-        .for_each(|x| print!("{} \n", x.clone().to_string()));
+static PRINT: BuiltinFn = |ctx, arg| {
+    for x in arg.iter() {
+        ctx.write(Stream::Stdout, &format!("{} \n", x.to_string()))?;
+    }
     Ok(Object::Null)
 };
+static EPRINT: BuiltinFn = |ctx, arg| {
+    for x in arg.iter() {
+        ctx.write(Stream::Stderr, &format!("{} \n", x.to_string()))?;
+    }
+    Ok(Object::Null)
+};
+// input(prompt?) reads a line, null at end of input
+static INPUT: BuiltinFn = |ctx, arg| {
+    for x in arg.iter() {
+        ctx.write(Stream::Stdout, &x.to_string())?;
+    }
+    let line = ctx
+        .runtime()
+        .io
+        .read_line()
+        .map_err(|err| EvalErr::Io(err.to_string()))?;
+    Ok(line.map_or(Object::Null, Object::String))
+};
 
-pub static BUILTINS: Lazy<
-    HashMap<&'static str, for<'a> fn(&Vec<Object<'a>>) -> Result<Object<'a>, EvalErr>>,
-> = Lazy::new(|| {
+// exit(code?) stops the program, 0 by default
+static EXIT: BuiltinFn = |ctx, arg| {
+    let code = match arg.first() {
+        Some(code) => code.to_num()?,
+        None => 0,
+    };
+    let code = u8::try_from(code).map_err(|_| EvalErr::ExitCode(code))?;
+    ctx.runtime().host.exit(code)?;
+    Err(EvalErr::Exit(code))
+};
+// args() is the script's arguments as strings
+static ARGS: BuiltinFn = |ctx, _| {
+    let args = ctx.runtime().host.args()?;
+    ctx.allocate(args.len() * size_of::<Object>())?;
+    Ok(args.into_object())
+};
+// env_var(name) is the variable's value, null when unset
+static ENV_VAR: BuiltinFn = |ctx, arg| {
+    let name = String::from_object(&arg[0])?;
+    Ok(ctx.runtime().host.env_var(&name)?.into_object())
+};
+
+const SIZED: ArgType = ArgType::OneOf(&[ArgType::String, ArgType::Array, ArgType::Hash]);
+
+// Builtins every registry starts with
+pub static BUILTINS: Lazy<HashMap<&'static str, (BuiltinFn, Signature)>> = Lazy::new(|| {
     let mut m = HashMap::new();
-    m.insert("len", GET_LEN);
-    m.insert("pop", POP);
-    m.insert("pop_left", POP_LEFT);
-    m.insert("push", PUSH);
-    m.insert("print", PRINT);
+    m.insert("len", (GET_LEN, Signature::new(vec![SIZED])));
+    m.insert("pop", (POP, Signature::new(vec![ArgType::Array])));
+    m.insert("pop_left", (POP_LEFT, Signature::new(vec![ArgType::Array])));
+    m.insert(
+        "push",
+        (PUSH, Signature::new(vec![ArgType::Array, ArgType::Any])),
+    );
+    m.insert("print", (PRINT, Signature::variadic(vec![], ArgType::Any)));
+    m.insert(
+        "eprint",
+        (EPRINT, Signature::variadic(vec![], ArgType::Any)),
+    );
+    m.insert(
+        "input",
+        (
+            INPUT,
+            Signature::new(vec![]).with_optional(vec![ArgType::String]),
+        ),
+    );
+    m.insert(
+        "exit",
+        (
+            EXIT,
+            Signature::new(vec![]).with_optional(vec![ArgType::Number]),
+        ),
+    );
+    m.insert("args", (ARGS, Signature::new(vec![])));
+    m.insert("env_var", (ENV_VAR, Signature::new(vec![ArgType::String])));
+
+    let iter = Signature::new(vec![ArgType::Array, ArgType::Function]);
+    m.insert("map", (builtin_iter::MAP, iter.clone()));
+    m.insert("filter", (builtin_iter::FILTER, iter.clone()));
+    m.insert("each", (builtin_iter::EACH, iter.clone()));
+    m.insert("any", (builtin_iter::ANY, iter.clone()));
+    m.insert("all", (builtin_iter::ALL, iter.clone()));
+    m.insert("find", (builtin_iter::FIND, iter.clone()));
+    m.insert("flat_map", (builtin_iter::FLAT_MAP, iter.clone()));
+    m.insert("sort_by", (builtin_iter::SORT_BY, iter));
+    m.insert(
+        "reduce",
+        (
+            builtin_iter::REDUCE,
+            Signature::new(vec![ArgType::Array, ArgType::Function, ArgType::Any]),
+        ),
+    );
     m
 });
+
+// One line descriptions of the default builtins, shown by editors
+pub fn builtin_doc(name: &str) -> Option<&'static str> {
+    let doc = match name {
+        "len" => "Length of a string, array or hash",
+        "pop" => "Removes and returns the last element of an array, null when empty",
+        "pop_left" => "Removes and returns the first element of an array, null when empty",
+        "push" => "Appends a value to an array and returns the value",
+        "print" => "Writes each value to stdout on its own line",
+        "eprint" => "Writes each value to stderr on its own line",
+        "input" => "Reads a line after printing the prompt, null at end of input",
+        "exit" => "Stops the program with the exit code, 0 by default",
+        "args" => "The script's arguments as strings",
+        "env_var" => "The value of an environment variable, null when unset",
+        "map" => "A new array of the callback's results for each element",
+        "filter" => "A new array of the elements the callback accepts",
+        "each" => "Calls the callback on each element",
+        "any" => "Whether the callback accepts some element",
+        "all" => "Whether the callback accepts every element",
+        "find" => "First element the callback accepts, or null",
+        "flat_map" => "Like map, arrays returned by the callback are spliced in",
+        "sort_by" => "Stable sort by the Number or String key the callback returns",
+        "reduce" => "Folds the array with fn(acc, x) starting from the initial value",
+        _ => return None,
+    };
+    Some(doc)
+}
+
+// Argument types a signature can require
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgType {
+    Any,
+    Number,
+    String,
+    Boolean,
+    Null,
+    Array,
+    Hash,
+    Function, // script functions and builtins
+    OneOf(&'static [ArgType]),
+}
+
+impl ArgType {
+    pub fn accepts(&self, obj: &Object) -> bool {
+        match (self, obj) {
+            (ArgType::Any, _) => true,
+            (ArgType::Number, Object::Number(_)) => true,
+            (ArgType::String, Object::String(_)) => true,
+            (ArgType::Boolean, Object::Boolean(_)) => true,
+            (ArgType::Null, Object::Null) => true,
+            (ArgType::Array, Object::Array(_)) => true,
+            (ArgType::Hash, Object::Hash(_)) => true,
+            (ArgType::Function, Object::Function(_) | Object::Builtin(_)) => true,
+            (ArgType::OneOf(types), obj) => types.iter().any(|t| t.accepts(obj)),
+            _ => false,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            ArgType::OneOf(types) => types
+                .iter()
+                .map(|t| t.name())
+                .collect::<Vec<String>>()
+                .join("|"),
+            t => format!("{:?}", t),
+        }
+    }
+}
+
+// Arity and argument types of a builtin, checked before it is called
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub params: Vec<ArgType>,
+    pub optional: Vec<ArgType>, // may follow the required params
+    pub rest: Option<ArgType>,  // type of any extra arguments
+}
+
+impl Signature {
+    pub fn new(params: Vec<ArgType>) -> Self {
+        Self {
+            params,
+            optional: vec![],
+            rest: None,
+        }
+    }
+    pub fn variadic(params: Vec<ArgType>, rest: ArgType) -> Self {
+        Self {
+            rest: Some(rest),
+            ..Self::new(params)
+        }
+    }
+
+    pub fn with_optional(mut self, optional: Vec<ArgType>) -> Self {
+        self.optional = optional;
+        self
+    }
+
+    // Fewest arguments the builtin takes, and most unless it is variadic
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        let min = self.params.len();
+        match self.rest {
+            Some(_) => (min, None),
+            None => (min, Some(min + self.optional.len())),
+        }
+    }
+
+    // `name(Array, Function)`, optional arguments show as `Type?` and extra
+    // ones as `...Type`
+    pub fn describe(&self, name: &str) -> String {
+        let mut params = self
+            .params
+            .iter()
+            .map(|x| x.name())
+            .chain(self.optional.iter().map(|x| format!("{}?", x.name())))
+            .collect::<Vec<String>>();
+        if let Some(rest) = &self.rest {
+            params.push(format!("...{}", rest.name()));
+        }
+        format!("{}({})", name, params.join(", "))
+    }
+
+    pub fn check(&self, name: &str, args: &[Object]) -> Result<(), EvalErr> {
+        match self.arity() {
+            (min, _) if args.len() < min => return Err(EvalErr::ArgsCount(min, args.len())),
+            (_, Some(max)) if args.len() > max => return Err(EvalErr::ArgsCount(max, args.len())),
+            _ => {}
+        }
+        let optional = self.optional.iter();
+        let mut types = self
+            .params
+            .iter()
+            .chain(optional)
+            .chain(self.rest.iter().cycle());
+        for arg in args.iter() {
+            let expected = types.next().unwrap();
+            if !expected.accepts(arg) {
+                return Err(EvalErr::BuiltinArgsType(
+                    name.to_string(),
+                    expected.name(),
+                    arg.get_type(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Builtin {
+    pub func: Rc<HostFn>,
+    pub signature: Option<Signature>,
+}
+
+impl Builtin {
+    pub fn call(&self, name: &str, rt: &mut Runtime, args: &[Object]) -> Result<Object, EvalErr> {
+        if let Some(signature) = &self.signature {
+            signature.check(name, args)?;
+        }
+        (self.func)(&mut Context::new(name, rt), args)
+    }
+}
+
+// Builtins visible to one runtime. Names may be namespaced with dots,
+// `host.log` is called from scripts as `host.log(...)`.
+#[derive(Clone, Default)]
+pub struct BuiltinRegistry {
+    fns: HashMap<String, Builtin>,
+}
+
+impl BuiltinRegistry {
+    // A registry with no builtins at all
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        for (name, (func, signature)) in BUILTINS.iter() {
+            registry.register(name, Some(signature.clone()), *func);
+        }
+        registry
+    }
+
+    // Replaces any builtin already registered under `name`
+    pub fn register<F>(&mut self, name: &str, signature: Option<Signature>, func: F)
+    where
+        F: Fn(&mut Context, &[Object]) -> Result<Object, EvalErr> + MaybeSend + MaybeSync + 'static,
+    {
+        let builtin = Builtin {
+            func: Rc::new(func),
+            signature,
+        };
+        self.fns.insert(name.to_string(), builtin);
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.fns.remove(name).is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Builtin> {
+        self.fns.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.fns.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.fns.keys().map(|name| name.as_str())
+    }
+}
+
+impl Debug for BuiltinRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.fns.keys()).finish()
+    }
+}
@@ -0,0 +1,125 @@
+use std::{cmp::Ordering, mem::size_of};
+
+use crate::{
+    errors::eval_errs::EvalErr,
+    utils::shared::{Rc, RefCell},
+};
+
+use super::{builtin::BuiltinFn, builtin::Context, eval::is_truthy, object::Object};
+
+// Higher order builtins, the signatures guarantee `arg[0]` is an array and
+// `arg[1]` a function. Callbacks get a snapshot of the elements, so they may
+// mutate the array being iterated.
+
+fn elements(arr: &Object) -> Vec<Object> {
+    match arr {
+        Object::Array(a) => a.borrow().clone(),
+        _ => vec![],
+    }
+}
+
+fn new_array(ctx: &mut Context, elements: Vec<Object>) -> Result<Object, EvalErr> {
+    ctx.allocate(elements.len() * size_of::<Object>())?;
+    Ok(Object::Array(Rc::new(RefCell::new(elements))))
+}
+
+pub static MAP: BuiltinFn = |ctx, arg| {
+    let mut output = vec![];
+    for x in elements(&arg[0]) {
+        output.push(ctx.call(&arg[1], vec![x])?);
+    }
+    new_array(ctx, output)
+};
+
+pub static FILTER: BuiltinFn = |ctx, arg| {
+    let mut output = vec![];
+    for x in elements(&arg[0]) {
+        if is_truthy(ctx.call(&arg[1], vec![x.clone()])?) {
+            output.push(x);
+        }
+    }
+    new_array(ctx, output)
+};
+
+// reduce(arr, fn(acc, x) { ... }, initial)
+pub static REDUCE: BuiltinFn = |ctx, arg| {
+    let mut acc = arg[2].clone();
+    for x in elements(&arg[0]) {
+        acc = ctx.call(&arg[1], vec![acc, x])?;
+    }
+    Ok(acc)
+};
+
+pub static EACH: BuiltinFn = |ctx, arg| {
+    for x in elements(&arg[0]) {
+        ctx.call(&arg[1], vec![x])?;
+    }
+    Ok(Object::Null)
+};
+
+pub static ANY: BuiltinFn = |ctx, arg| {
+    for x in elements(&arg[0]) {
+        if is_truthy(ctx.call(&arg[1], vec![x])?) {
+            return Ok(Object::Boolean(true));
+        }
+    }
+    Ok(Object::Boolean(false))
+};
+
+pub static ALL: BuiltinFn = |ctx, arg| {
+    for x in elements(&arg[0]) {
+        if !is_truthy(ctx.call(&arg[1], vec![x])?) {
+            return Ok(Object::Boolean(false));
+        }
+    }
+    Ok(Object::Boolean(true))
+};
+
+// First element matching the predicate, or null
+pub static FIND: BuiltinFn = |ctx, arg| {
+    for x in elements(&arg[0]) {
+        if is_truthy(ctx.call(&arg[1], vec![x.clone()])?) {
+            return Ok(x);
+        }
+    }
+    Ok(Object::Null)
+};
+
+// Arrays returned by the callback are spliced in, other values are kept as is
+pub static FLAT_MAP: BuiltinFn = |ctx, arg| {
+    let mut output = vec![];
+    for x in elements(&arg[0]) {
+        match ctx.call(&arg[1], vec![x])? {
+            Object::Array(a) => output.extend(a.borrow().iter().cloned()),
+            value => output.push(value),
+        }
+    }
+    new_array(ctx, output)
+};
+
+// Stable sort by the Number or String key the callback returns
+pub static SORT_BY: BuiltinFn = |ctx, arg| {
+    let mut keyed = vec![];
+    for x in elements(&arg[0]) {
+        keyed.push((ctx.call(&arg[1], vec![x.clone()])?, x));
+    }
+    let mut err = None;
+    keyed.sort_by(|(a, _), (b, _)| {
+        compare_keys(a, b).unwrap_or_else(|e| {
+            err.get_or_insert(e);
+            Ordering::Equal
+        })
+    });
+    if let Some(err) = err {
+        return Err(err);
+    }
+    new_array(ctx, keyed.into_iter().map(|(_, x)| x).collect())
+};
+
+fn compare_keys(a: &Object, b: &Object) -> Result<Ordering, EvalErr> {
+    match (a, b) {
+        (Object::Number(n1), Object::Number(n2)) => Ok(n1.cmp(n2)),
+        (Object::String(s1), Object::String(s2)) => Ok(s1.cmp(s2)),
+        (a, b) => Err(EvalErr::Order(a.get_type(), b.get_type())),
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    errors::eval_errs::EvalErr,
+    utils::shared::{Rc, RefCell},
+};
+
+use super::{
+    native::{downcast_native, NativeObject},
+    object::Object,
+};
+
+pub use r_piece_derive::{FromObject, IntoObject};
+
+// Rust values that can be handed to scripts
+pub trait IntoObject {
+    fn into_object(self) -> Object;
+}
+
+// Rust values that can be read back from script objects
+pub trait FromObject: Sized {
+    fn from_object(obj: &Object) -> Result<Self, EvalErr>;
+}
+
+fn mismatch(expected: &str, obj: &Object) -> EvalErr {
+    EvalErr::CoerceObject(expected.to_string(), obj.get_type())
+}
+
+// Prefix the location of a nested value to a conversion error, so a bad
+// element reads `expected Number at points[1].x`
+pub fn coerce_path(err: EvalErr, segment: &str) -> EvalErr {
+    match err {
+        EvalErr::CoerceObject(expected, got) => {
+            let (ty, path) = match expected.split_once(" at ") {
+                Some((ty, path)) => (ty.to_string(), path.to_string()),
+                None => (expected, String::new()),
+            };
+            let sep = if path.is_empty() || path.starts_with('[') {
+                ""
+            } else {
+                "."
+            };
+            EvalErr::CoerceObject(format!("{} at {}{}{}", ty, segment, sep, path), got)
+        }
+        err => err,
+    }
+}
+
+impl IntoObject for i64 {
+    fn into_object(self) -> Object {
+        Object::Number(self)
+    }
+}
+impl FromObject for i64 {
+    fn from_object(obj: &Object) -> Result<Self, EvalErr> {
+        match obj {
+            Object::Number(n) => Ok(*n),
+            obj => Err(mismatch("Number", obj)),
+        }
+    }
+}
+
+impl IntoObject for bool {
+    fn into_object(self) -> Object {
+        Object::Boolean(self)
+    }
+}
+impl FromObject for bool {
+    fn from_object(obj: &Object) -> Result<Self, EvalErr> {
+        match obj {
+            Object::Boolean(b) => Ok(*b),
+            obj => Err(mismatch("Boolean", obj)),
+        }
+    }
+}
+
+impl IntoObject for String {
+    fn into_object(self) -> Object {
+        Object::String(self)
+    }
+}
+impl IntoObject for &str {
+    fn into_object(self) -> Object {
+        Object::String(self.to_string())
+    }
+}
+impl FromObject for String {
+    fn from_object(obj: &Object) -> Result<Self, EvalErr> {
+        match obj {
+            Object::String(s) => Ok(s.clone()),
+            obj => Err(mismatch("String", obj)),
+        }
+    }
+}
+
+impl IntoObject for () {
+    fn into_object(self) -> Object {
+        Object::Null
+    }
+}
+// Discards any value, for calls made only for their effects
+impl FromObject for () {
+    fn from_object(_: &Object) -> Result<Self, EvalErr> {
+        Ok(())
+    }
+}
+
+// `None` is null
+impl<T: IntoObject> IntoObject for Option<T> {
+    fn into_object(self) -> Object {
+        match self {
+            Some(value) => value.into_object(),
+            None => Object::Null,
+        }
+    }
+}
+impl<T: FromObject> FromObject for Option<T> {
+    fn from_object(obj: &Object) -> Result<Self, EvalErr> {
+        match obj {
+            Object::Null => Ok(None),
+            obj => Ok(Some(T::from_object(obj)?)),
+        }
+    }
+}
+
+impl<T: IntoObject> IntoObject for Vec<T> {
+    fn into_object(self) -> Object {
+        let elements = self.into_iter().map(|x| x.into_object()).collect();
+        Object::Array(Rc::new(RefCell::new(elements)))
+    }
+}
+impl<T: FromObject> FromObject for Vec<T> {
+    fn from_object(obj: &Object) -> Result<Self, EvalErr> {
+        let Object::Array(arr) = obj else {
+            return Err(mismatch("Array", obj));
+        };
+        arr.borrow()
+            .iter()
+            .enumerate()
+            .map(|(idx, x)| {
+                T::from_object(x).map_err(|err| coerce_path(err, &format!("[{}]", idx)))
+            })
+            .collect()
+    }
+}
+
+impl<T: IntoObject> IntoObject for HashMap<String, T> {
+    fn into_object(self) -> Object {
+        let pairs = self
+            .into_iter()
+            .map(|(k, v)| (k, v.into_object()))
+            .collect();
+        Object::Hash(Rc::new(RefCell::new(pairs)))
+    }
+}
+impl<T: FromObject> FromObject for HashMap<String, T> {
+    fn from_object(obj: &Object) -> Result<Self, EvalErr> {
+        expect_hash(obj)?
+            .borrow()
+            .iter()
+            .map(|(k, v)| match T::from_object(v) {
+                Ok(v) => Ok((k.clone(), v)),
+                Err(err) => Err(coerce_path(err, &format!("[{:?}]", k))),
+            })
+            .collect()
+    }
+}
+
+// Host objects, shared with the script
+impl<T: NativeObject> IntoObject for Rc<T> {
+    fn into_object(self) -> Object {
+        Object::Native(self)
+    }
+}
+impl<T: NativeObject> FromObject for Rc<T> {
+    fn from_object(obj: &Object) -> Result<Self, EvalErr> {
+        let expected = || std::any::type_name::<T>().to_string();
+        match obj {
+            Object::Native(native) => downcast_native::<T>(native)
+                .ok_or_else(|| EvalErr::CoerceObject(expected(), native.type_name())),
+            obj => Err(EvalErr::CoerceObject(expected(), obj.get_type())),
+        }
+    }
+}
+
+// Used by `#[derive(IntoObject)]`
+pub fn hash_object(pairs: Vec<(&str, Object)>) -> Object {
+    let pairs = pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    Object::Hash(Rc::new(RefCell::new(pairs)))
+}
+
+pub fn expect_hash(obj: &Object) -> Result<&Rc<RefCell<BTreeMap<String, Object>>>, EvalErr> {
+    match obj {
+        Object::Hash(hash) => Ok(hash),
+        obj => Err(mismatch("Hash", obj)),
+    }
+}
+
+// Used by `#[derive(FromObject)]`, a missing field reads as null
+pub fn hash_field<T: FromObject>(
+    hash: &BTreeMap<String, Object>,
+    name: &str,
+) -> Result<T, EvalErr> {
+    let value = hash.get(name).cloned().unwrap_or(Object::Null);
+    T::from_object(&value).map_err(|err| coerce_path(err, name))
+}
+
+// Argument lists for calling script functions from Rust
+pub trait IntoArgs {
+    fn into_args(self) -> Vec<Object>;
+}
+
+impl IntoArgs for Vec<Object> {
+    fn into_args(self) -> Vec<Object> {
+        self
+    }
+}
+impl IntoArgs for () {
+    fn into_args(self) -> Vec<Object> {
+        vec![]
+    }
+}
+
+// Tuples are fixed length arrays, or argument lists
+macro_rules! tuple_conversions {
+    ($len:literal => $($name:ident $idx:tt),+) => {
+        impl<$($name: IntoObject),+> IntoObject for ($($name,)+) {
+            fn into_object(self) -> Object {
+                let elements = vec![$(self.$idx.into_object()),+];
+                Object::Array(Rc::new(RefCell::new(elements)))
+            }
+        }
+        impl<$($name: FromObject),+> FromObject for ($($name,)+) {
+            fn from_object(obj: &Object) -> Result<Self, EvalErr> {
+                let expected = concat!("Array of length ", $len);
+                let Object::Array(arr) = obj else {
+                    return Err(mismatch(expected, obj));
+                };
+                let arr = arr.borrow();
+                if arr.len() != $len {
+                    return Err(EvalErr::CoerceObject(
+                        expected.to_string(),
+                        format!("Array of length {}", arr.len()),
+                    ));
+                }
+                Ok(($($name::from_object(&arr[$idx])
+                    .map_err(|err| coerce_path(err, concat!("[", $idx, "]")))?,)+))
+            }
+        }
+        impl<$($name: IntoObject),+> IntoArgs for ($($name,)+) {
+            fn into_args(self) -> Vec<Object> {
+                vec![$(self.$idx.into_object()),+]
+            }
+        }
+    };
+}
+
+tuple_conversions!(1 => A 0);
+tuple_conversions!(2 => A 0, B 1);
+tuple_conversions!(3 => A 0, B 1, C 2);
+tuple_conversions!(4 => A 0, B 1, C 2, D 3);
@@ -1,29 +1,36 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::collections::HashMap;
 
-use crate::errors::eval_errs::EvalErr;
+use crate::{
+    errors::eval_errs::EvalErr,
+    utils::shared::{Rc, RefCell},
+};
 
 use super::object::Object;
 
+// Shared handle to a scope. Closures keep theirs, so it outlives the eval
+// call that created it.
+pub type Env = Rc<RefCell<Environment>>;
+
 #[derive(Debug, Clone, PartialEq)]
-pub struct Environment<'a> {
-    pub store: HashMap<String, Object<'a>>,
-    pub outer: Option<Rc<RefCell<Environment<'a>>>>,
+pub struct Environment {
+    pub store: HashMap<String, Object>,
+    pub outer: Option<Env>,
 }
-impl<'a> Environment<'a> {
+impl Environment {
     pub fn new() -> Self {
         Self {
             store: HashMap::new(),
             outer: None,
         }
     }
-    pub fn new_with_outer(outer: Rc<RefCell<Environment<'a>>>) -> Self {
+    pub fn new_with_outer(outer: Env) -> Self {
         Self {
             store: HashMap::new(),
             outer: Some(outer),
         }
     }
 
-    pub fn initiate(&mut self, key: String, value: Object<'a>) -> Result<Object<'a>, EvalErr> {
+    pub fn initiate(&mut self, key: String, value: Object) -> Result<Object, EvalErr> {
         if self.store.contains_key(&key) {
             return Err(EvalErr::AlreadyInitialized(key));
         }
@@ -31,8 +38,13 @@ impl<'a> Environment<'a> {
         return Ok(value);
     }
 
+    // Binds the key in this scope, replacing any previous value
+    pub fn set(&mut self, key: String, value: Object) {
+        self.store.insert(key, value);
+    }
+
     // Recursively searches for the key in the "parent" environment
-    pub fn get(&self, key: &str) -> Result<Object<'a>, EvalErr> {
+    pub fn get(&self, key: &str) -> Result<Object, EvalErr> {
         let res = self.store.get(key);
         if res.is_none() {
             if let Some(outer) = &self.outer {
@@ -44,8 +56,19 @@ impl<'a> Environment<'a> {
         return Ok(res.unwrap().clone());
     }
 
+    // Names bound here and in the outer scopes, sorted and without duplicates
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.store.keys().cloned().collect();
+        if let Some(outer) = &self.outer {
+            names.extend(outer.borrow().names());
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
     // Recursively reassigns
-    pub fn reassign(&mut self, key: &str, value: Object<'a>) -> Result<Object<'a>, EvalErr> {
+    pub fn reassign(&mut self, key: &str, value: Object) -> Result<Object, EvalErr> {
         let res = self.store.get(key);
 
         if res.is_none() {
@@ -1,24 +1,42 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{collections::BTreeMap, mem::size_of};
 
 use crate::{
-    ast::ast::{Expression, IfExpression, Node, NodeTrait, NodeType, Statement},
+    ast::ast::{
+        Expression, Identifier, IfExpression, MemberExpression, Node, NodeTrait, NodeType,
+        Statement,
+    },
     errors::eval_errs::EvalErr,
     lexer::token::TOKEN,
+    utils::shared::{Rc, RefCell},
 };
 
 use super::{
-    builtin::BUILTINS,
-    environment::Environment,
+    builtin::Context,
+    environment::{Env, Environment},
     eval_infix::eval_infix_expression,
+    io::Stream,
+    native::NativeObject,
     object::{Function, Object},
+    runtime::{Rebind, Runtime},
 };
 
-pub fn eval<'a>(node: Node, env: Rc<RefCell<Environment<'a>>>) -> Result<Object<'a>, EvalErr> {
+// Deep recursion is bounded by Runtime::max_depth, not by the native stack:
+// when less than RED_ZONE is left, evaluation continues on a fresh segment.
+const RED_ZONE: usize = 256 * 1024;
+const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
+pub fn eval(node: Node, env: Env, rt: &mut Runtime) -> Result<Object, EvalErr> {
+    stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || eval_node(node, env, rt))
+}
+
+fn eval_node(node: Node, env: Env, rt: &mut Runtime) -> Result<Object, EvalErr> {
+    rt.step()?;
     match node.node_type() {
         NodeType::Program => {
             return eval_statements(
                 &node.to_statement()?.to_program()?.statements,
                 Rc::clone(&env),
+                rt,
             )
         }
         NodeType::ExpressionStatement => {
@@ -26,20 +44,20 @@ pub fn eval<'a>(node: Node, env: Rc<RefCell<Environment<'a>>>) -> Result<Object<
             if expr.is_none() {
                 return Ok(Object::Null);
             }
-            return eval(Node::Expression(expr.unwrap()), Rc::clone(&env));
+            return eval(Node::Expression(expr.unwrap()), Rc::clone(&env), rt);
         }
         NodeType::PrefixExpression => {
             let expr = node.to_expression()?.to_prefix()?;
             return eval_prefix_expression(
                 expr.token.clone(),
-                eval(Node::Expression(expr.right), Rc::clone(&env))?,
+                eval(Node::Expression(expr.right), Rc::clone(&env), rt)?,
             );
         }
         NodeType::InfixExpression => {
             let expr = node.to_expression()?.to_infix()?;
-            let left = eval(Node::Expression(expr.left), Rc::clone(&env))?;
-            let right = eval(Node::Expression(expr.right), Rc::clone(&env))?;
-            return eval_infix_expression(expr.operator.clone(), left, right);
+            let left = eval(Node::Expression(expr.left), Rc::clone(&env), rt)?;
+            let right = eval(Node::Expression(expr.right), Rc::clone(&env), rt)?;
+            return eval_infix_expression(expr.operator.clone(), left, right, rt);
         }
         NodeType::ReturnStatement => {
             let expr = node.to_statement()?.to_return()?;
@@ -49,40 +67,53 @@ pub fn eval<'a>(node: Node, env: Rc<RefCell<Environment<'a>>>) -> Result<Object<
             return Ok(Object::Return(Box::new(eval(
                 Node::Expression(expr.expression.unwrap()),
                 Rc::clone(&env),
+                rt,
             )?)));
         }
         NodeType::IfExpression => {
             let expr = node.to_expression()?.to_if()?;
-            return eval_if_expression(expr, Rc::clone(&env));
+            return eval_if_expression(expr, Rc::clone(&env), rt);
         }
         NodeType::BlockStatement => {
             let expr = node.to_statement()?.to_block()?;
-            return eval_statements(&expr.statements, Rc::clone(&env));
+            return eval_statements(&expr.statements, Rc::clone(&env), rt);
         }
         NodeType::LetStatement => {
             let expr = node.to_statement()?.to_let()?;
-            let value = eval(Node::Expression(expr.value), Rc::clone(&env))?;
-            env.borrow_mut().initiate(expr.name.0.clone(), value)?;
+            let value = eval(Node::Expression(expr.value), Rc::clone(&env), rt)?;
+            bind_let(expr.name.0.clone(), value, &env, rt)?;
             return Ok(Object::Null);
         }
         NodeType::ReassignStatement => {
             let expr = node.to_statement()?.to_reassign()?;
-            let value = eval(Node::Expression(expr.value), Rc::clone(&env))?;
+            let value = eval(Node::Expression(expr.value), Rc::clone(&env), rt)?;
             match expr.lhs {
                 Expression::Identifier(identifier) => {
                     env.borrow_mut().reassign(&identifier.0, value)?;
                 }
                 Expression::Index(index) => {
                     let ident = index.left.to_ident()?;
-                    let idx = eval(Node::Expression(index.index), Rc::clone(&env))?.to_num()?;
+                    let target = env.borrow().get(&ident.0)?;
+                    if let Object::Native(native) = target {
+                        let index = eval(Node::Expression(index.index), Rc::clone(&env), rt)?;
+                        native.index_set(&index, value)?;
+                        return Ok(Object::Null);
+                    }
+                    if let Object::Hash(hash) = target {
+                        let key = eval(Node::Expression(index.index), Rc::clone(&env), rt)?;
+                        let key = hash_key(key)?;
+                        if !hash.borrow().contains_key(&key) {
+                            rt.allocate(size_of::<Object>() + key.len())?;
+                        }
+                        hash.borrow_mut().insert(key, value);
+                        return Ok(Object::Null);
+                    }
+                    let idx = eval(Node::Expression(index.index), Rc::clone(&env), rt)?.to_num()?;
                     if idx < 0 {
                         return Err(EvalErr::IndexOutOfBounds(-1, 0));
                     }
 
-                    let arr = env
-                        .borrow()
-                        .get(&ident.0)?
-                        .to_arr(EvalErr::IndexArray(ident.0.clone()))?;
+                    let arr = target.to_arr(EvalErr::IndexArray(ident.0.clone()))?;
                     // check if index is out of bounds
                     if arr.as_ref().borrow().len() <= (idx as usize) {
                         return Err(EvalErr::IndexOutOfBounds(idx, arr.as_ref().borrow().len()));
@@ -105,18 +136,24 @@ pub fn eval<'a>(node: Node, env: Rc<RefCell<Environment<'a>>>) -> Result<Object<
         }
         NodeType::CallExpression => {
             let expr = node.to_expression()?.to_call()?;
-            let function = eval(Node::Expression(expr.function), Rc::clone(&env))?;
+            let name = call_name(&expr.function);
+            let function = match expr.function {
+                Expression::Member(member) => eval_member(*member, Rc::clone(&env), rt)?,
+                function => Member::Value(eval(Node::Expression(function), Rc::clone(&env), rt)?),
+            };
 
-            let args = eval_call_args(&expr.arguments, Rc::clone(&env))?;
-            return apply_function(function, args);
+            let args = eval_call_args(&expr.arguments, Rc::clone(&env), rt)?;
+            match function {
+                Member::Value(function) => return apply_function(name, function, args, rt),
+                Member::Method(native, method) => return apply_method(native, method, args, rt),
+            }
         }
         NodeType::Identifier => {
             let key = node.to_expression()?.to_ident()?;
             let borrow_env = env.borrow();
             let value = borrow_env.get(&key.0);
             if value.is_err() {
-                let func = BUILTINS.get(key.0.as_str());
-                if func.is_some() {
+                if rt.builtins.contains(&key.0) {
                     return Ok(Object::Builtin(key.0.clone()));
                 }
                 return Err(EvalErr::IdentifierNotFound(key.0.clone()));
@@ -126,14 +163,33 @@ pub fn eval<'a>(node: Node, env: Rc<RefCell<Environment<'a>>>) -> Result<Object<
         }
         NodeType::IndexExpression => {
             let expr = node.to_expression()?.to_index()?;
-            let left = eval(Node::Expression(expr.left), Rc::clone(&env))?;
+            let left = eval(Node::Expression(expr.left), Rc::clone(&env), rt)?;
+            if let Object::Native(native) = left {
+                let index = eval(Node::Expression(expr.index), Rc::clone(&env), rt)?;
+                return native.index_get(&index);
+            }
+            if let Object::Hash(hash) = left {
+                let key = hash_key(eval(Node::Expression(expr.index), Rc::clone(&env), rt)?)?;
+                // missing keys read as null
+                return Ok(hash.borrow().get(&key).cloned().unwrap_or(Object::Null));
+            }
             // parse left to array, and index to number
             let arr = left.to_arr(EvalErr::IndexArray(left.to_string()))?;
-            let index = eval(Node::Expression(expr.index), Rc::clone(&env))?.to_num()?;
-            if index < 0 {
-                return Err(EvalErr::IndexOutOfBounds(-1, 0));
+            let index = eval(Node::Expression(expr.index), Rc::clone(&env), rt)?.to_num()?;
+            let arr = arr.as_ref().borrow();
+            let value = usize::try_from(index)
+                .ok()
+                .and_then(|x| arr.get(x).cloned());
+            value.ok_or(EvalErr::IndexOutOfBounds(index, arr.len()))
+        }
+        NodeType::MemberExpression => {
+            let expr = node.to_expression()?.to_member()?;
+            match eval_member(expr, Rc::clone(&env), rt)? {
+                Member::Value(value) => return Ok(value),
+                Member::Method(native, name) => {
+                    return Err(EvalErr::UncalledMethod(native.type_name(), name))
+                }
             }
-            return Ok(arr.as_ref().borrow().get(index as usize).unwrap().clone());
         }
         NodeType::String => return Ok(Object::String(node.to_expression()?.to_string_value()?)),
         NodeType::Number => return Ok(Object::Number(node.to_expression()?.to_num()?)),
@@ -143,21 +199,56 @@ pub fn eval<'a>(node: Node, env: Rc<RefCell<Environment<'a>>>) -> Result<Object<
             let elements = expr
                 .elements
                 .iter()
-                .map(|x| eval(Node::Expression(x.clone()), Rc::clone(&env)))
+                .map(|x| eval(Node::Expression(x.clone()), Rc::clone(&env), rt))
                 .collect::<Result<Vec<Object>, EvalErr>>()?;
             return Ok(Object::Array(Rc::new(RefCell::new(elements))));
         }
+        NodeType::HashLiteral => {
+            let expr = node.to_expression()?.to_hash_literal()?;
+            let mut hash = BTreeMap::new();
+            for (key, value) in expr.pairs.into_iter() {
+                let key = hash_key(eval(Node::Expression(key), Rc::clone(&env), rt)?)?;
+                let value = eval(Node::Expression(value), Rc::clone(&env), rt)?;
+                rt.allocate(size_of::<Object>() + key.len())?;
+                hash.insert(key, value);
+            }
+            return Ok(Object::Hash(Rc::new(RefCell::new(hash))));
+        }
     }
 }
 
-fn eval_statements<'a>(
+// Rebinding is only relaxed in the root scope, function scopes stay strict
+fn bind_let(name: String, value: Object, env: &Env, rt: &mut Runtime) -> Result<(), EvalErr> {
+    let rebound = {
+        let env = env.borrow();
+        env.outer.is_none() && env.store.contains_key(&name)
+    };
+    if !rebound || rt.rebind == Rebind::Deny {
+        env.borrow_mut().initiate(name, value)?;
+        return Ok(());
+    }
+    if rt.rebind == Rebind::Warn {
+        let warning = format!("warning: {} is already defined, replacing it\n", name);
+        rt.io
+            .write(Stream::Stderr, &warning)
+            .map_err(|err| EvalErr::Io(err.to_string()))?;
+    }
+    env.borrow_mut().set(name, value);
+    Ok(())
+}
+
+fn eval_statements(
     statements: &Vec<Statement>,
-    env: Rc<RefCell<Environment<'a>>>,
-) -> Result<Object<'a>, EvalErr> {
+    env: Env,
+    rt: &mut Runtime,
+) -> Result<Object, EvalErr> {
     let mut result = Object::Null;
 
     for stmt in statements.iter() {
-        result = eval(Node::Statement(stmt.clone()), Rc::clone(&env))?;
+        if rt.hook.is_some() {
+            run_hook(stmt, &env, rt)?;
+        }
+        result = eval(Node::Statement(stmt.clone()), Rc::clone(&env), rt)?;
         if result.is_return() {
             return Ok(result);
         }
@@ -166,7 +257,17 @@ fn eval_statements<'a>(
     return Ok(result);
 }
 
-fn eval_prefix_expression(operator: TOKEN, right: Object) -> Result<Object, EvalErr> {
+// The hook is put back even when it fails, so it sees the next evaluation
+fn run_hook(stmt: &Statement, env: &Env, rt: &mut Runtime) -> Result<(), EvalErr> {
+    let Some(mut hook) = rt.hook.take() else {
+        return Ok(());
+    };
+    let result = hook.statement(stmt, env, rt);
+    rt.hook = Some(hook);
+    result
+}
+
+pub fn eval_prefix_expression(operator: TOKEN, right: Object) -> Result<Object, EvalErr> {
     match operator {
         TOKEN::BANG => Ok(eval_bang_expression(right)),
         TOKEN::MINUS => match right {
@@ -179,7 +280,7 @@ fn eval_prefix_expression(operator: TOKEN, right: Object) -> Result<Object, Eval
         ))),
     }
 }
-fn is_truthy(value: Object) -> bool {
+pub fn is_truthy(value: Object) -> bool {
     match value {
         Object::Boolean(b) => b,
         Object::Null => false,
@@ -193,36 +294,124 @@ fn eval_bang_expression(value: Object) -> Object {
     Object::Boolean(!is_truthy(value))
 }
 
-fn eval_if_expression<'a>(
+fn eval_if_expression(
     expression: IfExpression,
-    env: Rc<RefCell<Environment<'a>>>,
-) -> Result<Object<'a>, EvalErr> {
-    let condition = eval(Node::Expression(expression.condition), Rc::clone(&env))?;
+    env: Env,
+    rt: &mut Runtime,
+) -> Result<Object, EvalErr> {
+    let condition = eval(Node::Expression(expression.condition), Rc::clone(&env), rt)?;
     if is_truthy(condition) {
-        return eval_statements(&expression.consequence.statements, Rc::clone(&env));
+        return eval_statements(&expression.consequence.statements, Rc::clone(&env), rt);
     }
     if let Some(alternative) = &expression.alternative {
-        return eval_statements(&alternative.statements, Rc::clone(&env));
+        return eval_statements(&alternative.statements, Rc::clone(&env), rt);
     }
     return Ok(Object::Null);
 }
 
-fn eval_call_args<'a>(
+fn hash_key(key: Object) -> Result<String, EvalErr> {
+    match key {
+        Object::String(s) => Ok(s),
+        key => Err(EvalErr::HashKey(key.get_type())),
+    }
+}
+
+fn eval_call_args(
     args: &Vec<Expression>,
-    env: Rc<RefCell<Environment<'a>>>,
-) -> Result<Vec<Object<'a>>, EvalErr> {
+    env: Env,
+    rt: &mut Runtime,
+) -> Result<Vec<Object>, EvalErr> {
     let mut output: Vec<Object> = vec![];
     for arg in args.iter() {
-        output.push(eval(Node::Expression(arg.clone()), Rc::clone(&env))?);
+        output.push(eval(Node::Expression(arg.clone()), Rc::clone(&env), rt)?);
     }
     return Ok(output);
 }
 
-fn apply_function<'a>(function: Object<'a>, args: Vec<Object<'a>>) -> Result<Object<'a>, EvalErr> {
+// Name shown for a call frame, functions are anonymous unless bound to an identifier
+fn call_name(function: &Expression) -> String {
+    match function {
+        Expression::Identifier(ident) => ident.0.clone(),
+        Expression::Member(member) if namespace_root(&member.object).is_some() => {
+            format!("{}.{}", call_name(&member.object), member.property.0)
+        }
+        _ => "<anonymous>".to_string(),
+    }
+}
+
+// What `object.property` refers to
+enum Member {
+    Value(Object),
+    Method(Rc<dyn NativeObject>, String),
+}
+
+fn eval_member(expr: MemberExpression, env: Env, rt: &mut Runtime) -> Result<Member, EvalErr> {
+    // `ns.name` is a namespaced builtin, unless `ns` is a variable
+    if let Some(root) = namespace_root(&expr.object) {
+        if env.borrow().get(&root.0).is_err() {
+            let name = call_name(&Expression::Member(Box::new(expr)));
+            if rt.builtins.contains(&name) {
+                return Ok(Member::Value(Object::Builtin(name)));
+            }
+            return Err(EvalErr::IdentifierNotFound(name));
+        }
+    }
+    let object = eval(Node::Expression(expr.object), Rc::clone(&env), rt)?;
+    match object {
+        // `h.name` reads like `h["name"]`
+        Object::Hash(hash) => {
+            let value = hash.borrow().get(&expr.property.0).cloned();
+            Ok(Member::Value(value.unwrap_or(Object::Null)))
+        }
+        Object::Native(native) => Ok(Member::Method(native, expr.property.0)),
+        object => Err(EvalErr::NoMember(object.get_type(), expr.property.0)),
+    }
+}
+
+// The leftmost identifier of a dotted path like `a.b.c`
+fn namespace_root(expression: &Expression) -> Option<&Identifier> {
+    match expression {
+        Expression::Identifier(ident) => Some(ident),
+        Expression::Member(member) => namespace_root(&member.object),
+        _ => None,
+    }
+}
+
+pub fn apply_function(
+    name: String,
+    function: Object,
+    args: Vec<Object>,
+    rt: &mut Runtime,
+) -> Result<Object, EvalErr> {
+    rt.enter(name)?;
+    // the frame must be popped on error too, so no `?` between enter and exit
+    let result = call_function(function, args, rt);
+    rt.exit();
+    return result;
+}
+
+fn apply_method(
+    native: Rc<dyn NativeObject>,
+    method: String,
+    args: Vec<Object>,
+    rt: &mut Runtime,
+) -> Result<Object, EvalErr> {
+    let name = format!("{}.{}", native.type_name(), method);
+    rt.enter(name.clone())?;
+    let result = native.call_method(&mut Context::new(&name, rt), &method, &args);
+    rt.exit();
+    return result;
+}
+
+fn call_function(function: Object, args: Vec<Object>, rt: &mut Runtime) -> Result<Object, EvalErr> {
     let func = match function {
         Object::Function(f) => f,
         Object::Builtin(s) => {
-            return BUILTINS.get(s.as_str()).unwrap()(&args);
+            let builtin = match rt.builtins.get(&s) {
+                Some(builtin) => builtin.clone(),
+                None => return Err(EvalErr::IdentifierNotFound(s)),
+            };
+            return builtin.call(&s, rt, &args);
         }
         _ => {
             return Err(EvalErr::NotImplemented(format!(
@@ -232,14 +421,14 @@ fn apply_function<'a>(function: Object<'a>, args: Vec<Object<'a>>) -> Result<Obj
         }
     };
     let extended_env = extend_fn_env(&func, args)?;
-    let evaluated = eval_statements(&func.body.statements, Rc::clone(&extended_env))?;
+    let evaluated = eval_statements(&func.body.statements, Rc::clone(&extended_env), rt)?;
     return unwrap_return(evaluated);
 }
 
-fn extend_fn_env<'a>(
-    function: &Function<'a>,
-    args: Vec<Object<'a>>,
-) -> Result<Rc<RefCell<Environment<'a>>>, EvalErr> {
+fn extend_fn_env(function: &Function, args: Vec<Object>) -> Result<Env, EvalErr> {
+    if args.len() < function.params.len() {
+        return Err(EvalErr::ArgsCount(function.params.len(), args.len()));
+    }
     let env = Rc::new(RefCell::new(Environment::new_with_outer(Rc::clone(
         &function.env,
     ))));
@@ -251,7 +440,7 @@ fn extend_fn_env<'a>(
     return Ok(env);
 }
 
-fn unwrap_return<'a>(value: Object<'a>) -> Result<Object<'a>, EvalErr> {
+fn unwrap_return(value: Object) -> Result<Object, EvalErr> {
     match value {
         Object::Return(v) => Ok(v.as_ref().clone()),
         obj => Ok(obj),
@@ -1,16 +1,17 @@
 use crate::{errors::eval_errs::EvalErr, lexer::token::TOKEN};
 
-use super::object::Object;
+use super::{object::Object, runtime::Runtime};
 
-pub fn eval_infix_expression<'a>(
+pub fn eval_infix_expression(
     operator: TOKEN,
-    left: Object<'a>,
-    right: Object<'a>,
-) -> Result<Object<'a>, EvalErr> {
+    left: Object,
+    right: Object,
+    rt: &mut Runtime,
+) -> Result<Object, EvalErr> {
     match operator {
         TOKEN::PLUS => eval_plus_expression(left, right),
         TOKEN::MINUS => eval_substract_expression(left, right),
-        TOKEN::ASTERISK => eval_multiply_expression(left, right),
+        TOKEN::ASTERISK => eval_multiply_expression(left, right, rt),
         TOKEN::SLASH => eval_div_expression(left, right),
 
         TOKEN::LT | TOKEN::GT => eval_order_expression(operator, left, right),
@@ -23,7 +24,7 @@ pub fn eval_infix_expression<'a>(
     }
 }
 
-fn eval_plus_expression<'a>(left: Object<'a>, right: Object<'a>) -> Result<Object<'a>, EvalErr> {
+fn eval_plus_expression(left: Object, right: Object) -> Result<Object, EvalErr> {
     match (left, right) {
         (Object::Number(n1), Object::Number(n2)) => Ok(Object::Number(n1 + n2)),
         (Object::String(s1), Object::String(s2)) => {
@@ -33,34 +34,30 @@ fn eval_plus_expression<'a>(left: Object<'a>, right: Object<'a>) -> Result<Objec
         (l, r) => Err(EvalErr::PlusError(l.to_string(), r.to_string())),
     }
 }
-fn eval_substract_expression<'a>(
-    left: Object<'a>,
-    right: Object<'a>,
-) -> Result<Object<'a>, EvalErr> {
+fn eval_substract_expression(left: Object, right: Object) -> Result<Object, EvalErr> {
     match (left, right) {
         (Object::Number(n1), Object::Number(n2)) => Ok(Object::Number(n1 - n2)),
         (l, r) => Err(EvalErr::SubstractError(l.to_string(), r.to_string())),
     }
 }
 
-fn eval_multiply_expression<'a>(
-    left: Object<'a>,
-    right: Object<'a>,
-) -> Result<Object<'a>, EvalErr> {
+fn eval_multiply_expression(
+    left: Object,
+    right: Object,
+    rt: &mut Runtime,
+) -> Result<Object, EvalErr> {
     match (left, right) {
         (Object::Number(n1), Object::Number(n2)) => Ok(Object::Number(n1 * n2)),
         (Object::Number(n), Object::String(s)) | (Object::String(s), Object::Number(n)) => {
-            let mut str = String::new();
-            for _ in 0..n {
-                str.push_str(&s);
-            }
-            Ok(Object::String(str))
+            let times = n.max(0) as usize;
+            rt.allocate(s.len().saturating_mul(times))?;
+            Ok(Object::String(s.repeat(times)))
         }
         (l, r) => Err(EvalErr::MultiplyError(l.to_string(), r.to_string())),
     }
 }
 
-fn eval_div_expression<'a>(left: Object<'a>, right: Object<'a>) -> Result<Object<'a>, EvalErr> {
+fn eval_div_expression(left: Object, right: Object) -> Result<Object, EvalErr> {
     match (left, right) {
         (_, Object::Number(n2)) if n2 == 0 => Err(EvalErr::DivideByZero),
         (Object::Number(n1), Object::Number(n2)) => Ok(Object::Number(n1 / n2)),
@@ -68,11 +65,7 @@ fn eval_div_expression<'a>(left: Object<'a>, right: Object<'a>) -> Result<Object
     }
 }
 
-fn eval_order_expression<'a>(
-    operator: TOKEN,
-    left: Object<'a>,
-    right: Object<'a>,
-) -> Result<Object<'a>, EvalErr> {
+fn eval_order_expression(operator: TOKEN, left: Object, right: Object) -> Result<Object, EvalErr> {
     match (left, right) {
         (Object::Number(n1), Object::Number(n2)) => Ok(Object::Boolean(
             (n1 > n2 && operator == TOKEN::GT) || (n1 < n2 && operator == TOKEN::LT),
@@ -84,11 +77,7 @@ fn eval_order_expression<'a>(
     }
 }
 
-fn eval_eq_expression<'a>(
-    operator: TOKEN,
-    left: Object<'a>,
-    right: Object<'a>,
-) -> Result<Object<'a>, EvalErr> {
+fn eval_eq_expression(operator: TOKEN, left: Object, right: Object) -> Result<Object, EvalErr> {
     match (left, right) {
         (Object::Number(n1), Object::Number(n2)) => Ok(Object::Boolean(
             (n1 == n2 && operator == TOKEN::EQ) || (n1 != n2 && operator == TOKEN::NotEQ),
@@ -99,6 +88,10 @@ fn eval_eq_expression<'a>(
         (Object::Boolean(b1), Object::Boolean(b2)) => Ok(Object::Boolean(
             (b1 == b2 && operator == TOKEN::EQ) || (b1 != b2 && operator == TOKEN::NotEQ),
         )),
+        (Object::Native(n1), Object::Native(n2)) => {
+            let eq = n1.equals(n2.as_ref());
+            Ok(Object::Boolean(eq == (operator == TOKEN::EQ)))
+        }
         (l, r) => Err(EvalErr::Equal(l.to_string(), r.to_string())),
     }
 }
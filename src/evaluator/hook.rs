@@ -0,0 +1,21 @@
+use std::fmt::{self, Debug};
+
+use crate::{ast::ast::Statement, errors::eval_errs::EvalErr, utils::shared::MaybeSend};
+
+use super::{environment::Env, runtime::Runtime};
+
+// Observes a running evaluation, e.g. to pause it in a debugger. Runtimes
+// have none by default, the evaluator then only checks an Option per
+// statement.
+pub trait EvalHook: MaybeSend {
+    // Called before each statement of the program or of a block runs in
+    // `env`. The hook is taken out of `rt` meanwhile, so it may evaluate code
+    // without being called again. An error stops the evaluation.
+    fn statement(&mut self, stmt: &Statement, env: &Env, rt: &mut Runtime) -> Result<(), EvalErr>;
+}
+
+impl Debug for dyn EvalHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EvalHook")
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{self, Debug},
+};
+
+use crate::{errors::eval_errs::EvalErr, utils::shared::MaybeSend};
+
+// Capabilities a script gets from its host: `args()`, `env_var(name)` and
+// `exit(code)`. Every one is denied unless the host implements it, so an
+// embedder can stub some and keep the rest out of reach.
+pub trait Host: MaybeSend {
+    // Arguments the script was started with
+    fn args(&mut self) -> Result<Vec<String>, EvalErr> {
+        Err(EvalErr::Denied("args".into()))
+    }
+
+    // `None` when the variable is not set
+    fn env_var(&mut self, _name: &str) -> Result<Option<String>, EvalErr> {
+        Err(EvalErr::Denied("env_var".into()))
+    }
+
+    // Returning Ok lets `exit` stop the program with the code
+    fn exit(&mut self, _code: u8) -> Result<(), EvalErr> {
+        Err(EvalErr::Denied("exit".into()))
+    }
+}
+
+impl Debug for dyn Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Host")
+    }
+}
+
+// The real process, used by the command line runner
+#[derive(Debug, Clone, Default)]
+pub struct ProcessHost {
+    args: Vec<String>,
+}
+
+impl ProcessHost {
+    pub fn new(args: Vec<String>) -> Self {
+        Self { args }
+    }
+}
+
+impl Host for ProcessHost {
+    fn args(&mut self) -> Result<Vec<String>, EvalErr> {
+        Ok(self.args.clone())
+    }
+    fn env_var(&mut self, name: &str) -> Result<Option<String>, EvalErr> {
+        Ok(env::var(name).ok())
+    }
+    fn exit(&mut self, _code: u8) -> Result<(), EvalErr> {
+        Ok(())
+    }
+}
+
+// Fixed arguments and variables instead of the process ones, the default
+// for embedded interpreters. `exit` is allowed, it only ends the program.
+#[derive(Debug, Clone, Default)]
+pub struct StubHost {
+    pub args: Vec<String>,
+    pub vars: HashMap<String, String>,
+}
+
+impl StubHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_args(mut self, args: &[&str]) -> Self {
+        self.args = args.iter().map(|x| x.to_string()).collect();
+        self
+    }
+    pub fn with_var(mut self, name: &str, value: &str) -> Self {
+        self.vars.insert(name.to_string(), value.to_string());
+        self
+    }
+}
+
+impl Host for StubHost {
+    fn args(&mut self) -> Result<Vec<String>, EvalErr> {
+        Ok(self.args.clone())
+    }
+    fn env_var(&mut self, name: &str) -> Result<Option<String>, EvalErr> {
+        Ok(self.vars.get(name).cloned())
+    }
+    fn exit(&mut self, _code: u8) -> Result<(), EvalErr> {
+        Ok(())
+    }
+}
+
+// Denies every capability
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DenyHost;
+
+impl Host for DenyHost {}
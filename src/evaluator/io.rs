@@ -0,0 +1,132 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Debug},
+    io::{self, BufRead, Write},
+};
+
+use crate::utils::shared::{MaybeSend, Rc, RefCell};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+// Everything a script reads or writes goes through the interpreter's
+// ScriptIo, so hosts and tests can capture or redirect it
+pub trait ScriptIo: MaybeSend {
+    fn write(&mut self, stream: Stream, text: &str) -> io::Result<()>;
+
+    // A line without its line ending, `None` at end of input
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+impl Debug for dyn ScriptIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ScriptIo")
+    }
+}
+
+// The process stdin, stdout and stderr
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdIo;
+
+impl ScriptIo for StdIo {
+    fn write(&mut self, stream: Stream, text: &str) -> io::Result<()> {
+        match stream {
+            Stream::Stdout => {
+                let mut out = io::stdout().lock();
+                out.write_all(text.as_bytes())?;
+                out.flush()
+            }
+            Stream::Stderr => io::stderr().lock().write_all(text.as_bytes()),
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(trim_line_ending(line)))
+    }
+}
+
+// In-memory I/O for tests. Clones share the buffers, keep one to inspect
+// the output after handing the other to an interpreter.
+#[derive(Debug, Clone, Default)]
+pub struct BufferIo {
+    out: Rc<RefCell<String>>,
+    err: Rc<RefCell<String>>,
+    input: Rc<RefCell<VecDeque<String>>>,
+}
+
+impl BufferIo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // Lines returned by successive `input()` calls
+    pub fn with_input(text: &str) -> Self {
+        let io = Self::new();
+        io.input
+            .borrow_mut()
+            .extend(text.lines().map(|x| x.to_string()));
+        io
+    }
+
+    pub fn stdout(&self) -> String {
+        self.out.borrow().clone()
+    }
+    pub fn stderr(&self) -> String {
+        self.err.borrow().clone()
+    }
+    pub fn clear(&self) {
+        self.out.borrow_mut().clear();
+        self.err.borrow_mut().clear();
+    }
+}
+
+impl ScriptIo for BufferIo {
+    fn write(&mut self, stream: Stream, text: &str) -> io::Result<()> {
+        match stream {
+            Stream::Stdout => self.out.borrow_mut().push_str(text),
+            Stream::Stderr => self.err.borrow_mut().push_str(text),
+        }
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        Ok(self.input.borrow_mut().pop_front())
+    }
+}
+
+// Hands output to a host callback, e.g. to forward it to a log or UI.
+// Scripts see no input.
+pub struct CallbackIo<F: FnMut(Stream, &str)> {
+    on_output: F,
+}
+
+impl<F: FnMut(Stream, &str)> CallbackIo<F> {
+    pub fn new(on_output: F) -> Self {
+        Self { on_output }
+    }
+}
+
+impl<F: FnMut(Stream, &str) + MaybeSend> ScriptIo for CallbackIo<F> {
+    fn write(&mut self, stream: Stream, text: &str) -> io::Result<()> {
+        (self.on_output)(stream, text);
+        Ok(())
+    }
+}
+
+fn trim_line_ending(mut line: String) -> String {
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    line
+}
@@ -1,6 +1,14 @@
 pub mod builtin;
+mod builtin_iter;
+pub mod convert;
 pub mod environment;
 pub mod eval;
 pub mod eval_infix;
+pub mod hook;
+pub mod host;
+pub mod io;
+pub mod native;
 pub mod object;
+pub mod runtime;
+pub mod serialize;
 mod test;
@@ -0,0 +1,64 @@
+use std::{
+    any::Any,
+    fmt::{self, Debug},
+};
+
+use crate::{
+    errors::eval_errs::EvalErr,
+    utils::shared::{MaybeSend, MaybeSync, Rc, SharedAny},
+};
+
+use super::{builtin::Context, object::Object};
+
+// Host values handed to scripts as `Object::Native`, e.g. a database handle.
+// Scripts can index them and call their methods (`conn.query("...")`);
+// everything but the type name has a default. Methods take `&self`, use
+// interior mutability for state.
+pub trait NativeObject: Any + MaybeSend + MaybeSync {
+    fn type_name(&self) -> String;
+
+    fn display(&self) -> String {
+        format!("<{}>", self.type_name())
+    }
+
+    // Identity by default
+    fn equals(&self, other: &dyn NativeObject) -> bool {
+        std::ptr::addr_eq(self as *const Self, other as *const dyn NativeObject)
+    }
+
+    fn index_get(&self, _index: &Object) -> Result<Object, EvalErr> {
+        Err(EvalErr::IndexArray(self.type_name()))
+    }
+
+    fn index_set(&self, _index: &Object, _value: Object) -> Result<(), EvalErr> {
+        Err(EvalErr::IndexArray(self.type_name()))
+    }
+
+    // `obj.name(args...)`, the context can call back into the script
+    fn call_method(
+        &self,
+        _ctx: &mut Context,
+        name: &str,
+        _args: &[Object],
+    ) -> Result<Object, EvalErr> {
+        Err(EvalErr::NoMember(self.type_name(), name.to_string()))
+    }
+}
+
+impl PartialEq for dyn NativeObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.equals(other)
+    }
+}
+
+impl Debug for dyn NativeObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display())
+    }
+}
+
+// Recover the concrete host type of a native object
+pub fn downcast_native<T: NativeObject>(native: &Rc<dyn NativeObject>) -> Option<Rc<T>> {
+    let any: Rc<SharedAny> = native.clone();
+    any.downcast::<T>().ok()
+}
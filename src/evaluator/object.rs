@@ -1,27 +1,30 @@
-use std::{cell::RefCell, rc::Rc};
+use std::collections::BTreeMap;
 
 use crate::{
     ast::ast::{BlockStatement, Identifier, NodeTrait},
     errors::eval_errs::EvalErr,
+    utils::shared::{Rc, RefCell},
 };
 
-use super::environment::Environment;
+use super::{convert::FromObject, environment::Env, native::NativeObject};
 
 #[allow(unused)]
 #[derive(Debug, Clone, PartialEq)]
-pub enum Object<'a> {
+pub enum Object {
     Number(i64),
     Identifier(Identifier),
     String(String),
     Builtin(String), // get function from builtin
     Boolean(bool),
-    Array(Rc<RefCell<Vec<Object<'a>>>>),
+    Array(Rc<RefCell<Vec<Object>>>),
+    Hash(Rc<RefCell<BTreeMap<String, Object>>>),
     Null,
-    Return(Box<Object<'a>>),
-    Function(Function<'a>),
+    Return(Box<Object>),
+    Function(Function),
+    Native(Rc<dyn NativeObject>),
 }
 
-impl<'a> Object<'a> {
+impl Object {
     pub fn get_type(&self) -> String {
         match self {
             Object::Number(_) => "Number".into(),
@@ -29,24 +32,20 @@ impl<'a> Object<'a> {
             Object::String(_) => "String".into(),
             Object::Boolean(_) => "Boolean".into(),
             Object::Array(_) => "Array".into(),
+            Object::Hash(_) => "Hash".into(),
             Object::Null => "Null".into(),
             Object::Return(_) => "Return".into(),
             Object::Function(_) => "Function".into(),
             Object::Builtin(_) => "Builtin".into(),
+            Object::Native(n) => n.type_name(),
         }
     }
 
     pub fn to_num(&self) -> Result<i64, EvalErr> {
-        match self {
-            Object::Number(n) => Ok(*n),
-            anything => Err(EvalErr::CoerceObject(
-                anything.to_string(),
-                "number".to_string(),
-            )),
-        }
+        i64::from_object(self)
     }
 
-    pub fn to_arr(&self, err: EvalErr) -> Result<Rc<RefCell<Vec<Object<'a>>>>, EvalErr> {
+    pub fn to_arr(&self, err: EvalErr) -> Result<Rc<RefCell<Vec<Object>>>, EvalErr> {
         match self {
             Object::Array(a) => Ok(a.clone()),
             _ => Err(err),
@@ -73,6 +72,14 @@ impl<'a> Object<'a> {
                 str.push(']');
                 return str;
             }
+            Object::Hash(h) => {
+                let pairs = h
+                    .borrow()
+                    .iter()
+                    .map(|(k, v)| format!("{:?}: {}", k, v.to_string()))
+                    .collect::<Vec<String>>();
+                format!("{{{}}}", pairs.join(", "))
+            }
             Object::Null => "Null".to_string(),
             Object::Return(r) => r.to_string(),
             Object::Function(f) => {
@@ -89,6 +96,7 @@ impl<'a> Object<'a> {
                 return str;
             }
             Object::Builtin(s) => format!("builtins({})", s),
+            Object::Native(n) => n.display(),
         }
     }
 
@@ -101,23 +109,22 @@ impl<'a> Object<'a> {
 }
 
 #[derive(Debug, Clone)]
-pub struct Function<'a> {
+pub struct Function {
     pub params: Vec<Identifier>,
     pub body: BlockStatement,
-    pub env: Rc<RefCell<Environment<'a>>>,
+    pub env: Env,
 }
-impl<'a> Function<'a> {
-    pub fn new(
-        params: Vec<Identifier>,
-        body: BlockStatement,
-        env: Rc<RefCell<Environment<'a>>>,
-    ) -> Self {
+impl Function {
+    pub fn new(params: Vec<Identifier>, body: BlockStatement, env: Env) -> Self {
         Self { params, body, env }
     }
 }
 
-impl<'a> PartialEq for Function<'a> {
+// Same code closing over the same scope
+impl PartialEq for Function {
     fn eq(&self, other: &Self) -> bool {
-        self == other
+        Rc::ptr_eq(&self.env, &other.env)
+            && self.params == other.params
+            && self.body.to_str() == other.body.to_str()
     }
 }
@@ -0,0 +1,198 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::errors::eval_errs::EvalErr;
+
+use super::{
+    builtin::BuiltinRegistry,
+    hook::EvalHook,
+    host::{Host, StubHost},
+    io::{ScriptIo, StdIo},
+};
+
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+// How many of the innermost calls are reported by a StackOverflow error
+const REPORTED_FRAMES: usize = 5;
+// Reading the clock on every step is too slow, check the deadline
+// and the interrupt flag periodically
+const CHECK_INTERVAL: u64 = 256;
+
+// Budgets for running untrusted scripts, `None` means unlimited
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limits {
+    pub max_depth: usize,
+    pub max_steps: Option<u64>,
+    pub timeout: Option<Duration>,
+    pub max_alloc: Option<usize>, // in bytes
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_steps: None,
+            timeout: None,
+            max_alloc: None,
+        }
+    }
+}
+
+// What a top level `let` does when its name is already bound. Scripts are
+// strict, a REPL lets an input be run again.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Rebind {
+    #[default]
+    Deny, // AlreadyInitialized error
+    Replace,
+    Warn, // replace, with a warning on stderr
+}
+
+// Cloneable flag to stop a running evaluation from another thread or a signal handler
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+    pub fn clear(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+    // Consume a pending interrupt so the next evaluation can run
+    fn take(&self) -> bool {
+        self.0.swap(false, Ordering::SeqCst)
+    }
+}
+
+// Mutable state of a single evaluation, shared by every `eval` call
+#[derive(Debug)]
+pub struct Runtime {
+    pub limits: Limits,
+    pub builtins: BuiltinRegistry,
+    pub io: Box<dyn ScriptIo>,
+    pub host: Box<dyn Host>,
+    pub rebind: Rebind,
+    pub hook: Option<Box<dyn EvalHook>>,
+    call_stack: Vec<String>,
+    steps: u64,
+    deadline: Option<Instant>,
+    allocated: usize,
+    interrupt: InterruptHandle,
+}
+
+impl Runtime {
+    pub fn new() -> Self {
+        Self::with_limits(Limits::default())
+    }
+    pub fn with_limits(limits: Limits) -> Self {
+        let mut rt = Self {
+            limits,
+            builtins: BuiltinRegistry::with_defaults(),
+            io: Box::new(StdIo),
+            host: Box::new(StubHost::new()),
+            rebind: Rebind::Deny,
+            hook: None,
+            call_stack: vec![],
+            steps: 0,
+            deadline: None,
+            allocated: 0,
+            interrupt: InterruptHandle::new(),
+        };
+        rt.reset_usage();
+        rt
+    }
+
+    // Start the budgets over, e.g. before each REPL input
+    pub fn reset_usage(&mut self) {
+        self.steps = 0;
+        self.allocated = 0;
+        self.deadline = self.limits.timeout.map(|t| Instant::now() + t);
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    fn check_interrupt(&self) -> Result<(), EvalErr> {
+        if self.interrupt.take() {
+            return Err(EvalErr::Interrupted);
+        }
+        Ok(())
+    }
+
+    pub fn depth(&self) -> usize {
+        self.call_stack.len()
+    }
+
+    // Names of the calls being evaluated, outermost first
+    pub fn call_stack(&self) -> &[String] {
+        &self.call_stack
+    }
+
+    // Push a call frame, failing once the configured depth is reached
+    pub fn enter(&mut self, name: String) -> Result<(), EvalErr> {
+        self.check_interrupt()?;
+        if self.call_stack.len() >= self.limits.max_depth {
+            let innermost = std::iter::once(&name)
+                .chain(self.call_stack.iter().rev())
+                .take(REPORTED_FRAMES)
+                .cloned()
+                .collect();
+            return Err(EvalErr::StackOverflow(self.limits.max_depth, innermost));
+        }
+        self.call_stack.push(name);
+        Ok(())
+    }
+
+    pub fn exit(&mut self) {
+        self.call_stack.pop();
+    }
+
+    // Count one evaluation step against the budgets, and poll for interrupts
+    pub fn step(&mut self) -> Result<(), EvalErr> {
+        self.steps += 1;
+        if let Some(max) = self.limits.max_steps {
+            if self.steps > max {
+                return Err(EvalErr::StepLimit(max));
+            }
+        }
+        if !self.steps.is_multiple_of(CHECK_INTERVAL) {
+            return Ok(());
+        }
+        self.check_interrupt()?;
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return Err(EvalErr::Timeout(self.limits.timeout.unwrap_or_default()));
+            }
+        }
+        Ok(())
+    }
+
+    // Charge `bytes` against the allocation budget before allocating them
+    pub fn allocate(&mut self, bytes: usize) -> Result<(), EvalErr> {
+        self.allocated = self.allocated.saturating_add(bytes);
+        if let Some(max) = self.limits.max_alloc {
+            if self.allocated > max {
+                return Err(EvalErr::MemoryLimit(max));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,181 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeMap, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::utils::shared::{Rc, RefCell};
+
+use super::object::Object;
+
+// Data values map to their serde counterparts: numbers, strings, booleans,
+// null, arrays and hashes. Functions, builtins and native objects are errors.
+
+impl Serialize for Object {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Value {
+            obj: self,
+            parents: None,
+        }
+        .serialize(serializer)
+    }
+}
+
+// Arrays and hashes being serialized, from the innermost out. A shared
+// reference is written out at each place it appears, one that contains
+// itself is an error instead of an endless recursion.
+struct Parents<'p> {
+    ptr: *const (),
+    outer: Option<&'p Parents<'p>>,
+}
+
+impl Parents<'_> {
+    fn contains(&self, ptr: *const ()) -> bool {
+        self.ptr == ptr || self.outer.is_some_and(|outer| outer.contains(ptr))
+    }
+}
+
+struct Value<'o> {
+    obj: &'o Object,
+    parents: Option<&'o Parents<'o>>,
+}
+
+impl Value<'_> {
+    fn enter<E: ser::Error>(&self, ptr: *const ()) -> Result<Parents<'_>, E> {
+        if self.parents.is_some_and(|p| p.contains(ptr)) {
+            return Err(E::custom(format!(
+                "cannot serialize {}: it contains itself",
+                self.obj.get_type()
+            )));
+        }
+        Ok(Parents {
+            ptr,
+            outer: self.parents,
+        })
+    }
+}
+
+impl Serialize for Value<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.obj {
+            Object::Number(n) => serializer.serialize_i64(*n),
+            Object::String(s) => serializer.serialize_str(s),
+            Object::Boolean(b) => serializer.serialize_bool(*b),
+            Object::Null => serializer.serialize_unit(),
+            Object::Return(r) => Value {
+                obj: r,
+                parents: self.parents,
+            }
+            .serialize(serializer),
+            Object::Array(a) => {
+                let parents = self.enter(Rc::as_ptr(a) as *const ())?;
+                let items = a.borrow();
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for obj in items.iter() {
+                    seq.serialize_element(&Value {
+                        obj,
+                        parents: Some(&parents),
+                    })?;
+                }
+                seq.end()
+            }
+            Object::Hash(h) => {
+                let parents = self.enter(Rc::as_ptr(h) as *const ())?;
+                let pairs = h.borrow();
+                let mut map = serializer.serialize_map(Some(pairs.len()))?;
+                for (key, obj) in pairs.iter() {
+                    map.serialize_entry(
+                        key,
+                        &Value {
+                            obj,
+                            parents: Some(&parents),
+                        },
+                    )?;
+                }
+                map.end()
+            }
+            obj => Err(ser::Error::custom(format!(
+                "cannot serialize {}, only data values can be serialized",
+                obj.get_type()
+            ))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Object {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ObjectVisitor)
+    }
+}
+
+struct ObjectVisitor;
+
+impl<'de> Visitor<'de> for ObjectVisitor {
+    type Value = Object;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an integer, string, boolean, null, array or map")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Object, E> {
+        Ok(Object::Boolean(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Object, E> {
+        Ok(Object::Number(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Object, E> {
+        match i64::try_from(v) {
+            Ok(n) => Ok(Object::Number(n)),
+            Err(_) => Err(E::custom(format!("number {} is out of range", v))),
+        }
+    }
+
+    // Numbers are integers, `2.0` is accepted but `2.5` is not
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Object, E> {
+        if v.fract() == 0.0 && v >= i64::MIN as f64 && v < i64::MAX as f64 {
+            return Ok(Object::Number(v as i64));
+        }
+        Err(E::custom(format!("number {} is not an integer", v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Object, E> {
+        Ok(Object::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Object, E> {
+        Ok(Object::String(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Object, E> {
+        Ok(Object::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Object, E> {
+        Ok(Object::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Object, D::Error> {
+        Object::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Object, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(obj) = seq.next_element()? {
+            items.push(obj);
+        }
+        Ok(Object::Array(Rc::new(RefCell::new(items))))
+    }
+
+    // Hash keys are strings, other key types are rejected by the deserializer
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Object, A::Error> {
+        let mut pairs = BTreeMap::new();
+        while let Some((key, obj)) = map.next_entry::<String, Object>()? {
+            pairs.insert(key, obj);
+        }
+        Ok(Object::Hash(Rc::new(RefCell::new(pairs))))
+    }
+}
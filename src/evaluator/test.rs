@@ -1,14 +1,22 @@
 #[cfg(test)]
 mod tests {
 
-    use std::{cell::RefCell, rc::Rc};
+    use std::{collections::HashMap, thread, time::Duration};
 
     use crate::{
         ast::ast::{Node, NodeTrait, Statement},
         errors::eval_errs::EvalErr,
-        evaluator::{environment::Environment, eval::*, object::Object},
+        evaluator::{
+            builtin::{builtin_doc, BUILTINS},
+            convert::{FromObject, IntoObject},
+            environment::Environment,
+            eval::*,
+            object::Object,
+            runtime::{Limits, Runtime},
+        },
         lexer::lexer::Lexer,
         parser::parser::Parser,
+        utils::shared::{Rc, RefCell},
     };
 
     fn test_eval(input: &str) -> Result<Object, EvalErr> {
@@ -20,6 +28,7 @@ mod tests {
         return eval(
             Node::Statement(Statement::Program(program)),
             Rc::clone(&env),
+            &mut Runtime::new(),
         );
     }
 
@@ -165,6 +174,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_environment_names() {
+        let outer = Rc::new(RefCell::new(Environment::new()));
+        outer.borrow_mut().set("b".into(), Object::Number(1));
+        outer.borrow_mut().set("a".into(), Object::Number(1));
+        let mut inner = Environment::new_with_outer(Rc::clone(&outer));
+        inner.set("c".into(), Object::Null);
+        inner.set("a".into(), Object::Null);
+        assert_eq!(inner.names(), vec!["a", "b", "c"]);
+        assert_eq!(outer.borrow().names(), vec!["a", "b"]);
+    }
+
     #[test]
     fn test_let_stmt() {
         let test = vec![
@@ -339,6 +360,113 @@ mod tests {
             let obj = test_eval(input);
             assert_eq!(obj.unwrap(), expected);
         }
+
+        // exit unwinds from any depth
+        let tests = vec![
+            ("exit()", 0),
+            ("exit(3); 1", 3),
+            (
+                "let f = fn() { map([1], fn(x) { exit(x + 1) }) }; f(); 5",
+                2,
+            ),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let err = test_eval(input).unwrap_err();
+            assert!(
+                matches!(err, EvalErr::Exit(code) if code == expected),
+                "{}",
+                input
+            );
+        }
+        let err = test_eval("exit(256)").unwrap_err();
+        assert!(err.match_err(EvalErr::ExitCode(0)));
+        let err = test_eval("exit(1, 2)").unwrap_err();
+        assert!(err.match_err(EvalErr::ArgsCount(0, 0)));
+    }
+
+    #[test]
+    fn test_builtin_docs() {
+        for name in BUILTINS.keys() {
+            assert!(builtin_doc(name).is_some(), "{}", name);
+        }
+        let describe = |name: &str| BUILTINS[name].1.describe(name);
+        assert_eq!(describe("len"), "len(String|Array|Hash)");
+        assert_eq!(describe("print"), "print(...Any)");
+        assert_eq!(describe("exit"), "exit(Number?)");
+        assert_eq!(describe("input"), "input(String?)");
+        assert_eq!(describe("reduce"), "reduce(Array, Function, Any)");
+    }
+
+    #[test]
+    fn test_higher_order_builtins() {
+        let tests = vec![
+            ("map([1, 2, 3], fn(x) { x * 2 })", "[2, 4, 6]"),
+            ("map([[1], [], [1, 2]], len)", "[1, 0, 2]"),
+            ("filter([1, 2, 3, 4], fn(x) { x > 2 })", "[3, 4]"),
+            ("reduce([1, 2, 3], fn(acc, x) { acc + x }, 10)", "16"),
+            (r#"reduce(["a", "b"], fn(acc, x) { x + acc }, "")"#, "ba"),
+            (
+                "let s = [0]; each([1, 2], fn(x) { s[0] = s[0] + x }); s[0]",
+                "3",
+            ),
+            ("any([1, 2], fn(x) { x > 1 })", "true"),
+            ("any([], fn(x) { true })", "false"),
+            ("all([1, 2], fn(x) { x > 1 })", "false"),
+            ("all([], fn(x) { false })", "true"),
+            ("find([1, 2, 3], fn(x) { x > 1 })", "2"),
+            ("find([1], fn(x) { x > 1 })", "Null"),
+            ("flat_map([1, 2], fn(x) { [x, x * 10] })", "[1, 10, 2, 20]"),
+            ("flat_map([1, [2]], fn(x) { x })", "[1, 2]"),
+            ("sort_by([3, 1, 2], fn(x) { x })", "[1, 2, 3]"),
+            (
+                r#"sort_by([[2, "b"], [1, "c"], [2, "a"]], fn(x) { x[0] })"#,
+                "[[1, c], [2, b], [2, a]]",
+            ),
+            (r#"sort_by(["bb", "a"], fn(x) { x })"#, "[a, bb]"),
+            // closures see their environment
+            ("let k = 3; map([1], fn(x) { x + k })", "[4]"),
+            // callbacks may mutate the iterated array
+            ("let a = [1, 2]; map(a, fn(x) { push(a, x) }); len(a)", "4"),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let obj = test_eval(input).unwrap();
+            assert_eq!(obj.to_string(), expected, "{}", input);
+        }
+
+        let tests = vec![
+            (
+                "map([1], 1)",
+                EvalErr::BuiltinArgsType("".into(), "".into(), "".into()),
+            ),
+            (
+                "map(1, fn(x) { x })",
+                EvalErr::BuiltinArgsType("".into(), "".into(), "".into()),
+            ),
+            ("reduce([1], fn(a, b) { a })", EvalErr::ArgsCount(0, 0)),
+            ("map([1], fn(x) { x / 0 })", EvalErr::DivideByZero),
+            (
+                "sort_by([1, \"a\"], fn(x) { x })",
+                EvalErr::Order("".into(), "".into()),
+            ),
+            ("map([1], fn(a, b) { a })", EvalErr::ArgsCount(0, 0)),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let err = test_eval(input).unwrap_err();
+            assert!(err.match_err(expected), "{}", input);
+        }
+
+        // callbacks count against the call depth
+        let input = "let f = fn(n) { if (n == 0) { 0 } else { map([n - 1], f)[0] } }; f(100)";
+        let err = test_eval_with_limits(
+            input,
+            Limits {
+                max_depth: 50,
+                ..Limits::default()
+            },
+        )
+        .unwrap_err();
+        assert!(err.match_err(EvalErr::StackOverflow(0, vec![])));
+        assert!(err.to_string().contains("<map callback> <- map"), "{}", err);
     }
 
     #[test]
@@ -365,6 +493,161 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hash() {
+        let tests = vec![
+            (r#"{"a": 1, "b": 2}["b"]"#, Object::Number(2)),
+            (
+                r#"let k = "x"; {k: 1 + 1, "y": [1]}["x"]"#,
+                Object::Number(2),
+            ),
+            (r#"{"a": 1}["missing"]"#, Object::Null),
+            (r#"let p = {"x": {"y": 3}}; p.x.y"#, Object::Number(3)),
+            (
+                r#"let h = {}; h["a"] = 5; h["a"] = h["a"] + 1; h.a"#,
+                Object::Number(6),
+            ),
+            (r#"len({"a": 1, "b": 2})"#, Object::Number(2)),
+            (
+                r#"let h = {"b": true, "a": "s"}; h"#,
+                Object::String(r#"{"a": s, "b": true}"#.into()),
+            ),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let obj = test_eval(input).unwrap();
+            match expected {
+                Object::String(s) => assert_eq!(obj.to_string(), s, "{}", input),
+                expected => assert_eq!(obj, expected, "{}", input),
+            }
+        }
+
+        let tests = vec![
+            ("{1: 2}", EvalErr::HashKey("".into())),
+            (r#"let h = {}; h[1] = 2"#, EvalErr::HashKey("".into())),
+            ("1[0]", EvalErr::IndexArray("".into())),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let err = test_eval(input).unwrap_err();
+            assert!(err.match_err(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let tests = vec![
+            ("42", "42"),
+            (r#""hi""#, r#""hi""#),
+            (r#"let h = {}; h["null"]"#, "null"),
+            (
+                r#"let h = {}; [1, "a", true, h["null"]]"#,
+                r#"[1,"a",true,null]"#,
+            ),
+            (
+                r#"let h = {}; {"b": [1, {"c": 2}], "a": h["null"]}"#,
+                r#"{"a":null,"b":[1,{"c":2}]}"#,
+            ),
+            // shared but not cyclic references are written out at each use
+            ("let a = [1]; [a, a]", "[[1],[1]]"),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let obj = test_eval(input).unwrap();
+            assert_eq!(serde_json::to_string(&obj).unwrap(), expected, "{}", input);
+            let back: Object = serde_json::from_str(expected).unwrap();
+            assert_eq!(back, obj, "{}", input);
+        }
+
+        let tests = vec![
+            ("fn(x) { x }", "cannot serialize Function"),
+            ("len", "cannot serialize Builtin"),
+            ("[1, fn() { 1 }]", "cannot serialize Function"),
+            (
+                "let a = [1]; push(a, a); a",
+                "cannot serialize Array: it contains itself",
+            ),
+            (
+                r#"let h = {}; h["h"] = [h]; h"#,
+                "cannot serialize Hash: it contains itself",
+            ),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let obj = test_eval(input).unwrap();
+            let err = serde_json::to_string(&obj).unwrap_err().to_string();
+            assert!(err.starts_with(expected), "{}: {}", input, err);
+        }
+
+        assert_eq!(
+            serde_json::from_str::<Object>("2.0").unwrap(),
+            Object::Number(2)
+        );
+        for input in ["2.5", "18446744073709551615"] {
+            assert!(serde_json::from_str::<Object>(input).is_err(), "{}", input);
+        }
+    }
+
+    #[derive(Debug, PartialEq, IntoObject, FromObject)]
+    struct Point {
+        x: i64,
+        y: i64,
+    }
+
+    #[derive(Debug, PartialEq, IntoObject, FromObject)]
+    struct Shape {
+        name: String,
+        points: Vec<Point>,
+        closed: Option<bool>,
+    }
+
+    #[test]
+    fn test_conversions() {
+        let obj = (1, "a", vec![true], None::<i64>).into_object();
+        assert_eq!(obj.to_string(), "[1, a, [true], Null]");
+        let back: (i64, String, Vec<bool>, Option<i64>) = FromObject::from_object(&obj).unwrap();
+        assert_eq!(back, (1, "a".into(), vec![true], None));
+
+        let map = HashMap::from([("k".to_string(), vec![1, 2])]);
+        let obj = map.clone().into_object();
+        assert_eq!(HashMap::<String, Vec<i64>>::from_object(&obj).unwrap(), map);
+
+        let shape = Shape {
+            name: "line".into(),
+            points: vec![Point { x: 0, y: 1 }, Point { x: 2, y: 3 }],
+            closed: None,
+        };
+        let obj = test_eval(r#"{"name": "line", "points": [{"x": 0, "y": 1}, {"x": 2, "y": 3}]}"#)
+            .unwrap();
+        assert_eq!(Shape::from_object(&obj).unwrap(), shape);
+        let obj = shape.into_object();
+        assert_eq!(
+            obj.to_string(),
+            r#"{"closed": Null, "name": line, "points": [{"x": 0, "y": 1}, {"x": 2, "y": 3}]}"#
+        );
+
+        let tests = vec![
+            (Object::String("1".into()), "expected Number, got String"),
+            (
+                vec![1].into_object(),
+                "expected Array of length 2, got Array of length 1",
+            ),
+            (
+                test_eval(r#"{"name": "l", "points": [{"x": 0, "y": 1}, {"x": "2"}]}"#).unwrap(),
+                "expected Number at points[1].x, got String",
+            ),
+            (
+                test_eval(r#"{"name": "l", "points": [], "closed": 1}"#).unwrap(),
+                "expected Boolean at closed, got Number",
+            ),
+        ];
+        for (obj, expected) in tests.into_iter() {
+            let err = match obj {
+                Object::String(_) => i64::from_object(&obj).unwrap_err(),
+                Object::Array(_) => <(i64, i64)>::from_object(&obj).unwrap_err(),
+                _ => Shape::from_object(&obj).unwrap_err(),
+            };
+            assert!(err.match_err(EvalErr::CoerceObject("".into(), "".into())));
+            assert_eq!(err.to_string(), format!("Object mismatch, {}", expected));
+        }
+    }
+
     #[test]
     fn test_extended_functions() {
         let tests = vec![
@@ -455,4 +738,137 @@ mod tests {
             assert_eq!(obj, expected);
         }
     }
+
+    #[test]
+    fn test_stack_overflow() {
+        let input = "let f = fn(n) { f(n + 1) }; f(0)";
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let mut rt = Runtime::with_limits(Limits {
+            max_depth: 50,
+            ..Limits::default()
+        });
+        let obj = eval(
+            Node::Statement(Statement::Program(program)),
+            Rc::clone(&env),
+            &mut rt,
+        );
+        match obj {
+            Err(EvalErr::StackOverflow(depth, names)) => {
+                assert_eq!(depth, 50);
+                assert_eq!(names, vec!["f"; 5]);
+            }
+            anything => panic!("{:?}", anything),
+        }
+        // frames are unwound and the environment is still usable
+        assert_eq!(rt.depth(), 0);
+        let program = Parser::new(Lexer::new("f")).parse_program().unwrap();
+        let obj = eval(Node::Statement(Statement::Program(program)), env, &mut rt);
+        assert!(matches!(obj, Ok(Object::Function(_))));
+
+        // the default limit triggers before the Rust stack runs out
+        let obj = test_eval("let g = fn() { g() }; g()");
+        assert!(obj
+            .unwrap_err()
+            .match_err(EvalErr::StackOverflow(0, vec![])));
+        let obj = test_eval("let h = fn(n) { if (n == 0) { 0 } else { 1 + h(n - 1) } }; h(400)");
+        assert_eq!(obj.unwrap(), Object::Number(400));
+    }
+
+    fn test_eval_with_limits(input: &str, limits: Limits) -> Result<Object, EvalErr> {
+        let program = Parser::new(Lexer::new(input)).parse_program()?;
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let mut rt = Runtime::with_limits(limits);
+        return eval(Node::Statement(Statement::Program(program)), env, &mut rt);
+    }
+
+    #[test]
+    fn test_limits() {
+        let steps = Limits {
+            max_steps: Some(1000),
+            ..Limits::default()
+        };
+        let timeout = Limits {
+            timeout: Some(Duration::from_millis(50)),
+            ..Limits::default()
+        };
+        let memory = Limits {
+            max_alloc: Some(1024),
+            ..Limits::default()
+        };
+        let tests = vec![
+            (
+                "let f = fn(n) { f(n) }; f(0)",
+                steps.clone(),
+                EvalErr::StepLimit(0),
+            ),
+            (
+                "let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) + f(n - 1) } }; f(64)",
+                timeout.clone(),
+                EvalErr::Timeout(Duration::ZERO),
+            ),
+            (
+                r#""a" * 1000000000"#,
+                memory.clone(),
+                EvalErr::MemoryLimit(0),
+            ),
+            (
+                "let a = []; let f = fn() { push(a, 1); f() }; f()",
+                memory.clone(),
+                EvalErr::MemoryLimit(0),
+            ),
+        ];
+        for (input, limits, expected) in tests.into_iter() {
+            let err = test_eval_with_limits(input, limits).unwrap_err();
+            assert!(err.match_err(expected), "{:?}", err);
+        }
+
+        // untrusted scripts fail with an error instead of a panic
+        let tests = vec![
+            ("let a = [1]; a[5]", EvalErr::IndexOutOfBounds(5, 1)),
+            ("let a = [1]; a[-1]", EvalErr::IndexOutOfBounds(-1, 1)),
+            ("[][0]", EvalErr::IndexOutOfBounds(0, 0)),
+            ("let a = [1]; a[5] = 2", EvalErr::IndexOutOfBounds(5, 1)),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let err = test_eval_with_limits(input, Limits::default()).unwrap_err();
+            assert_eq!(err.to_string(), expected.to_string(), "{}", input);
+        }
+
+        let tests = vec![
+            ("1 + 2 * 3", steps, Object::Number(7)),
+            (r#""ab" * 3"#, memory, Object::String("ababab".into())),
+        ];
+        for (input, limits, expected) in tests.into_iter() {
+            let obj = test_eval_with_limits(input, limits).unwrap();
+            assert_eq!(obj, expected);
+        }
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let input = "let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) + f(n - 1) } }; f(64)";
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let mut rt = Runtime::new();
+
+        let handle = rt.interrupt_handle();
+        let interrupter = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            handle.interrupt();
+        });
+        let obj = eval(
+            Node::Statement(Statement::Program(program)),
+            Rc::clone(&env),
+            &mut rt,
+        );
+        interrupter.join().unwrap();
+        assert!(obj.unwrap_err().match_err(EvalErr::Interrupted));
+
+        // the interrupt is consumed and the session keeps its bindings
+        assert!(!rt.interrupt_handle().is_interrupted());
+        let program = Parser::new(Lexer::new("f(2)")).parse_program().unwrap();
+        let obj = eval(Node::Statement(Statement::Program(program)), env, &mut rt);
+        assert_eq!(obj.unwrap(), Object::Number(0));
+    }
 }
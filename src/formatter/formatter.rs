@@ -0,0 +1,617 @@
+use crate::{
+    ast::ast::{BlockStatement, Expression, IfExpression, Span, Statement},
+    errors::parser_errs::ParseErr,
+    lexer::{
+        lexer::{comments, Lexer},
+        token::TOKEN,
+    },
+    parser::parser::{Parser, Precedence},
+};
+
+// Binds tighter than any operator, literals and `if`/`fn` never need parens
+const ATOM: i32 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub indent: usize, // spaces per level
+    pub width: usize,  // lines longer than this are broken where possible
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            width: 100,
+        }
+    }
+}
+
+// The canonical layout of a program. Comments are kept: a comment inside a
+// statement, but outside its blocks, moves to the line above it.
+pub fn format(src: &str, options: FormatOptions) -> Result<String, ParseErr> {
+    let program = Parser::new(Lexer::new(src)).parse_program()?;
+    let mut printer = Printer::new(src, options);
+    if src.starts_with("#!") {
+        printer
+            .out
+            .push_str(src.lines().next().unwrap_or("").trim_end());
+        printer.out.push('\n');
+    }
+    printer.statements(&program.statements, src.len(), false);
+
+    let mut out = printer.out;
+    out.truncate(out.trim_end().len());
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+struct Printer<'a> {
+    src: &'a str,
+    options: FormatOptions,
+    comments: Vec<Span>,
+    printed: Vec<bool>,
+    out: String,
+    level: usize,
+}
+
+impl<'a> Printer<'a> {
+    fn new(src: &'a str, options: FormatOptions) -> Self {
+        let comments = comments(src);
+        Self {
+            src,
+            options,
+            printed: vec![false; comments.len()],
+            comments,
+            out: String::new(),
+            level: 0,
+        }
+    }
+
+    // One statement per line. `end` is where the enclosing block closes, the
+    // last expression of a block is its value and takes no `;`.
+    fn statements(&mut self, stmts: &[Statement], end: usize, in_block: bool) {
+        let stmts: Vec<&Statement> = stmts.iter().filter(|x| !is_empty(x)).collect();
+        let mut prev = None;
+        // where a `;` goes if the next statement would continue the last `if`
+        let mut pending_semicolon = None;
+        for (idx, stmt) in stmts.iter().enumerate() {
+            let span = stmt.span();
+            let blocks = self.stmt_blocks(stmt);
+            for comment in self.pending(|c| {
+                c.start < span.start
+                    || (c.start < span.end && !blocks.iter().any(|b| b.contains(c.start)))
+            }) {
+                // a comment moved out of the statement goes where it starts
+                self.comment(comment, comment.start.min(span.start), &mut prev);
+            }
+            self.blank_line(&prev, span.start);
+            self.indent();
+
+            let begin = self.out.len();
+            let last = in_block && idx == stmts.len() - 1;
+            let ends_with_block = self.statement(stmt, last);
+            if let Some(at) = pending_semicolon.take() {
+                if self.out[begin..].starts_with(['(', '[', '-']) {
+                    self.out.insert(at, ';');
+                }
+            }
+            if ends_with_block && !last {
+                pending_semicolon = Some(self.out.len());
+            }
+
+            prev = Some(span.end);
+            self.trailing_comment(span.end, end, &mut prev);
+            self.out.push('\n');
+        }
+        for comment in self.pending(|c| c.start < end) {
+            self.comment(comment, comment.start, &mut prev);
+        }
+    }
+
+    // Returns whether the statement ends with a block and took no `;`
+    fn statement(&mut self, stmt: &Statement, last: bool) -> bool {
+        match stmt {
+            Statement::Let(stmt) => {
+                self.out.push_str(&format!("let {} = ", stmt.name.0));
+                self.expr(&stmt.value, 1);
+                self.out.push(';');
+            }
+            Statement::Reassign(stmt) => {
+                self.expr(&stmt.lhs, 0);
+                self.out.push_str(" = ");
+                self.expr(&stmt.value, 1);
+                self.out.push(';');
+            }
+            Statement::Return(stmt) => {
+                self.out.push_str("return");
+                if let Some(expression) = &stmt.expression {
+                    self.out.push(' ');
+                    self.expr(expression, 1);
+                }
+                self.out.push(';');
+            }
+            Statement::Expression(stmt) => {
+                let Some(expression) = &stmt.expression else {
+                    return false;
+                };
+                let reserve = match last {
+                    true => 0,
+                    false => 1,
+                };
+                self.expr(expression, reserve);
+                if matches!(expression, Expression::If(_) | Expression::Function(_)) {
+                    return true;
+                }
+                if !last {
+                    self.out.push(';');
+                }
+            }
+            Statement::Block(block) => {
+                self.block(block);
+                return true;
+            }
+            Statement::Program(program) => {
+                self.statements(&program.statements, self.src.len(), false);
+            }
+        }
+        false
+    }
+
+    // Flat when it fits on the line, `reserve` is the text that follows it
+    fn expr(&mut self, expression: &Expression, reserve: usize) {
+        match self.flat(expression) {
+            Some(flat) if self.fits(flat.chars().count() + reserve) => self.out.push_str(&flat),
+            _ => self.broken(expression, reserve),
+        }
+    }
+
+    fn operand(&mut self, expression: &Expression, parens: bool, reserve: usize) {
+        if parens {
+            self.out.push('(');
+            self.expr(expression, reserve + 1);
+            self.out.push(')');
+        } else {
+            self.expr(expression, reserve);
+        }
+    }
+
+    // Spread over several lines, one element or argument per line
+    fn broken(&mut self, expression: &Expression, reserve: usize) {
+        match expression {
+            Expression::ArrayLiteral(arr) => {
+                self.out.push('[');
+                self.lines(&arr.elements, |printer, x| printer.expr(x, 1));
+                self.out.push(']');
+            }
+            Expression::HashLiteral(hash) => {
+                self.out.push('{');
+                self.lines(&hash.pairs, |printer, (k, v)| {
+                    printer.expr(k, 2);
+                    printer.out.push_str(": ");
+                    printer.expr(v, 1);
+                });
+                self.out.push('}');
+            }
+            Expression::Call(call) => {
+                let parens = precedence(&call.function) < Precedence::INDEX.order();
+                self.operand(&call.function, parens, 1);
+                self.call_args(&call.arguments, reserve);
+            }
+            Expression::Index(index) => {
+                let parens = precedence(&index.left) < Precedence::INDEX.order();
+                self.operand(&index.left, parens, 1);
+                self.out.push('[');
+                self.expr(&index.index, reserve + 1);
+                self.out.push(']');
+            }
+            Expression::Member(member) => {
+                let parens = precedence(&member.object) < Precedence::INDEX.order();
+                let property = member.property.0.chars().count() + 1;
+                self.operand(&member.object, parens, property + reserve);
+                self.out.push('.');
+                self.out.push_str(&member.property.0);
+            }
+            Expression::Prefix(prefix) => {
+                self.out.push_str(&prefix.token.literal());
+                let parens = precedence(&prefix.right) < Precedence::PREFIX.order();
+                self.operand(&prefix.right, parens, reserve);
+            }
+            Expression::Infix(infix) => {
+                let order = precedence(expression);
+                let operator = infix.operator.literal();
+                let left = precedence(&infix.left) < order;
+                self.operand(&infix.left, left, operator.len() + 2);
+                self.out.push_str(&format!(" {} ", operator));
+                let right = precedence(&infix.right) <= order;
+                self.operand(&infix.right, right, reserve);
+            }
+            Expression::If(if_exp) => self.if_expression(if_exp),
+            Expression::Function(func) => {
+                let params = func
+                    .parameters
+                    .iter()
+                    .map(|x| x.0.as_str())
+                    .collect::<Vec<&str>>();
+                self.out.push_str(&format!("fn({}) ", params.join(", ")));
+                self.block(&func.body);
+            }
+            atom => self.out.push_str(&flat_atom(atom)),
+        }
+    }
+
+    // Arguments hug the parens when only the last one needs several lines,
+    // as with a callback: `map(xs, fn(x) {`
+    fn call_args(&mut self, args: &[Expression], reserve: usize) {
+        if let Some((last, init)) = args.split_last() {
+            let init = init
+                .iter()
+                .map(|x| self.flat(x))
+                .collect::<Option<Vec<String>>>();
+            let hugs = matches!(
+                last,
+                Expression::Function(_) | Expression::ArrayLiteral(_) | Expression::HashLiteral(_)
+            );
+            if let (Some(init), true) = (init, hugs) {
+                let mut head = String::from("(");
+                for arg in init {
+                    head.push_str(&arg);
+                    head.push_str(", ");
+                }
+                if self.fits(head.chars().count() + 1) {
+                    self.out.push_str(&head);
+                    self.expr(last, reserve + 1);
+                    self.out.push(')');
+                    return;
+                }
+            }
+        }
+        self.out.push('(');
+        self.lines(args, |printer, x| printer.expr(x, 1));
+        self.out.push(')');
+    }
+
+    // Items on their own lines, one level deeper, separated by commas
+    fn lines<T>(&mut self, items: &[T], mut print: impl FnMut(&mut Self, &T)) {
+        if items.is_empty() {
+            return;
+        }
+        self.level += 1;
+        for (idx, item) in items.iter().enumerate() {
+            self.out.push('\n');
+            self.indent();
+            print(self, item);
+            if idx < items.len() - 1 {
+                self.out.push(',');
+            }
+        }
+        self.level -= 1;
+        self.out.push('\n');
+        self.indent();
+    }
+
+    fn if_expression(&mut self, if_exp: &IfExpression) {
+        self.out.push_str("if (");
+        self.expr(&if_exp.condition, 3);
+        self.out.push_str(") ");
+        self.block(&if_exp.consequence);
+        if let Some(alternative) = &if_exp.alternative {
+            self.out.push_str(" else ");
+            match self.else_if(alternative) {
+                Some(else_if) => self.if_expression(else_if),
+                None => self.block(alternative),
+            }
+        }
+    }
+
+    fn block(&mut self, block: &BlockStatement) {
+        if let Some(flat) = self.flat_block(block) {
+            if self.fits(flat.chars().count()) {
+                self.out.push_str(&flat);
+                return;
+            }
+        }
+        self.out.push_str("{\n");
+        self.level += 1;
+        self.statements(&block.statements, block.span.end.saturating_sub(1), true);
+        self.level -= 1;
+        self.indent();
+        self.out.push('}');
+    }
+
+    // The one-line form, `None` when the expression holds a block that
+    // cannot be written on one line
+    fn flat(&self, expression: &Expression) -> Option<String> {
+        let flat = match expression {
+            Expression::ArrayLiteral(arr) => {
+                let elements = self.flat_list(&arr.elements)?;
+                format!("[{}]", elements)
+            }
+            Expression::HashLiteral(hash) => {
+                let pairs = hash
+                    .pairs
+                    .iter()
+                    .map(|(k, v)| Some(format!("{}: {}", self.flat(k)?, self.flat(v)?)))
+                    .collect::<Option<Vec<String>>>()?;
+                format!("{{{}}}", pairs.join(", "))
+            }
+            Expression::Call(call) => {
+                let parens = precedence(&call.function) < Precedence::INDEX.order();
+                let function = self.flat_operand(&call.function, parens)?;
+                format!("{}({})", function, self.flat_list(&call.arguments)?)
+            }
+            Expression::Index(index) => {
+                let parens = precedence(&index.left) < Precedence::INDEX.order();
+                let left = self.flat_operand(&index.left, parens)?;
+                format!("{}[{}]", left, self.flat(&index.index)?)
+            }
+            Expression::Member(member) => {
+                let parens = precedence(&member.object) < Precedence::INDEX.order();
+                let object = self.flat_operand(&member.object, parens)?;
+                format!("{}.{}", object, member.property.0)
+            }
+            Expression::Prefix(prefix) => {
+                let parens = precedence(&prefix.right) < Precedence::PREFIX.order();
+                let right = self.flat_operand(&prefix.right, parens)?;
+                format!("{}{}", prefix.token.literal(), right)
+            }
+            Expression::Infix(infix) => {
+                let order = precedence(expression);
+                let left = self.flat_operand(&infix.left, precedence(&infix.left) < order)?;
+                let right = self.flat_operand(&infix.right, precedence(&infix.right) <= order)?;
+                format!("{} {} {}", left, infix.operator.literal(), right)
+            }
+            Expression::If(if_exp) => self.flat_if(if_exp)?,
+            Expression::Function(func) => {
+                let params = func
+                    .parameters
+                    .iter()
+                    .map(|x| x.0.as_str())
+                    .collect::<Vec<&str>>();
+                format!("fn({}) {}", params.join(", "), self.flat_block(&func.body)?)
+            }
+            atom => flat_atom(atom),
+        };
+        Some(flat)
+    }
+
+    fn flat_operand(&self, expression: &Expression, parens: bool) -> Option<String> {
+        let flat = self.flat(expression)?;
+        match parens {
+            true => Some(format!("({})", flat)),
+            false => Some(flat),
+        }
+    }
+
+    fn flat_list(&self, expressions: &[Expression]) -> Option<String> {
+        let flat = expressions
+            .iter()
+            .map(|x| self.flat(x))
+            .collect::<Option<Vec<String>>>()?;
+        Some(flat.join(", "))
+    }
+
+    fn flat_if(&self, if_exp: &IfExpression) -> Option<String> {
+        let mut flat = format!(
+            "if ({}) {}",
+            self.flat(&if_exp.condition)?,
+            self.flat_block(&if_exp.consequence)?
+        );
+        if let Some(alternative) = &if_exp.alternative {
+            let alternative = match self.else_if(alternative) {
+                Some(else_if) => self.flat_if(else_if)?,
+                None => self.flat_block(alternative)?,
+            };
+            flat.push_str(" else ");
+            flat.push_str(&alternative);
+        }
+        Some(flat)
+    }
+
+    // `{}` or `{ expr }`, blocks holding comments always span lines
+    fn flat_block(&self, block: &BlockStatement) -> Option<String> {
+        if self.has_comments(block.span) {
+            return None;
+        }
+        let stmts: Vec<&Statement> = block.statements.iter().filter(|x| !is_empty(x)).collect();
+        match stmts.as_slice() {
+            [] => Some("{}".to_string()),
+            [Statement::Expression(stmt)] => {
+                let expression = stmt.expression.as_ref()?;
+                Some(format!("{{ {} }}", self.flat(expression)?))
+            }
+            _ => None,
+        }
+    }
+
+    // The `if` of an `else if`. A block written `else { if .. }` is printed
+    // the same way, unless comments sit around the `if`.
+    fn else_if<'b>(&self, block: &'b BlockStatement) -> Option<&'b IfExpression> {
+        let [Statement::Expression(stmt)] = block.statements.as_slice() else {
+            return None;
+        };
+        let Some(Expression::If(if_exp)) = &stmt.expression else {
+            return None;
+        };
+        let before = Span::new(block.span.start, stmt.span.start);
+        let after = Span::new(stmt.span.end, block.span.end);
+        match self.has_comments(before) || self.has_comments(after) {
+            true => None,
+            false => Some(if_exp),
+        }
+    }
+
+    // Spans of the blocks printed by `statements`, the comments inside them
+    // are left to it
+    fn stmt_blocks(&self, stmt: &Statement) -> Vec<Span> {
+        let mut blocks = vec![];
+        match stmt {
+            Statement::Let(stmt) => self.blocks(&stmt.value, &mut blocks),
+            Statement::Reassign(stmt) => {
+                self.blocks(&stmt.lhs, &mut blocks);
+                self.blocks(&stmt.value, &mut blocks);
+            }
+            Statement::Return(stmt) => {
+                if let Some(expression) = &stmt.expression {
+                    self.blocks(expression, &mut blocks);
+                }
+            }
+            Statement::Expression(stmt) => {
+                if let Some(expression) = &stmt.expression {
+                    self.blocks(expression, &mut blocks);
+                }
+            }
+            Statement::Block(block) => blocks.push(block.span),
+            Statement::Program(_) => {}
+        }
+        blocks
+    }
+
+    fn blocks(&self, expression: &Expression, blocks: &mut Vec<Span>) {
+        match expression {
+            Expression::ArrayLiteral(arr) => {
+                arr.elements.iter().for_each(|x| self.blocks(x, blocks));
+            }
+            Expression::HashLiteral(hash) => hash.pairs.iter().for_each(|(k, v)| {
+                self.blocks(k, blocks);
+                self.blocks(v, blocks);
+            }),
+            Expression::Index(index) => {
+                self.blocks(&index.left, blocks);
+                self.blocks(&index.index, blocks);
+            }
+            Expression::Member(member) => self.blocks(&member.object, blocks),
+            Expression::Prefix(prefix) => self.blocks(&prefix.right, blocks),
+            Expression::Infix(infix) => {
+                self.blocks(&infix.left, blocks);
+                self.blocks(&infix.right, blocks);
+            }
+            Expression::If(if_exp) => self.if_blocks(if_exp, blocks),
+            Expression::Function(func) => blocks.push(func.body.span),
+            Expression::Call(call) => {
+                self.blocks(&call.function, blocks);
+                call.arguments.iter().for_each(|x| self.blocks(x, blocks));
+            }
+            Expression::Identifier(_)
+            | Expression::String(_)
+            | Expression::Number(_)
+            | Expression::Bool(_) => {}
+        }
+    }
+
+    fn if_blocks(&self, if_exp: &IfExpression, blocks: &mut Vec<Span>) {
+        self.blocks(&if_exp.condition, blocks);
+        blocks.push(if_exp.consequence.span);
+        if let Some(alternative) = &if_exp.alternative {
+            match self.else_if(alternative) {
+                Some(else_if) => self.if_blocks(else_if, blocks),
+                None => blocks.push(alternative.span),
+            }
+        }
+    }
+
+    fn has_comments(&self, span: Span) -> bool {
+        self.comments
+            .iter()
+            .zip(&self.printed)
+            .any(|(c, printed)| !printed && span.contains(c.start))
+    }
+
+    // Comments not printed yet that match, marked as printed
+    fn pending(&mut self, matches: impl Fn(Span) -> bool) -> Vec<Span> {
+        let mut pending = vec![];
+        for (idx, comment) in self.comments.iter().enumerate() {
+            if !self.printed[idx] && matches(*comment) {
+                self.printed[idx] = true;
+                pending.push(*comment);
+            }
+        }
+        pending
+    }
+
+    // A comment on its own line, `at` is where it is placed in the source
+    fn comment(&mut self, comment: Span, at: usize, prev: &mut Option<usize>) {
+        self.blank_line(prev, at);
+        self.indent();
+        self.out.push_str(comment.text(self.src).trim_end());
+        self.out.push('\n');
+        *prev = Some(comment.end);
+    }
+
+    // A comment after the statement on the same line stays there
+    fn trailing_comment(&mut self, stmt_end: usize, end: usize, prev: &mut Option<usize>) {
+        let src = self.src;
+        for comment in self.pending(|c| {
+            stmt_end <= c.start && c.start < end && !src[stmt_end..c.start].contains('\n')
+        }) {
+            self.out.push(' ');
+            self.out.push_str(comment.text(src).trim_end());
+            *prev = Some(comment.end);
+        }
+    }
+
+    // Keeps one empty line where the source had at least one
+    fn blank_line(&mut self, prev: &Option<usize>, start: usize) {
+        if let Some(prev) = *prev {
+            if prev < start && self.src[prev..start].matches('\n').count() > 1 {
+                self.out.push('\n');
+            }
+        }
+    }
+
+    fn indent(&mut self) {
+        let width = self.level * self.options.indent;
+        self.out.push_str(&" ".repeat(width));
+    }
+
+    fn fits(&self, len: usize) -> bool {
+        let line = match self.out.rfind('\n') {
+            Some(idx) => &self.out[idx + 1..],
+            None => &self.out,
+        };
+        line.chars().count() + len <= self.options.width
+    }
+}
+
+fn is_empty(stmt: &Statement) -> bool {
+    matches!(stmt, Statement::Expression(x) if x.expression.is_none())
+}
+
+// Parsing order of an expression, children of lower order need parens
+fn precedence(expression: &Expression) -> i32 {
+    match expression {
+        Expression::Infix(infix) => Precedence::from_kind(infix.operator.kind()).order(),
+        Expression::Prefix(_) => Precedence::PREFIX.order(),
+        Expression::Index(_) | Expression::Member(_) => Precedence::INDEX.order(),
+        Expression::Call(_) => Precedence::CALL.order(),
+        _ => ATOM,
+    }
+}
+
+fn flat_atom(expression: &Expression) -> String {
+    match expression {
+        Expression::Identifier(ident) => ident.0.clone(),
+        Expression::String(str) => escape(str),
+        Expression::Number(num) => num.to_string(),
+        Expression::Bool(true) => TOKEN::TRUE.literal(),
+        Expression::Bool(false) => TOKEN::FALSE.literal(),
+        _ => unreachable!("not an atom: {:?}", expression),
+    }
+}
+
+// The string literal that `unescape` reads back as `str`
+pub fn escape(str: &str) -> String {
+    let mut out = String::with_capacity(str.len() + 2);
+    out.push('"');
+    for c in str.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,2 @@
+pub mod formatter;
+mod test;
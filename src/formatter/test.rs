@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::ast::Program,
+        formatter::formatter::{format, FormatOptions},
+        lexer::lexer::{comments, Lexer},
+        parser::parser::Parser,
+    };
+
+    fn parse(src: &str) -> Program {
+        Parser::new(Lexer::new(src)).parse_program().unwrap()
+    }
+
+    fn fmt(src: &str) -> String {
+        format(src, FormatOptions::default()).unwrap()
+    }
+
+    const SOURCES: [&str; 12] = [
+        "let x=1+2*3;let y = (1 + 2) * 3; x - (y - 1)",
+        "let add = fn(a,b){a+b}; add(1, 2)",
+        "let f = fn(n) { if (n < 2) { return n; } f(n - 1) + f(n - 2) }; print(f(10));",
+        "if (a == b) { 1 } else if (a > b) { 2 } else { 3 }",
+        "let h = {\"a\": [1, 2, {\"b\": -x}], \"q\\\"\\\\\\n\": !true}; h[\"a\"][2].b",
+        "let arr = [1, 2, 3]; arr[0] = 5; x = arr[0]; -(1 + 2); -f(x)[0]; (-a)[0]",
+        "map(filter([1, 2, 3, 4], fn(x) { x / 2 * 2 == x }), fn(x) { let y = x * x; y })",
+        "if (x) { 1 }\n[1]\nif (y) { 2 }\n-1",
+        "#!/usr/bin/env r-piece\nlet a = fn() { fn(x) { x } }; a()(1)",
+        "// a\nlet x = [1, // b\n2]; // c\nlet f = fn() { // d\n1 // e\n}; // f\n// g",
+        "if (a) { // a\n} else { // b\nif (b) { 1 } } // c",
+        "let s = \"a long string to push the line over the width\"; let fs = [fn(a) { let b = a; b }, fn() { 1 }, s, s];",
+    ];
+
+    #[test]
+    fn test_format() {
+        let tests = vec![
+            ("let x=1+2*3", "let x = 1 + 2 * 3;\n"),
+            ("x - (y - 1); (x - y) - 1", "x - (y - 1);\nx - y - 1;\n"),
+            ("-(1 + 2) * ((3))", "-(1 + 2) * 3;\n"),
+            ("let f=fn(a,b){a+b}", "let f = fn(a, b) { a + b };\n"),
+            ("fn(x){let y=x;y;}", "fn(x) {\n    let y = x;\n    y\n}\n"),
+            (
+                "if(a){1}else{if(b){2}else{3}}",
+                "if (a) { 1 } else if (b) { 2 } else { 3 }\n",
+            ),
+            ("x = 1; a[0] = 2;", "x = 1;\na[0] = 2;\n"),
+            ("return;", "return;\n"),
+            ("\"a\\\"b\\\\c\\n\"", "\"a\\\"b\\\\c\\n\";\n"),
+            (
+                "let x = 1;;;\n\n\n\nlet y = 2;",
+                "let x = 1;\n\nlet y = 2;\n",
+            ),
+            // `;` keeps the array from indexing the `if`
+            (
+                "if (x) { 1 }; [1]; if (x) { 1 } y",
+                "if (x) { 1 };\n[1];\nif (x) { 1 }\ny;\n",
+            ),
+            ("", ""),
+        ];
+        for (src, expected) in tests.into_iter() {
+            assert_eq!(fmt(src), expected, "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_format_width() {
+        let src =
+            "let xs = [first_value, second_value, third_value]; each(xs, fn(x) { print(x) });";
+        let options = FormatOptions {
+            indent: 2,
+            width: 30,
+        };
+        let expected = "\
+let xs = [
+  first_value,
+  second_value,
+  third_value
+];
+each(xs, fn(x) { print(x) });
+";
+        assert_eq!(format(src, options).unwrap(), expected);
+
+        let options = FormatOptions {
+            indent: 2,
+            width: 20,
+        };
+        let expected = "\
+let xs = [
+  first_value,
+  second_value,
+  third_value
+];
+each(xs, fn(x) {
+  print(x)
+});
+";
+        assert_eq!(format(src, options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let src = "\
+// header
+
+let x = 1; // one
+let f = fn(a) {
+  // inside
+  a // value
+  // end of body
+};
+let arr = [1, // hoisted
+  2];
+if (x) {
+  1
+} else { // else
+  2
+}
+let b = [1,
+  2, // inner
+  3];
+// last";
+        let expected = "\
+// header
+
+let x = 1; // one
+let f = fn(a) {
+    // inside
+    a // value
+    // end of body
+};
+// hoisted
+let arr = [1, 2];
+if (x) { 1 } else {
+    // else
+    2
+}
+// inner
+let b = [1, 2, 3];
+// last
+";
+        assert_eq!(fmt(src), expected);
+        assert_eq!(fmt("fn() { // todo\n}"), "fn() {\n    // todo\n}\n");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let options = [
+            FormatOptions::default(),
+            FormatOptions {
+                indent: 2,
+                width: 16,
+            },
+        ];
+        for src in SOURCES.iter() {
+            for options in options.iter() {
+                let formatted = format(src, *options).unwrap();
+                assert_eq!(parse(&formatted), parse(src), "{}\n{}", src, formatted);
+                // idempotent
+                assert_eq!(format(&formatted, *options).unwrap(), formatted);
+                // no comment is lost, some may move
+                let texts = |src: &str| {
+                    let mut texts = comments(src)
+                        .iter()
+                        .map(|x| x.text(src).trim_end().to_string())
+                        .collect::<Vec<String>>();
+                    texts.sort();
+                    texts
+                };
+                assert_eq!(texts(&formatted), texts(src));
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::{fs, path::Path};
+
+use crate::{
+    ast::ast::{Node, Program, Statement},
+    errors::{eval_errs::EvalErr, interpreter_errs::InterpreterErr},
+    evaluator::{
+        builtin::{BuiltinRegistry, Context, Signature},
+        convert::{FromObject, IntoArgs},
+        environment::{Env, Environment},
+        eval::{apply_function, eval},
+        hook::EvalHook,
+        host::Host,
+        io::ScriptIo,
+        object::Object,
+        runtime::{InterruptHandle, Limits, Rebind, Runtime},
+    },
+    lexer::lexer::Lexer,
+    optimizer::optimizer::optimize,
+    parser::parser::Parser,
+    utils::shared::{MaybeSend, MaybeSync, Rc, RefCell},
+};
+
+// What `Interpreter::call` invokes: a global or builtin by name, or a
+// function value obtained from an earlier evaluation
+#[derive(Debug, Clone)]
+pub enum Callee {
+    Name(String),
+    Object(Object),
+}
+
+impl From<&str> for Callee {
+    fn from(name: &str) -> Self {
+        Callee::Name(name.to_string())
+    }
+}
+impl From<String> for Callee {
+    fn from(name: String) -> Self {
+        Callee::Name(name)
+    }
+}
+impl From<Object> for Callee {
+    fn from(obj: Object) -> Self {
+        Callee::Object(obj)
+    }
+}
+impl From<&Object> for Callee {
+    fn from(obj: &Object) -> Self {
+        Callee::Object(obj.clone())
+    }
+}
+
+// Embeddable Lexer -> Parser -> eval pipeline. Bindings of the root
+// environment persist across calls, like a REPL session.
+pub struct Interpreter {
+    env: Env,
+    rt: Runtime,
+    optimize: bool,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::with_limits(Limits::default())
+    }
+    pub fn with_limits(limits: Limits) -> Self {
+        Self {
+            env: Rc::new(RefCell::new(Environment::new())),
+            rt: Runtime::with_limits(limits),
+            optimize: true,
+        }
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.rt.limits
+    }
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.rt.limits = limits;
+    }
+
+    // Where print, eprint and input go, the process stdio by default
+    pub fn set_io(&mut self, io: impl ScriptIo + 'static) {
+        self.rt.io = Box::new(io);
+    }
+
+    // Whether a top level `let` may bind a name again, strict by default
+    pub fn set_rebind(&mut self, rebind: Rebind) {
+        self.rt.rebind = rebind;
+    }
+
+    // What args(), env_var() and exit() may do, see `Host`
+    pub fn set_host(&mut self, host: impl Host + 'static) {
+        self.rt.host = Box::new(host);
+    }
+
+    // Whether `eval_str` folds constants and prunes dead code before running
+    // a program, on by default. Programs run under a hook are never optimized,
+    // so it sees the statements as written.
+    pub fn set_optimize(&mut self, optimize: bool) {
+        self.optimize = optimize;
+    }
+
+    // Called before each statement runs, e.g. a `Debugger`
+    pub fn set_hook(&mut self, hook: impl EvalHook + 'static) {
+        self.rt.hook = Some(Box::new(hook));
+    }
+    pub fn clear_hook(&mut self) {
+        self.rt.hook = None;
+    }
+
+    // Handle to stop a running evaluation from another thread
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.rt.interrupt_handle()
+    }
+
+    pub fn env(&self) -> Env {
+        Rc::clone(&self.env)
+    }
+
+    // Drops every binding by starting over with an empty root environment.
+    // Builtins, limits, I/O and host stay; handles from `env` keep the old one.
+    pub fn reset(&mut self) {
+        self.env = Rc::new(RefCell::new(Environment::new()));
+    }
+
+    pub fn parse(src: &str) -> Result<Program, InterpreterErr> {
+        let mut p = Parser::new(Lexer::new(src));
+        Ok(p.parse_program()?)
+    }
+
+    pub fn eval_str(&mut self, src: &str) -> Result<Object, InterpreterErr> {
+        let mut program = Self::parse(src)?;
+        if self.optimize && self.rt.hook.is_none() {
+            program = optimize(program);
+        }
+        self.eval_program(program)
+    }
+
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<Object, InterpreterErr> {
+        let path = path.as_ref();
+        let src = fs::read_to_string(path)
+            .map_err(|err| InterpreterErr::Io(path.display().to_string(), err))?;
+        self.eval_str(&src)
+    }
+
+    // Every program starts with fresh execution budgets
+    pub fn eval_program(&mut self, program: Program) -> Result<Object, InterpreterErr> {
+        self.rt.reset_usage();
+        let value = eval(
+            Node::Statement(Statement::Program(program)),
+            Rc::clone(&self.env),
+            &mut self.rt,
+        )?;
+        // a top level `return` ends the program with its value
+        match value {
+            Object::Return(value) => Ok(*value),
+            value => Ok(value),
+        }
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Object) {
+        self.env.borrow_mut().set(name.to_string(), value);
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        self.env.borrow().store.get(name).cloned()
+    }
+
+    // Invoke a script function or builtin with converted arguments, e.g.
+    // `let n: i64 = interpreter.call("add", (1, 2))?`. Closures keep their
+    // environment, so callbacks can be called repeatedly.
+    pub fn call<R: FromObject>(
+        &mut self,
+        callee: impl Into<Callee>,
+        args: impl IntoArgs,
+    ) -> Result<R, InterpreterErr> {
+        let value = self.call_object(callee, args.into_args())?;
+        Ok(R::from_object(&value)?)
+    }
+
+    pub fn call_object(
+        &mut self,
+        callee: impl Into<Callee>,
+        args: Vec<Object>,
+    ) -> Result<Object, InterpreterErr> {
+        let (name, function) = match callee.into() {
+            Callee::Name(name) => match self.get_global(&name) {
+                Some(function) => (name, function),
+                None if self.rt.builtins.contains(&name) => (name.clone(), Object::Builtin(name)),
+                None => return Err(EvalErr::IdentifierNotFound(name).into()),
+            },
+            Callee::Object(function) => ("<host>".to_string(), function),
+        };
+        self.rt.reset_usage();
+        Ok(apply_function(name, function, args, &mut self.rt)?)
+    }
+
+    // Expose a host function to scripts of this interpreter. With a signature,
+    // arity and argument types are checked before `func` runs; the context
+    // lets `func` call back into script functions.
+    pub fn register_builtin<F>(&mut self, name: &str, signature: Option<Signature>, func: F)
+    where
+        F: Fn(&mut Context, &[Object]) -> Result<Object, EvalErr> + MaybeSend + MaybeSync + 'static,
+    {
+        self.rt.builtins.register(name, signature, func);
+    }
+
+    pub fn unregister_builtin(&mut self, name: &str) -> bool {
+        self.rt.builtins.remove(name)
+    }
+
+    pub fn builtins(&self) -> &BuiltinRegistry {
+        &self.rt.builtins
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
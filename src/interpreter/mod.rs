@@ -0,0 +1,2 @@
+pub mod interpreter;
+mod test;
@@ -0,0 +1,703 @@
+#[cfg(test)]
+mod tests {
+    use std::{any::Any, collections::HashMap, env, fs, thread};
+
+    use crate::{
+        errors::{eval_errs::EvalErr, interpreter_errs::InterpreterErr, parser_errs::ParseErr},
+        evaluator::{
+            builtin::{ArgType, Context, Signature},
+            convert::{FromObject, IntoObject},
+            host::{DenyHost, Host, ProcessHost, StubHost},
+            io::{BufferIo, CallbackIo, Stream},
+            native::NativeObject,
+            object::Object,
+            runtime::{Limits, Rebind},
+        },
+        lexer::token::TOKEN,
+        utils::shared::{Rc, RefCell},
+        Interpreter,
+    };
+
+    #[test]
+    fn test_eval_str() {
+        let mut interpreter = Interpreter::new();
+        let tests = vec![
+            ("let add = fn(a, b) { a + b };", Object::Null),
+            ("let x = add(1, 2);", Object::Null),
+            ("x * 2", Object::Number(6)),
+            ("return x; 5", Object::Number(3)),
+            (r#"add("a", "b")"#, Object::String("ab".into())),
+        ];
+        for (input, expected) in tests.into_iter() {
+            assert_eq!(interpreter.eval_str(input).unwrap(), expected);
+        }
+
+        let err = interpreter.eval_str("let = 5").unwrap_err();
+        assert!(err.match_err(InterpreterErr::Parse(ParseErr::None)));
+        let err = interpreter.eval_str("y").unwrap_err();
+        assert!(matches!(
+            err,
+            InterpreterErr::Eval(EvalErr::IdentifierNotFound(_))
+        ));
+        let err = interpreter.eval_str("let x = 1").unwrap_err();
+        assert!(matches!(
+            err,
+            InterpreterErr::Eval(EvalErr::AlreadyInitialized(_))
+        ));
+        // failed inputs keep the session usable
+        assert_eq!(interpreter.eval_str("x").unwrap(), Object::Number(3));
+    }
+
+    #[test]
+    fn test_globals() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("answer", Object::Number(41));
+        let arr = Rc::new(RefCell::new(vec![Object::Number(1)]));
+        interpreter.set_global("items", Object::Array(Rc::clone(&arr)));
+
+        interpreter.eval_str("answer = answer + 1").unwrap();
+        interpreter
+            .eval_str("push(items, 2); let name = \"r\"")
+            .unwrap();
+
+        assert_eq!(interpreter.get_global("answer"), Some(Object::Number(42)));
+        assert_eq!(
+            interpreter.get_global("name"),
+            Some(Object::String("r".into()))
+        );
+        assert_eq!(interpreter.get_global("missing"), None);
+        assert_eq!(arr.borrow().len(), 2);
+
+        // globals can be replaced, unlike `let` bindings
+        interpreter.set_global("answer", Object::Boolean(true));
+        assert_eq!(
+            interpreter.eval_str("!answer").unwrap(),
+            Object::Boolean(false)
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_builtin("one", None, |_, _| Ok(Object::Number(1)));
+        interpreter.eval_str("let x = 1;").unwrap();
+        let old = interpreter.env();
+        interpreter.reset();
+        assert!(interpreter.get_global("x").is_none());
+        assert!(old.borrow().get("x").is_ok());
+        assert_eq!(
+            interpreter.eval_str("let x = one(); x").unwrap(),
+            Object::Number(1)
+        );
+    }
+
+    #[test]
+    fn test_rebind() {
+        let mut interpreter = Interpreter::new();
+        let io = BufferIo::new();
+        interpreter.set_io(io.clone());
+        interpreter.eval_str("let x = 1;").unwrap();
+        let err = interpreter.eval_str("let x = 2;").unwrap_err();
+        assert!(err.match_err(InterpreterErr::Eval(EvalErr::AlreadyInitialized("".into()))));
+
+        interpreter.set_rebind(Rebind::Replace);
+        interpreter
+            .eval_str("let get_x = fn() { x }; let x = 2;")
+            .unwrap();
+        // functions see the replaced binding
+        assert_eq!(interpreter.eval_str("get_x()").unwrap(), Object::Number(2));
+        // function scopes stay strict
+        let err = interpreter
+            .eval_str("let f = fn(a) { let a = 1; a }; f(0)")
+            .unwrap_err();
+        assert!(err.match_err(InterpreterErr::Eval(EvalErr::AlreadyInitialized("".into()))));
+        assert_eq!(io.stderr(), "");
+
+        interpreter.set_rebind(Rebind::Warn);
+        interpreter.eval_str("let x = 3; let y = 1;").unwrap();
+        assert_eq!(interpreter.get_global("x"), Some(Object::Number(3)));
+        assert_eq!(io.stderr(), "warning: x is already defined, replacing it\n");
+    }
+
+    #[test]
+    fn test_optimize() {
+        // folding saves evaluation steps
+        let src = "let f = fn() { let a = 2; if (a > 1) { a * 3 + 4 } else { 0 } }; f()";
+        let limits = Limits {
+            max_steps: Some(12),
+            ..Limits::default()
+        };
+        let mut interpreter = Interpreter::with_limits(limits);
+        assert_eq!(interpreter.eval_str(src).unwrap(), Object::Number(10));
+        interpreter.set_optimize(false);
+        interpreter.reset();
+        let err = interpreter.eval_str(src).unwrap_err();
+        assert!(matches!(err, InterpreterErr::Eval(EvalErr::StepLimit(12))));
+
+        // top level bindings are read at runtime, later programs may change them
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str("let x = 1; let get_x = fn() { x };")
+            .unwrap();
+        interpreter.eval_str("x = 2;").unwrap();
+        assert_eq!(interpreter.eval_str("get_x()").unwrap(), Object::Number(2));
+        interpreter.set_rebind(Rebind::Replace);
+        interpreter.eval_str("let x = 3;").unwrap();
+        assert_eq!(interpreter.eval_str("get_x()").unwrap(), Object::Number(3));
+    }
+
+    #[test]
+    fn test_eval_file() {
+        let path = env::temp_dir().join(format!("r-piece-test-{}.rp", std::process::id()));
+        fs::write(&path, "let double = fn(x) { x * 2 };\ndouble(21)\n").unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let obj = interpreter.eval_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(obj.unwrap(), Object::Number(42));
+        assert!(interpreter.get_global("double").is_some());
+
+        let err = interpreter.eval_file(path).unwrap_err();
+        assert!(err.match_err(InterpreterErr::Io(
+            "".into(),
+            std::io::ErrorKind::NotFound.into()
+        )));
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut interpreter = Interpreter::with_limits(Limits {
+            max_steps: Some(100),
+            ..Limits::default()
+        });
+        let err = interpreter
+            .eval_str("let f = fn() { f() }; f()")
+            .unwrap_err();
+        assert!(err.match_err(InterpreterErr::Eval(EvalErr::StepLimit(0))));
+        // budgets are per program
+        assert_eq!(interpreter.eval_str("1 + 1").unwrap(), Object::Number(2));
+        assert_eq!(
+            format!(
+                "{}",
+                InterpreterErr::Parse(ParseErr::LET("IDENT".into(), TOKEN::ASSIGN))
+            ),
+            "Let Error: Expected: IDENT | got ASSIGN"
+        );
+    }
+
+    #[test]
+    fn test_register_builtin() {
+        let mut interpreter = Interpreter::new();
+        let logs = Rc::new(RefCell::new(vec![]));
+        let sink = Rc::clone(&logs);
+        interpreter.register_builtin(
+            "host.log",
+            Some(Signature::variadic(vec![ArgType::String], ArgType::Any)),
+            move |_, args| {
+                let line = args.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+                sink.borrow_mut().push(line.join(" "));
+                Ok(Object::Null)
+            },
+        );
+        let counter = Rc::new(RefCell::new(0));
+        let count = Rc::clone(&counter);
+        interpreter.register_builtin("tick", None, move |_, _| {
+            *count.borrow_mut() += 1;
+            Ok(Object::Number(*count.borrow()))
+        });
+
+        let tests = vec![
+            (r#"host.log("a", 1, true)"#, Object::Null),
+            ("tick(); tick()", Object::Number(2)),
+            ("let f = host.log; f(\"b\")", Object::Null),
+            ("let g = fn(log) { log(\"c\") }; g(host.log)", Object::Null),
+            ("len([1, 2, 3])", Object::Number(3)),
+        ];
+        for (input, expected) in tests.into_iter() {
+            assert_eq!(interpreter.eval_str(input).unwrap(), expected, "{}", input);
+        }
+        assert_eq!(*logs.borrow(), vec!["a 1 true", "b", "c"]);
+        assert_eq!(*counter.borrow(), 2);
+
+        let tests = vec![
+            ("host.log()", EvalErr::ArgsCount(0, 0)),
+            (
+                "host.log(1)",
+                EvalErr::BuiltinArgsType("".into(), "".into(), "".into()),
+            ),
+            (
+                "len(1)",
+                EvalErr::BuiltinArgsType("".into(), "".into(), "".into()),
+            ),
+            ("push([])", EvalErr::ArgsCount(0, 0)),
+            ("host.missing(1)", EvalErr::IdentifierNotFound("".into())),
+            ("let n = 1; n.log", EvalErr::NoMember("".into(), "".into())),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let err = interpreter.eval_str(input).unwrap_err();
+            assert!(err.match_err(InterpreterErr::Eval(expected)), "{}", input);
+        }
+        assert_eq!(
+            interpreter.eval_str("host.log(1)").unwrap_err().to_string(),
+            "Builtin(host.log) expects String arguments, got Number"
+        );
+
+        // host builtins can call back into the script
+        interpreter.register_builtin(
+            "host.twice",
+            Some(Signature::new(vec![ArgType::Function, ArgType::Any])),
+            |ctx, args| {
+                let once = ctx.call(&args[0], vec![args[1].clone()])?;
+                ctx.call(&args[0], vec![once])
+            },
+        );
+        assert_eq!(
+            interpreter
+                .eval_str("host.twice(fn(x) { x * 3 }, 2)")
+                .unwrap(),
+            Object::Number(18)
+        );
+        // a variable shadows the namespace, and registries are per interpreter
+        interpreter.set_global("host", Object::Number(1));
+        assert!(interpreter.eval_str("host.log(\"d\")").is_err());
+        assert!(interpreter.unregister_builtin("tick"));
+        assert!(interpreter.eval_str("tick()").is_err());
+        assert!(Interpreter::new().eval_str("host.log(\"e\")").is_err());
+        assert_eq!(logs.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_call() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str(
+                r#"
+                let events = [];
+                let on_event = fn(payload) { push(events, payload.kind); len(events) };
+                let add = fn(a, b) { a + b };
+                let counter = fn() {
+                    let c = [0];
+                    fn() { c[0] = c[0] + 1; c[0] }
+                };
+            "#,
+            )
+            .unwrap();
+
+        let n: i64 = interpreter.call("add", (1, 2)).unwrap();
+        assert_eq!(n, 3);
+        let s: String = interpreter.call("add", ("a", "b")).unwrap();
+        assert_eq!(s, "ab");
+        for i in 1..=3 {
+            let payload = HashMap::from([("kind".to_string(), format!("e{}", i))]);
+            let count: i64 = interpreter.call("on_event", (payload,)).unwrap();
+            assert_eq!(count, i);
+        }
+        let events: Vec<String> = interpreter.call("pop", (vec![vec!["x"]],)).unwrap();
+        assert_eq!(events, vec!["x"]);
+        assert_eq!(
+            interpreter.eval_str("events").unwrap().to_string(),
+            "[e1, e2, e3]"
+        );
+
+        // a returned closure keeps its environment between calls
+        let tick = interpreter.call_object("counter", vec![]).unwrap();
+        for i in 1..=3 {
+            let n: i64 = interpreter.call(&tick, ()).unwrap();
+            assert_eq!(n, i);
+        }
+        interpreter.call::<()>(&tick, ()).unwrap();
+        assert_eq!(interpreter.call::<i64>(tick, ()).unwrap(), 5);
+
+        let tests = vec![
+            ("missing", EvalErr::IdentifierNotFound("".into())),
+            ("add", EvalErr::ArgsCount(0, 0)),
+            ("events", EvalErr::NotImplemented("".into())),
+        ];
+        for (name, expected) in tests.into_iter() {
+            let err = interpreter.call::<()>(name, (1,)).unwrap_err();
+            assert!(err.match_err(InterpreterErr::Eval(expected)), "{}", name);
+        }
+        let err = interpreter.call::<bool>("add", (1, 2)).unwrap_err();
+        assert!(err.match_err(InterpreterErr::Eval(EvalErr::CoerceObject(
+            "".into(),
+            "".into()
+        ))));
+    }
+
+    struct Connection {
+        url: String,
+        queries: RefCell<Vec<String>>,
+        settings: RefCell<HashMap<String, i64>>,
+    }
+
+    impl NativeObject for Connection {
+        fn type_name(&self) -> String {
+            "Connection".into()
+        }
+        fn display(&self) -> String {
+            format!("<Connection {}>", self.url)
+        }
+        fn equals(&self, other: &dyn NativeObject) -> bool {
+            let other = (other as &dyn Any).downcast_ref::<Connection>();
+            other.is_some_and(|other| other.url == self.url)
+        }
+        fn index_get(&self, index: &Object) -> Result<Object, EvalErr> {
+            let key = String::from_object(index)?;
+            Ok(self.settings.borrow().get(&key).copied().into_object())
+        }
+        fn index_set(&self, index: &Object, value: Object) -> Result<(), EvalErr> {
+            let key = String::from_object(index)?;
+            self.settings
+                .borrow_mut()
+                .insert(key, i64::from_object(&value)?);
+            Ok(())
+        }
+        fn call_method(
+            &self,
+            ctx: &mut Context,
+            name: &str,
+            args: &[Object],
+        ) -> Result<Object, EvalErr> {
+            match name {
+                "query" => {
+                    let sql = String::from_object(&args[0])?;
+                    self.queries.borrow_mut().push(sql.clone());
+                    Ok(vec![sql.len() as i64].into_object())
+                }
+                // rows are handed to a script callback
+                "each_row" => {
+                    for row in 1..=2 {
+                        ctx.call(&args[0], vec![Object::Number(row)])?;
+                    }
+                    Ok(Object::Null)
+                }
+                name => Err(EvalErr::NoMember(self.type_name(), name.to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_native_objects() {
+        let mut interpreter = Interpreter::new();
+        let conn = Rc::new(Connection {
+            url: "db://main".into(),
+            queries: RefCell::new(vec![]),
+            settings: RefCell::new(HashMap::new()),
+        });
+        interpreter.set_global("conn", Rc::clone(&conn).into_object());
+        interpreter.register_builtin("db.open", None, |_, args| {
+            let url = String::from_object(&args[0])?;
+            let conn = Connection {
+                url,
+                queries: RefCell::new(vec![]),
+                settings: RefCell::new(HashMap::new()),
+            };
+            Ok(Rc::new(conn).into_object())
+        });
+        interpreter.register_builtin("db.url", None, |_, args| {
+            let conn = Rc::<Connection>::from_object(&args[0])?;
+            Ok(conn.url.clone().into_object())
+        });
+
+        let tests = vec![
+            (r#"conn.query("select 1")"#, "[8]"),
+            ("conn", "<Connection db://main>"),
+            (r#"conn["timeout"] = 30; conn["timeout"]"#, "30"),
+            (r#"conn["missing"]"#, "Null"),
+            (r#"conn == db.open("db://main")"#, "true"),
+            (r#"conn != db.open("db://other")"#, "true"),
+            (
+                "let rows = []; conn.each_row(fn(r) { push(rows, r) }); rows",
+                "[1, 2]",
+            ),
+            ("db.url(conn)", "db://main"),
+            ("let c = conn; [c][0].query(\"x\")", "[1]"),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let obj = interpreter.eval_str(input).unwrap();
+            assert_eq!(obj.to_string(), expected, "{}", input);
+        }
+        assert_eq!(conn.queries.borrow().clone(), vec!["select 1", "x"]);
+        assert_eq!(conn.settings.borrow().get("timeout"), Some(&30));
+        assert_eq!(
+            interpreter.get_global("conn").unwrap().get_type(),
+            "Connection"
+        );
+
+        let tests = vec![
+            ("conn.drop()", EvalErr::NoMember("".into(), "".into())),
+            ("conn.query", EvalErr::UncalledMethod("".into(), "".into())),
+            ("conn.query(1)", EvalErr::CoerceObject("".into(), "".into())),
+            (
+                r#"conn["t"] = "slow""#,
+                EvalErr::CoerceObject("".into(), "".into()),
+            ),
+            ("db.url(1)", EvalErr::CoerceObject("".into(), "".into())),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let err = interpreter.eval_str(input).unwrap_err();
+            assert!(err.match_err(InterpreterErr::Eval(expected)), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_io() {
+        let mut interpreter = Interpreter::new();
+        let io = BufferIo::with_input("alice\r\n42\n");
+        interpreter.set_io(io.clone());
+
+        interpreter
+            .eval_str(
+                r#"
+                let name = input("name? ");
+                print("hi " + name, [1, 2]);
+                eprint("warn");
+                let n = input();
+                let eof = input();
+                print(n, eof);
+            "#,
+            )
+            .unwrap();
+        assert_eq!(io.stdout(), "name? hi alice \n[1, 2] \n42 \nNull \n");
+        assert_eq!(io.stderr(), "warn \n");
+        io.clear();
+        assert_eq!(io.stdout(), "");
+
+        let err = interpreter.eval_str(r#"input("a", "b")"#).unwrap_err();
+        assert!(matches!(
+            err,
+            InterpreterErr::Eval(EvalErr::ArgsCount(1, 2))
+        ));
+        let err = interpreter.eval_str("input(1)").unwrap_err();
+        assert!(err.match_err(InterpreterErr::Eval(EvalErr::BuiltinArgsType(
+            "".into(),
+            "".into(),
+            "".into()
+        ))));
+
+        let lines = Rc::new(RefCell::new(vec![]));
+        let sink = Rc::clone(&lines);
+        interpreter.set_io(CallbackIo::new(move |stream, text| {
+            sink.borrow_mut().push((stream, text.to_string()))
+        }));
+        interpreter
+            .eval_str(r#"print(1); eprint("e"); input()"#)
+            .unwrap();
+        assert_eq!(
+            *lines.borrow(),
+            vec![
+                (Stream::Stdout, "1 \n".to_string()),
+                (Stream::Stderr, "e \n".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_host() {
+        // Embedded interpreters see no arguments nor process variables
+        let mut interpreter = Interpreter::new();
+        let value = interpreter
+            .eval_str(r#"[args(), env_var("PATH")]"#)
+            .unwrap();
+        assert_eq!(value.to_string(), "[[], Null]");
+
+        interpreter.set_host(
+            StubHost::new()
+                .with_args(&["in.txt", "-v"])
+                .with_var("MODE", "test"),
+        );
+        let value = interpreter
+            .eval_str(r#"[args(), env_var("MODE"), env_var("HOME")]"#)
+            .unwrap();
+        assert_eq!(value.to_string(), "[[in.txt, -v], test, Null]");
+        let err = interpreter.eval_str("exit(3)").unwrap_err();
+        assert!(matches!(err, InterpreterErr::Eval(EvalErr::Exit(3))));
+
+        interpreter.set_host(DenyHost);
+        for input in ["args()", r#"env_var("MODE")"#, "exit(0)"] {
+            let err = interpreter.eval_str(input).unwrap_err();
+            assert!(
+                err.match_err(InterpreterErr::Eval(EvalErr::Denied("".into()))),
+                "{}",
+                input
+            );
+        }
+
+        // A host can allow some capabilities and deny the rest
+        struct OnlyArgs;
+        impl Host for OnlyArgs {
+            fn args(&mut self) -> Result<Vec<String>, EvalErr> {
+                Ok(vec!["a".into()])
+            }
+        }
+        interpreter.set_host(OnlyArgs);
+        assert_eq!(interpreter.eval_str("args()[0]").unwrap().to_string(), "a");
+        let err = interpreter.eval_str(r#"env_var("MODE")"#).unwrap_err();
+        assert!(err.to_string().contains("env_var"));
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_host(ProcessHost::new(vec!["x".into()]));
+        env::set_var("R_PIECE_TEST_VAR", "42");
+        let value = interpreter
+            .eval_str(r#"[args(), env_var("R_PIECE_TEST_VAR")]"#)
+            .unwrap();
+        assert_eq!(value.to_string(), "[[x], 42]");
+        let err = interpreter.eval_str("env_var(1)").unwrap_err();
+        assert!(err.match_err(InterpreterErr::Eval(EvalErr::BuiltinArgsType(
+            "".into(),
+            "".into(),
+            "".into()
+        ))));
+    }
+
+    #[test]
+    fn test_owned_objects() {
+        // Objects own their data, hosts can keep them around
+        struct Cache {
+            values: Vec<Object>,
+        }
+        let mut cache = Cache { values: vec![] };
+        let adder = {
+            let mut interpreter = Interpreter::new();
+            for src in ["[1, 2]", r#"{"a": 1}"#, "let n = 10; fn(x) { x + n }"] {
+                cache.values.push(interpreter.eval_str(src).unwrap());
+            }
+            cache.values[2].clone()
+        };
+        assert_eq!(cache.values[0].to_string(), "[1, 2]");
+        assert_eq!(cache.values[1].to_string(), r#"{"a": 1}"#);
+
+        // The closure keeps its scope alive after its interpreter is gone
+        let mut interpreter = Interpreter::new();
+        assert_eq!(interpreter.call::<i64>(&adder, (5,)).unwrap(), 15);
+        assert_eq!(adder, cache.values[2]);
+        let other = interpreter.eval_str("let n = 10; fn(x) { x + n }").unwrap();
+        assert_ne!(adder, other);
+    }
+
+    #[test]
+    fn test_threads() {
+        // Interpreters share no state, one per thread
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                thread::spawn(move || {
+                    let mut interpreter = Interpreter::new();
+                    interpreter.set_global("seed", Object::Number(i));
+                    let result = interpreter
+                        .eval_str(
+                            r#"
+                            let fib = fn(n) { if (n < 2) { return n; }; return fib(n - 1) + fib(n - 2); };
+                            fib(15) + seed
+                        "#,
+                        )
+                        .unwrap();
+                    i64::from_object(&result).unwrap()
+                })
+            })
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), 610 + i as i64);
+        }
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    #[should_panic(expected = "already borrowed")]
+    fn test_sync_nested_borrow() {
+        let mut interpreter = Interpreter::new();
+        let cell = Rc::new(RefCell::new(0));
+        let inner = Rc::clone(&cell);
+        interpreter.register_builtin("bump", None, move |_, _| {
+            *inner.borrow_mut() += 1;
+            Ok(Object::Null)
+        });
+        // a borrow held across a call that borrows mutably panics like
+        // std's RefCell instead of waiting on the lock
+        let _guard = cell.borrow();
+        let _ = interpreter.eval_str("bump()");
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_sync_shared_borrow() {
+        // borrows held by other threads are waited for
+        let items = Rc::new(RefCell::new(vec![]));
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let items = Rc::clone(&items);
+                thread::spawn(move || {
+                    for j in 0..200 {
+                        let mut items = items.borrow_mut();
+                        let len = items.len();
+                        thread::yield_now();
+                        items.push(i * 1000 + j);
+                        assert_eq!(items.len(), len + 1);
+                    }
+                    items.borrow().len()
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.join().unwrap() >= 200);
+        }
+        assert_eq!(items.borrow().len(), 1600);
+
+        // a writer waits for a reader of another thread, which may read
+        // again while the writer is waiting
+        let cell = Rc::new(RefCell::new(0));
+        let reading = cell.borrow();
+        let writer = {
+            let cell = Rc::clone(&cell);
+            thread::spawn(move || *cell.borrow_mut() = 1)
+        };
+        thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!(*cell.borrow(), 0);
+        assert_eq!(*reading, 0);
+        drop(reading);
+        writer.join().unwrap();
+        assert_eq!(*cell.borrow(), 1);
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_send_interpreter() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Interpreter>();
+        assert_send::<Object>();
+
+        let mut interpreter = Interpreter::new();
+        let io = BufferIo::new();
+        interpreter.set_io(io.clone());
+        let counter = Rc::new(RefCell::new(0));
+        let count = Rc::clone(&counter);
+        interpreter.register_builtin("bump", None, move |_, _| {
+            *count.borrow_mut() += 1;
+            Ok(Object::Null)
+        });
+        interpreter
+            .eval_str("let items = [1, 2]; let add = fn(x) { push(items, x); bump(); };")
+            .unwrap();
+
+        // The same interpreter, moved between threads
+        for i in 3..6 {
+            interpreter = thread::spawn(move || {
+                interpreter.call::<()>("add", (i,)).unwrap();
+                interpreter
+            })
+            .join()
+            .unwrap();
+        }
+        let items = interpreter.get_global("items").unwrap();
+        assert_eq!(
+            Vec::<i64>::from_object(&items).unwrap(),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert_eq!(*counter.borrow(), 3);
+
+        // Script values can be handed to another thread
+        let printed = thread::spawn(move || items.to_string()).join().unwrap();
+        assert_eq!(printed, "[1, 2, 3, 4, 5]");
+        interpreter.eval_str("print(len(items))").unwrap();
+        assert_eq!(io.stdout(), "5 \n");
+    }
+}
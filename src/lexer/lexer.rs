@@ -1,18 +1,58 @@
-use crate::ast::ast::Identifier;
+use crate::ast::ast::{Identifier, Span};
+
+use super::token::{TokenKind, KEYWORDS, TOKEN};
+
+// A token as it appears in the source. `text` borrows from the input, so
+// lexing does not allocate; string escapes are only resolved by `unescape`.
+// `span` covers the whole token, quotes of strings included.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lexeme<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+impl<'a> Lexeme<'a> {
+    pub fn new(kind: TokenKind, text: &'a str, span: Span) -> Self {
+        Self { kind, text, span }
+    }
+
+    pub fn is(&self, kind: TokenKind) -> bool {
+        self.kind == kind
+    }
 
-use super::token::{KEYWORDS, TOKEN};
-use std::{iter::Peekable, str::Chars};
+    // Owned token, only allocates for identifiers and strings
+    pub fn to_token(&self) -> TOKEN {
+        match self.kind {
+            TokenKind::IDENT => TOKEN::IDENT(Identifier(self.text.to_string(), self.span)),
+            // the parser rejects literals out of range, the token only names them
+            TokenKind::NUMBER => TOKEN::NUMBER(self.text.parse::<i64>().unwrap_or(i64::MAX)),
+            TokenKind::STRING => TOKEN::STRING(unescape(self.text)),
+            TokenKind::ILLEGAL => TOKEN::ILLEGAL(self.text.chars().next().unwrap_or('\0')),
+            kind => kind.to_token().unwrap(),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Lexer<'a> {
-    input: Peekable<Chars<'a>>,
+    input: &'a str,
+    pos: usize,      // byte offset of `ch`
+    next_pos: usize, // byte offset of the char after `ch`
     ch: char,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Lexer<'a> {
+        // `#!/usr/bin/env r-piece` on the first line of a script
+        let start = match input.starts_with("#!") {
+            true => input.find('\n').unwrap_or(input.len()),
+            false => 0,
+        };
         let mut l = Lexer {
-            input: input.chars().peekable(),
+            input,
+            pos: start,
+            next_pos: start,
             ch: '\0',
         };
         l.read_char();
@@ -20,120 +60,179 @@ impl<'a> Lexer<'a> {
     }
 
     pub fn read_char(&mut self) {
-        self.ch = if self.input.peek().is_none() {
-            '\0'
-        } else {
-            self.input.next().unwrap()
+        self.pos = self.next_pos;
+        self.ch = match self.input[self.pos..].chars().next() {
+            Some(c) => {
+                self.next_pos += c.len_utf8();
+                c
+            }
+            None => '\0',
         }
     }
     pub fn read_peek(&mut self) -> char {
-        self.input.peek().unwrap_or(&'\0').clone()
+        self.input[self.next_pos..].chars().next().unwrap_or('\0')
     }
 
     pub fn next_token(&mut self) -> TOKEN {
+        self.next_lexeme().to_token()
+    }
+
+    pub fn next_lexeme(&mut self) -> Lexeme<'a> {
         self.skip_white_space();
         let peek = self.read_peek();
+        let start = self.pos;
 
-        let token = match self.ch {
+        let kind = match self.ch {
             '=' if peek == '=' => {
                 self.read_char();
-                TOKEN::EQ
+                TokenKind::EQ
             }
-            '=' => TOKEN::ASSIGN,
-            '+' => TOKEN::PLUS,
-            '-' => TOKEN::MINUS,
+            '=' => TokenKind::ASSIGN,
+            '+' => TokenKind::PLUS,
+            '-' => TokenKind::MINUS,
             '!' if peek == '=' => {
                 self.read_char();
-                TOKEN::NotEQ
+                TokenKind::NotEQ
             }
-            '!' => TOKEN::BANG,
-            '*' => TOKEN::ASTERISK,
-            '/' => TOKEN::SLASH,
-            '>' => TOKEN::GT,
-            '<' => TOKEN::LT,
-            ',' => TOKEN::COMMA,
-            ';' => TOKEN::SEMICOLON,
-            '(' => TOKEN::LPAREN,
-            ')' => TOKEN::RPAREN,
-            '{' => TOKEN::LBRACE,
-            '}' => TOKEN::RBRACE,
-            '[' => TOKEN::LBRACKET,
-            ']' => TOKEN::RBRACKET,
+            '!' => TokenKind::BANG,
+            '*' => TokenKind::ASTERISK,
+            '/' => TokenKind::SLASH,
+            '>' => TokenKind::GT,
+            '<' => TokenKind::LT,
+            ',' => TokenKind::COMMA,
+            ':' => TokenKind::COLON,
+            '.' => TokenKind::DOT,
+            ';' => TokenKind::SEMICOLON,
+            '(' => TokenKind::LPAREN,
+            ')' => TokenKind::RPAREN,
+            '{' => TokenKind::LBRACE,
+            '}' => TokenKind::RBRACE,
+            '[' => TokenKind::LBRACKET,
+            ']' => TokenKind::RBRACKET,
             '"' => {
-                return TOKEN::STRING(self.read_str());
+                return self.read_str();
             }
             c if is_letter(c) => {
                 return self.read_identifier();
             }
-            c if c.is_digit(10) => {
-                return TOKEN::NUMBER(self.read_number());
+            c if c.is_ascii_digit() => {
+                return self.read_number();
             }
-            '\0' => TOKEN::EOF,
-            c => TOKEN::ILLEGAL(c),
+            '\0' => TokenKind::EOF,
+            _ => TokenKind::ILLEGAL,
         };
 
         self.read_char();
-        return token;
+        return Lexeme::new(kind, &self.input[start..self.pos], self.span(start));
     }
 
-    pub fn read_identifier(&mut self) -> TOKEN {
-        let mut identifier = String::new();
-
+    pub fn read_identifier(&mut self) -> Lexeme<'a> {
+        let start = self.pos;
         while is_letter(self.ch) {
-            identifier.push(self.ch);
             self.read_char();
         }
 
-        if KEYWORDS.contains_key(identifier.as_str()) {
-            return KEYWORDS.get(identifier.as_str()).unwrap().clone();
-        }
-        return TOKEN::IDENT(Identifier(identifier));
+        let identifier = &self.input[start..self.pos];
+        let kind = KEYWORDS
+            .get(identifier)
+            .copied()
+            .unwrap_or(TokenKind::IDENT);
+        return Lexeme::new(kind, identifier, self.span(start));
     }
 
-    pub fn read_number(&mut self) -> i64 {
-        let mut number = String::new();
-        while self.ch.is_digit(10) {
-            number.push(self.ch);
+    pub fn read_number(&mut self) -> Lexeme<'a> {
+        let start = self.pos;
+        while self.ch.is_ascii_digit() {
             self.read_char();
         }
-        return number.parse::<i64>().unwrap();
+        let number = &self.input[start..self.pos];
+        return Lexeme::new(TokenKind::NUMBER, number, self.span(start));
     }
 
-    pub fn read_str(&mut self) -> String {
-        let mut str = String::new();
+    // The text of a string lexeme is the raw source between the quotes. An
+    // unterminated string is ILLEGAL, its text runs from the quote to the end.
+    pub fn read_str(&mut self) -> Lexeme<'a> {
+        let quote = self.pos;
         self.read_char();
+        let start = self.pos;
         while self.ch != '"' {
             if self.ch == '\0' {
-                panic!("EOF for string lexing");
-            }
-
-            // \n should considered as new line
-            if self.ch == '\\' && self.read_peek() == 'n' {
-                self.read_char();
-                self.read_char();
-                str.push('\n');
-                continue;
+                return Lexeme::new(TokenKind::ILLEGAL, &self.input[quote..], self.span(quote));
             }
-
             // \ should be considered as escape character
             if self.ch == '\\' {
                 self.read_char();
             }
-            str.push(self.ch);
-
             self.read_char();
         }
+        let str = &self.input[start..self.pos];
         self.read_char();
-        return str;
+        return Lexeme::new(TokenKind::STRING, str, self.span(quote));
     }
 
+    // Whitespace and `//` comments, which run to the end of the line
     pub fn skip_white_space(&mut self) {
-        while self.ch == ' ' || self.ch == '\t' || self.ch == '\n' || self.ch == '\r' {
-            self.read_char();
+        loop {
+            while self.ch == ' ' || self.ch == '\t' || self.ch == '\n' || self.ch == '\r' {
+                self.read_char();
+            }
+            if self.ch != '/' || self.read_peek() != '/' {
+                return;
+            }
+            while self.ch != '\n' && self.ch != '\0' {
+                self.read_char();
+            }
         }
     }
+
+    // From `start` to the current position
+    fn span(&self, start: usize) -> Span {
+        Span::new(start, self.pos)
+    }
+}
+
+// Spans of the `//` comments of the input, which the parser never sees.
+// Only whitespace and comments lie between two lexemes, so every `//`
+// found there starts a comment.
+pub fn comments(input: &str) -> Vec<Span> {
+    let mut lexer = Lexer::new(input);
+    let mut comments = vec![];
+    let mut gap_start = lexer.pos;
+    loop {
+        let lexeme = lexer.next_lexeme();
+        let gap = &input[gap_start..lexeme.span.start];
+        let mut offset = 0;
+        while let Some(idx) = gap[offset..].find("//") {
+            let start = offset + idx;
+            let end = gap[start..].find('\n').map_or(gap.len(), |x| start + x);
+            comments.push(Span::new(gap_start + start, gap_start + end));
+            offset = end;
+        }
+        if lexeme.is(TokenKind::EOF) {
+            return comments;
+        }
+        gap_start = lexeme.span.end;
+    }
 }
 
 pub fn is_letter(ch: char) -> bool {
     ch.is_alphabetic() || ch == '_'
 }
+
+// Resolve the escapes of a raw string lexeme
+pub fn unescape(raw: &str) -> String {
+    let mut str = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // \n should considered as new line
+            '\\' => match chars.next() {
+                Some('n') => str.push('\n'),
+                Some(escaped) => str.push(escaped),
+                None => {}
+            },
+            c => str.push(c),
+        }
+    }
+    return str;
+}
@@ -3,7 +3,11 @@ mod tests {
 
     use crate::{
         ast::ast::Identifier,
-        lexer::{lexer::Lexer, token::TOKEN},
+        lexer::{
+            lexer::{comments, unescape, Lexer},
+            token::{TokenKind, TOKEN},
+        },
+        parser::parser::Parser,
     };
 
     #[test]
@@ -34,39 +38,39 @@ mod tests {
 
         let tokens = vec![
             TOKEN::LET,
-            TOKEN::IDENT(Identifier("five".to_string())),
+            TOKEN::IDENT(Identifier::new("five")),
             TOKEN::ASSIGN,
             TOKEN::NUMBER(5),
             TOKEN::SEMICOLON,
             TOKEN::LET,
-            TOKEN::IDENT(Identifier("ten".to_string())),
+            TOKEN::IDENT(Identifier::new("ten")),
             TOKEN::ASSIGN,
             TOKEN::NUMBER(10),
             TOKEN::SEMICOLON,
             TOKEN::LET,
-            TOKEN::IDENT(Identifier("add".to_string())),
+            TOKEN::IDENT(Identifier::new("add")),
             TOKEN::ASSIGN,
             TOKEN::FUNCTION,
             TOKEN::LPAREN,
-            TOKEN::IDENT(Identifier("x".to_string())),
+            TOKEN::IDENT(Identifier::new("x")),
             TOKEN::COMMA,
-            TOKEN::IDENT(Identifier("y".to_string())),
+            TOKEN::IDENT(Identifier::new("y")),
             TOKEN::RPAREN,
             TOKEN::LBRACE,
-            TOKEN::IDENT(Identifier("x".to_string())),
+            TOKEN::IDENT(Identifier::new("x")),
             TOKEN::PLUS,
-            TOKEN::IDENT(Identifier("y".to_string())),
+            TOKEN::IDENT(Identifier::new("y")),
             TOKEN::SEMICOLON,
             TOKEN::RBRACE,
             TOKEN::SEMICOLON,
             TOKEN::LET,
-            TOKEN::IDENT(Identifier("result".to_string())),
+            TOKEN::IDENT(Identifier::new("result")),
             TOKEN::ASSIGN,
-            TOKEN::IDENT(Identifier("add".to_string())),
+            TOKEN::IDENT(Identifier::new("add")),
             TOKEN::LPAREN,
-            TOKEN::IDENT(Identifier("five".to_string())),
+            TOKEN::IDENT(Identifier::new("five")),
             TOKEN::COMMA,
-            TOKEN::IDENT(Identifier("ten".to_string())),
+            TOKEN::IDENT(Identifier::new("ten")),
             TOKEN::RPAREN,
             TOKEN::SEMICOLON,
             TOKEN::BANG,
@@ -122,4 +126,113 @@ mod tests {
             assert_eq!(l.next_token(), *token);
         }
     }
+
+    #[test]
+    fn test_lexeme() {
+        let input = r#"let café = "a\"b\n"; café != 10"#;
+        let lexemes = vec![
+            (TokenKind::LET, "let"),
+            (TokenKind::IDENT, "café"),
+            (TokenKind::ASSIGN, "="),
+            (TokenKind::STRING, r#"a\"b\n"#),
+            (TokenKind::SEMICOLON, ";"),
+            (TokenKind::IDENT, "café"),
+            (TokenKind::NotEQ, "!="),
+            (TokenKind::NUMBER, "10"),
+            (TokenKind::EOF, ""),
+        ];
+        let mut l = Lexer::new(input);
+        let range = input.as_bytes().as_ptr_range();
+
+        for (kind, text) in lexemes.into_iter() {
+            let lexeme = l.next_lexeme();
+            assert_eq!((lexeme.kind, lexeme.text), (kind, text));
+            // text is a slice of the input, not a copy
+            assert!(range.contains(&lexeme.text.as_ptr()) || lexeme.text.is_empty());
+            assert_eq!(lexeme.to_token().kind(), kind);
+        }
+        assert_eq!(unescape(r#"a\"b\n"#), "a\"b\n");
+    }
+
+    #[test]
+    fn test_shebang() {
+        let tests = vec![
+            (
+                "#!/usr/bin/env r-piece\nlet x",
+                vec![TokenKind::LET, TokenKind::IDENT],
+            ),
+            ("#!/usr/bin/env r-piece", vec![]),
+            // only on the first line
+            (
+                "x\n#!",
+                vec![TokenKind::IDENT, TokenKind::ILLEGAL, TokenKind::BANG],
+            ),
+        ];
+        for (input, kinds) in tests.into_iter() {
+            let mut l = Lexer::new(input);
+            for kind in kinds {
+                assert_eq!(l.next_lexeme().kind, kind, "{}", input);
+            }
+            assert_eq!(l.next_lexeme().kind, TokenKind::EOF, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        for input in [r#"let s = "abc"#, r#"let s = "a\"#, r#"let s = "a\""#] {
+            let mut l = Lexer::new(input);
+            let kinds = [TokenKind::LET, TokenKind::IDENT, TokenKind::ASSIGN];
+            for kind in kinds {
+                assert_eq!(l.next_lexeme().kind, kind);
+            }
+            let lexeme = l.next_lexeme();
+            assert_eq!(lexeme.kind, TokenKind::ILLEGAL, "{}", input);
+            assert_eq!(lexeme.text, &input[8..]);
+            assert_eq!(l.next_lexeme().kind, TokenKind::EOF);
+        }
+        assert!(Parser::new(Lexer::new(r#"print("hi)"#))
+            .parse_program()
+            .is_err());
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "let x = 1; // one\n// two\nx / 2 // \"three\"\n\"//\"";
+        let mut l = Lexer::new(input);
+        let kinds = [
+            TokenKind::LET,
+            TokenKind::IDENT,
+            TokenKind::ASSIGN,
+            TokenKind::NUMBER,
+            TokenKind::SEMICOLON,
+            TokenKind::IDENT,
+            TokenKind::SLASH,
+            TokenKind::NUMBER,
+            TokenKind::STRING,
+            TokenKind::EOF,
+        ];
+        for kind in kinds {
+            assert_eq!(l.next_lexeme().kind, kind);
+        }
+        let comments = comments(input)
+            .iter()
+            .map(|x| x.text(input))
+            .collect::<Vec<&str>>();
+        assert_eq!(comments, ["// one", "// two", "// \"three\""]);
+    }
+
+    #[test]
+    fn test_spans() {
+        let input = "let s = \"a\\\"b\";";
+        let mut l = Lexer::new(input);
+        let mut spans = vec![];
+        loop {
+            let lexeme = l.next_lexeme();
+            spans.push(lexeme.span.text(input));
+            if lexeme.is(TokenKind::EOF) {
+                break;
+            }
+        }
+        assert_eq!(spans, ["let", "s", "=", "\"a\\\"b\"", ";", ""]);
+    }
 }
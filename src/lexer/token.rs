@@ -1,6 +1,5 @@
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
-use std::mem::discriminant;
 
 use crate::ast::ast::{Identifier, Number};
 
@@ -29,6 +28,8 @@ pub enum TOKEN {
 
     //Delimeters
     COMMA,
+    COLON,
+    DOT,
     SEMICOLON,
     LPAREN,   // (
     RPAREN,   // )
@@ -61,6 +62,8 @@ impl TOKEN {
             TOKEN::EQ => String::from("=="),
             TOKEN::NotEQ => String::from("!="),
             TOKEN::COMMA => String::from(","),
+            TOKEN::COLON => String::from(":"),
+            TOKEN::DOT => String::from("."),
             TOKEN::SEMICOLON => String::from(";"),
             TOKEN::LPAREN => String::from("("),
             TOKEN::RPAREN => String::from(")"),
@@ -83,56 +86,137 @@ impl TOKEN {
             TOKEN::STRING(s) => s.clone(),
         }
     }
-    pub fn to_type_name(&self) -> String {
+    pub fn kind(&self) -> TokenKind {
         match self {
-            TOKEN::ASSIGN => String::from("="),
-            TOKEN::PLUS => String::from("+"),
-            TOKEN::MINUS => String::from("-"),
-            TOKEN::BANG => String::from("!"),
-            TOKEN::ASTERISK => String::from("*"),
-            TOKEN::SLASH => String::from("/"),
-            TOKEN::GT => String::from(">"),
-            TOKEN::LT => String::from("<"),
-            TOKEN::EQ => String::from("=="),
-            TOKEN::NotEQ => String::from("!="),
-            TOKEN::COMMA => String::from(","),
-            TOKEN::SEMICOLON => String::from(";"),
-            TOKEN::LPAREN => String::from("("),
-            TOKEN::RPAREN => String::from(")"),
-            TOKEN::LBRACE => String::from("{"),
-            TOKEN::RBRACE => String::from("}"),
-            TOKEN::LBRACKET => String::from("["),
-            TOKEN::RBRACKET => String::from("]"),
-            TOKEN::EOF => String::from("EOF"),
-            TOKEN::LET => String::from("LET"),
-            TOKEN::RETURN => String::from("RETURN"),
-            TOKEN::TRUE => String::from("true"),
-            TOKEN::FALSE => String::from("false"),
-            TOKEN::IF => String::from("if"),
-            TOKEN::ELSE => String::from("else"),
-            TOKEN::FUNCTION => String::from("fn"),
-            //
-            TOKEN::IDENT(_) => String::from("IDENT"),
-            TOKEN::NUMBER(_) => String::from("NUMBER"),
-            TOKEN::STRING(_) => String::from("STRING"),
-            TOKEN::ILLEGAL(_) => String::from("ILLEGAL"),
+            TOKEN::EOF => TokenKind::EOF,
+            TOKEN::ILLEGAL(_) => TokenKind::ILLEGAL,
+            TOKEN::IDENT(_) => TokenKind::IDENT,
+            TOKEN::NUMBER(_) => TokenKind::NUMBER,
+            TOKEN::STRING(_) => TokenKind::STRING,
+            TOKEN::ASSIGN => TokenKind::ASSIGN,
+            TOKEN::PLUS => TokenKind::PLUS,
+            TOKEN::MINUS => TokenKind::MINUS,
+            TOKEN::BANG => TokenKind::BANG,
+            TOKEN::ASTERISK => TokenKind::ASTERISK,
+            TOKEN::SLASH => TokenKind::SLASH,
+            TOKEN::GT => TokenKind::GT,
+            TOKEN::LT => TokenKind::LT,
+            TOKEN::EQ => TokenKind::EQ,
+            TOKEN::NotEQ => TokenKind::NotEQ,
+            TOKEN::COMMA => TokenKind::COMMA,
+            TOKEN::COLON => TokenKind::COLON,
+            TOKEN::DOT => TokenKind::DOT,
+            TOKEN::SEMICOLON => TokenKind::SEMICOLON,
+            TOKEN::LPAREN => TokenKind::LPAREN,
+            TOKEN::RPAREN => TokenKind::RPAREN,
+            TOKEN::LBRACE => TokenKind::LBRACE,
+            TOKEN::RBRACE => TokenKind::RBRACE,
+            TOKEN::LBRACKET => TokenKind::LBRACKET,
+            TOKEN::RBRACKET => TokenKind::RBRACKET,
+            TOKEN::FUNCTION => TokenKind::FUNCTION,
+            TOKEN::LET => TokenKind::LET,
+            TOKEN::TRUE => TokenKind::TRUE,
+            TOKEN::FALSE => TokenKind::FALSE,
+            TOKEN::IF => TokenKind::IF,
+            TOKEN::ELSE => TokenKind::ELSE,
+            TOKEN::RETURN => TokenKind::RETURN,
         }
     }
+}
+
+// Same variants as TOKEN without their values, cheap to copy and usable as
+// an index into the parser dispatch tables
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum TokenKind {
+    EOF,
+    ILLEGAL,
+
+    IDENT,
+    NUMBER,
+    STRING,
+
+    ASSIGN,
+    PLUS,
+    MINUS,
+    BANG,
+    ASTERISK,
+    SLASH,
+
+    GT,
+    LT,
+    EQ,
+    NotEQ,
+
+    COMMA,
+    COLON,
+    DOT,
+    SEMICOLON,
+    LPAREN,
+    RPAREN,
+    LBRACE,
+    RBRACE,
+    LBRACKET,
+    RBRACKET,
+
+    FUNCTION,
+    LET,
+    TRUE,
+    FALSE,
+    IF,
+    ELSE,
+    RETURN,
+}
+
+impl TokenKind {
+    pub const COUNT: usize = TokenKind::RETURN as usize + 1;
 
-    pub fn is_same_with(&self, token: TOKEN) -> bool {
-        // Check 2 token are same type without checking the value inside
-        return discriminant(self) == discriminant(&token);
+    // The token of a kind that carries no value
+    pub fn to_token(self) -> Option<TOKEN> {
+        let token = match self {
+            TokenKind::EOF => TOKEN::EOF,
+            TokenKind::ASSIGN => TOKEN::ASSIGN,
+            TokenKind::PLUS => TOKEN::PLUS,
+            TokenKind::MINUS => TOKEN::MINUS,
+            TokenKind::BANG => TOKEN::BANG,
+            TokenKind::ASTERISK => TOKEN::ASTERISK,
+            TokenKind::SLASH => TOKEN::SLASH,
+            TokenKind::GT => TOKEN::GT,
+            TokenKind::LT => TOKEN::LT,
+            TokenKind::EQ => TOKEN::EQ,
+            TokenKind::NotEQ => TOKEN::NotEQ,
+            TokenKind::COMMA => TOKEN::COMMA,
+            TokenKind::COLON => TOKEN::COLON,
+            TokenKind::DOT => TOKEN::DOT,
+            TokenKind::SEMICOLON => TOKEN::SEMICOLON,
+            TokenKind::LPAREN => TOKEN::LPAREN,
+            TokenKind::RPAREN => TOKEN::RPAREN,
+            TokenKind::LBRACE => TOKEN::LBRACE,
+            TokenKind::RBRACE => TOKEN::RBRACE,
+            TokenKind::LBRACKET => TOKEN::LBRACKET,
+            TokenKind::RBRACKET => TOKEN::RBRACKET,
+            TokenKind::FUNCTION => TOKEN::FUNCTION,
+            TokenKind::LET => TOKEN::LET,
+            TokenKind::TRUE => TOKEN::TRUE,
+            TokenKind::FALSE => TOKEN::FALSE,
+            TokenKind::IF => TOKEN::IF,
+            TokenKind::ELSE => TOKEN::ELSE,
+            TokenKind::RETURN => TOKEN::RETURN,
+            TokenKind::ILLEGAL | TokenKind::IDENT | TokenKind::NUMBER | TokenKind::STRING => {
+                return None
+            }
+        };
+        Some(token)
     }
 }
 
-pub static KEYWORDS: Lazy<HashMap<&'static str, TOKEN>> = Lazy::new(|| {
+pub static KEYWORDS: Lazy<HashMap<&'static str, TokenKind>> = Lazy::new(|| {
     let mut m = HashMap::new();
-    m.insert("fn", TOKEN::FUNCTION);
-    m.insert("let", TOKEN::LET);
-    m.insert("true", TOKEN::TRUE);
-    m.insert("false", TOKEN::FALSE);
-    m.insert("if", TOKEN::IF);
-    m.insert("else", TOKEN::ELSE);
-    m.insert("return", TOKEN::RETURN);
+    m.insert("fn", TokenKind::FUNCTION);
+    m.insert("let", TokenKind::LET);
+    m.insert("true", TokenKind::TRUE);
+    m.insert("false", TokenKind::FALSE);
+    m.insert("if", TokenKind::IF);
+    m.insert("else", TokenKind::ELSE);
+    m.insert("return", TokenKind::RETURN);
     m
 });
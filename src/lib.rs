@@ -0,0 +1,20 @@
+// Derived conversions name items by `::r_piece::...`, also inside this crate
+extern crate self as r_piece;
+
+pub mod ast;
+pub mod debugger;
+pub mod errors;
+pub mod evaluator;
+pub mod formatter;
+pub mod interpreter;
+pub mod lexer;
+pub mod linter;
+pub mod lsp;
+pub mod optimizer;
+pub mod parser;
+pub mod resolver;
+pub mod utils;
+
+pub use errors::interpreter_errs::InterpreterErr;
+pub use evaluator::convert::{FromObject, IntoObject};
+pub use interpreter::interpreter::{Callee, Interpreter};
@@ -0,0 +1,350 @@
+use std::collections::HashSet;
+
+use crate::{
+    ast::ast::{Expression, InfixExpression, Program, Span, Statement},
+    evaluator::builtin::BuiltinRegistry,
+    lexer::token::TOKEN,
+    resolver::resolver::{resolve, static_type, BindingKind, Resolution, Target},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rule {
+    UnusedLet,
+    UnusedParam,
+    Shadow,
+    Unreachable,
+    InvalidComparison,
+    BuiltinArity,
+    UndeclaredAssign,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error, // fails whenever the code runs
+}
+
+impl Rule {
+    pub const ALL: [Rule; 7] = [
+        Rule::UnusedLet,
+        Rule::UnusedParam,
+        Rule::Shadow,
+        Rule::Unreachable,
+        Rule::InvalidComparison,
+        Rule::BuiltinArity,
+        Rule::UndeclaredAssign,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::UnusedLet => "unused-let",
+            Rule::UnusedParam => "unused-param",
+            Rule::Shadow => "shadowed-name",
+            Rule::Unreachable => "unreachable-code",
+            Rule::InvalidComparison => "invalid-comparison",
+            Rule::BuiltinArity => "builtin-arity",
+            Rule::UndeclaredAssign => "undeclared-assign",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|x| x.name() == name)
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Rule::UnusedLet | Rule::UnusedParam | Rule::Shadow | Rule::Unreachable => {
+                Severity::Warning
+            }
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub message: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    pub rules: HashSet<Rule>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            rules: Rule::ALL.into_iter().collect(),
+        }
+    }
+}
+
+impl LintConfig {
+    // A config checking only the given rules
+    pub fn only(rules: &[Rule]) -> Self {
+        Self {
+            rules: rules.iter().copied().collect(),
+        }
+    }
+}
+
+// Checks the program with the enabled rules, diagnostics come in source order
+pub fn lint(program: &Program, builtins: &BuiltinRegistry, config: &LintConfig) -> Vec<Diagnostic> {
+    let resolution = resolve(program, builtins);
+    let mut linter = Linter {
+        builtins,
+        resolution: &resolution,
+        diagnostics: vec![],
+    };
+    linter.bindings();
+    linter.statements(&program.statements);
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.retain(|x| config.rules.contains(&x.rule));
+    diagnostics.sort_by_key(|x| (x.span.start, x.span.end, x.rule));
+    diagnostics
+}
+
+// 1-based line and column of a byte offset, columns count chars
+pub fn line_col(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset.min(src.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |x| x + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+struct Linter<'a> {
+    builtins: &'a BuiltinRegistry,
+    resolution: &'a Resolution,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, rule: Rule, span: Span, message: String) {
+        self.diagnostics.push(Diagnostic {
+            rule,
+            message,
+            span,
+        });
+    }
+
+    // Rules on names: unused, shadowed and undeclared
+    fn bindings(&mut self) {
+        let resolution = self.resolution;
+        for (idx, binding) in resolution.bindings.iter().enumerate() {
+            // `_name` is unused on purpose
+            if binding.name.starts_with('_') {
+                continue;
+            }
+            if !resolution.is_read(idx) {
+                let (rule, what) = match binding.kind {
+                    BindingKind::Let => (Rule::UnusedLet, "variable"),
+                    BindingKind::Param => (Rule::UnusedParam, "parameter"),
+                };
+                let message = format!("unused {} `{}`", what, binding.name);
+                self.report(rule, binding.span, message);
+            }
+            let mut outer = resolution.scopes[binding.scope].parent;
+            while let Some(scope) = outer {
+                let scope = &resolution.scopes[scope];
+                let shadows = scope
+                    .bindings
+                    .iter()
+                    .any(|x| resolution.bindings[*x].name == binding.name);
+                if shadows {
+                    let message =
+                        format!("`{}` shadows a binding of an enclosing scope", binding.name);
+                    self.report(Rule::Shadow, binding.span, message);
+                    break;
+                }
+                outer = scope.parent;
+            }
+        }
+        for reference in resolution.references.iter() {
+            if reference.write && !matches!(reference.target, Target::Binding(_)) {
+                let message = format!("assignment to undeclared `{}`", reference.name);
+                self.report(Rule::UndeclaredAssign, reference.span, message);
+            }
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        let returned = statements
+            .iter()
+            .position(|x| matches!(x, Statement::Return(_)));
+        if let Some(idx) = returned {
+            if let (Some(first), Some(last)) = (statements.get(idx + 1), statements.last()) {
+                let span = Span::new(first.span().start, last.span().end);
+                self.report(
+                    Rule::Unreachable,
+                    span,
+                    "unreachable code after `return`".into(),
+                );
+            }
+        }
+        for stmt in statements {
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Let(stmt) => self.expression(&stmt.value),
+            Statement::Reassign(stmt) => {
+                self.expression(&stmt.lhs);
+                self.expression(&stmt.value);
+            }
+            Statement::Return(stmt) => {
+                if let Some(expression) = &stmt.expression {
+                    self.expression(expression);
+                }
+            }
+            Statement::Expression(stmt) => {
+                if let Some(expression) = &stmt.expression {
+                    self.expression(expression);
+                }
+            }
+            Statement::Program(program) => self.statements(&program.statements),
+            Statement::Block(block) => self.statements(&block.statements),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::ArrayLiteral(arr) => arr.elements.iter().for_each(|x| self.expression(x)),
+            Expression::HashLiteral(hash) => hash.pairs.iter().for_each(|(k, v)| {
+                self.expression(k);
+                self.expression(v);
+            }),
+            Expression::Index(index) => {
+                self.expression(&index.left);
+                self.expression(&index.index);
+            }
+            Expression::Member(member) => self.expression(&member.object),
+            Expression::Prefix(prefix) => self.expression(&prefix.right),
+            Expression::Infix(infix) => {
+                self.comparison(infix);
+                self.expression(&infix.left);
+                self.expression(&infix.right);
+            }
+            Expression::If(if_exp) => {
+                self.expression(&if_exp.condition);
+                self.statements(&if_exp.consequence.statements);
+                if let Some(alternative) = &if_exp.alternative {
+                    self.statements(&alternative.statements);
+                }
+            }
+            Expression::Function(function) => self.statements(&function.body.statements),
+            Expression::Call(call) => {
+                self.arity(&call.function, call.arguments.len(), call.span);
+                self.expression(&call.function);
+                call.arguments.iter().for_each(|x| self.expression(x));
+            }
+            Expression::Identifier(_)
+            | Expression::String(_)
+            | Expression::Number(_)
+            | Expression::Bool(_) => {}
+        }
+    }
+
+    // Operands `eval_eq_expression` and `eval_order_expression` never accept
+    fn comparison(&mut self, infix: &InfixExpression) {
+        let comparable: &[&str] = match infix.operator {
+            TOKEN::EQ | TOKEN::NotEQ => &["Number", "String", "Boolean"],
+            TOKEN::LT | TOKEN::GT => &["Number", "String"],
+            _ => return,
+        };
+        let left = self.static_type(&infix.left);
+        let right = self.static_type(&infix.right);
+        let rejected = match (left, right) {
+            (Some(l), Some(r)) => l != r || !comparable.contains(&l),
+            (Some(x), None) | (None, Some(x)) => !comparable.contains(&x),
+            (None, None) => false,
+        };
+        if rejected {
+            let operand = |x: Option<&str>| x.unwrap_or("anything").to_string();
+            let message = format!(
+                "`{}` between {} and {} always fails",
+                infix.operator.literal(),
+                operand(left),
+                operand(right)
+            );
+            self.report(Rule::InvalidComparison, infix.span, message);
+        }
+    }
+
+    // Like `resolver::static_type`, also following `let` bindings that are
+    // never reassigned
+    fn static_type(&self, expression: &Expression) -> Option<&'static str> {
+        if let Expression::Identifier(ident) = expression {
+            let reference = self.resolution.reference_at(ident.1.start)?;
+            return match reference.target {
+                Target::Binding(idx) if !self.resolution.is_reassigned(idx) => {
+                    self.resolution.bindings[idx].ty
+                }
+                _ => None,
+            };
+        }
+        static_type(expression)
+    }
+
+    fn arity(&mut self, function: &Expression, count: usize, span: Span) {
+        let mut root = function;
+        while let Expression::Member(member) = root {
+            root = &member.object;
+        }
+        let Expression::Identifier(ident) = root else {
+            return;
+        };
+        let Some(reference) = self.resolution.reference_at(ident.1.start) else {
+            return;
+        };
+        if reference.target != Target::Builtin {
+            return;
+        }
+        let Some(signature) = self
+            .builtins
+            .get(&reference.name)
+            .and_then(|x| x.signature.as_ref())
+        else {
+            return;
+        };
+        let name = &reference.name;
+        let message = match signature.arity() {
+            (min, Some(max)) if min == max && count != min => {
+                format!("`{}` takes {}, got {}", name, arguments(min), count)
+            }
+            (min, _) if count < min => {
+                format!(
+                    "`{}` takes at least {}, got {}",
+                    name,
+                    arguments(min),
+                    count
+                )
+            }
+            (_, Some(max)) if count > max => {
+                format!("`{}` takes at most {}, got {}", name, arguments(max), count)
+            }
+            _ => return,
+        };
+        self.report(Rule::BuiltinArity, span, message);
+    }
+}
+
+fn arguments(count: usize) -> String {
+    match count {
+        1 => "1 argument".into(),
+        n => format!("{} arguments", n),
+    }
+}
@@ -0,0 +1,2 @@
+pub mod linter;
+mod test;
@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluator::{
+            builtin::{ArgType, BuiltinRegistry, Signature},
+            object::Object,
+        },
+        lexer::lexer::Lexer,
+        linter::linter::{line_col, lint, LintConfig, Rule},
+        parser::parser::Parser,
+    };
+
+    // The defaults and a namespaced one
+    fn builtins() -> BuiltinRegistry {
+        let mut builtins = BuiltinRegistry::with_defaults();
+        let signature = Signature::new(vec![ArgType::Any]);
+        builtins.register("host.log", Some(signature), |_, _| Ok(Object::Null));
+        builtins
+    }
+
+    // Each diagnostic as its rule name and the source it points at
+    fn lint_str(src: &str, config: &LintConfig) -> Vec<(&'static str, String)> {
+        let program = Parser::new(Lexer::new(src)).parse_program().unwrap();
+        lint(&program, &builtins(), config)
+            .into_iter()
+            .map(|x| (x.rule.name(), x.span.text(src).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint() {
+        let tests: Vec<(&str, Vec<(&str, &str)>)> = vec![
+            ("let x = 1; print(x)", vec![]),
+            ("let x = 1; let _y = 2;", vec![("unused-let", "x")]),
+            ("let x = 1; x = 2;", vec![("unused-let", "x")]),
+            (
+                "let f = fn(a, b) { a }; f(1, 2)",
+                vec![("unused-param", "b")],
+            ),
+            (
+                "let f = fn(n) { if (n < 1) { return 0; } f(n - 1) }; f(3)",
+                vec![],
+            ),
+            (
+                "let x = 1; let f = fn(x) { fn(y) { let x = y + x; x } }; f(x)",
+                vec![("shadowed-name", "x"), ("shadowed-name", "x")],
+            ),
+            // sibling functions do not shadow each other
+            ("let f = fn(x) { x }; let g = fn(x) { x }; f(g(1))", vec![]),
+            (
+                "let f = fn() { return 1; print(2); 3 }; f()",
+                vec![("unreachable-code", "print(2); 3")],
+            ),
+            ("1 == \"1\"", vec![("invalid-comparison", "1 == \"1\"")]),
+            ("[1] == [1]", vec![("invalid-comparison", "[1] == [1]")]),
+            ("true < false", vec![("invalid-comparison", "true < false")]),
+            (
+                "let s = \"a\"; if (s != 1) { 1 }",
+                vec![("invalid-comparison", "s != 1")],
+            ),
+            ("let s = \"a\"; s = 1; s == 1", vec![]),
+            ("let f = fn(x) { x == 1 }; f(1)", vec![]),
+            ("\"a\" < \"b\"; 1 + 1 == 2", vec![]),
+            (
+                "len(); len([1], 2)",
+                vec![("builtin-arity", "len()"), ("builtin-arity", "len([1], 2)")],
+            ),
+            (
+                "print(); reduce([1], fn(a, x) { a + x })",
+                vec![("builtin-arity", "reduce([1], fn(a, x) { a + x })")],
+            ),
+            ("let len = fn() { 1 }; len()", vec![]),
+            (
+                "exit(); exit(1); exit(1, 2)",
+                vec![("builtin-arity", "exit(1, 2)")],
+            ),
+            ("host.log()", vec![("builtin-arity", "host.log()")]),
+            (
+                "x = 1; print = 2",
+                vec![("undeclared-assign", "x"), ("undeclared-assign", "print")],
+            ),
+        ];
+        for (src, expected) in tests.into_iter() {
+            let expected = expected
+                .into_iter()
+                .map(|(x, y)| (x, y.to_string()))
+                .collect::<Vec<_>>();
+            assert_eq!(lint_str(src, &LintConfig::default()), expected, "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_lint_config() {
+        let src = "let x = 1; y = len();";
+        let config = LintConfig::only(&[Rule::BuiltinArity]);
+        assert_eq!(
+            lint_str(src, &config),
+            vec![("builtin-arity", "len()".to_string())]
+        );
+        let mut config = LintConfig::default();
+        config.rules.remove(&Rule::UnusedLet);
+        assert_eq!(lint_str(src, &config).len(), 2);
+        assert_eq!(Rule::from_name("shadowed-name"), Some(Rule::Shadow));
+        assert_eq!(Rule::from_name("nope"), None);
+
+        let program = Parser::new(Lexer::new("exit(1, 2)"))
+            .parse_program()
+            .unwrap();
+        let diagnostics = lint(&program, &builtins(), &LintConfig::default());
+        assert_eq!(
+            diagnostics[0].message,
+            "`exit` takes at most 1 argument, got 2"
+        );
+    }
+
+    #[test]
+    fn test_line_col() {
+        let src = "let a = 1;\n  ünï = 2;\n";
+        assert_eq!(line_col(src, 0), (1, 1));
+        assert_eq!(line_col(src, 4), (1, 5));
+        assert_eq!(line_col(src, 11), (2, 1));
+        assert_eq!(line_col(src, src.rfind('=').unwrap()), (2, 7));
+        assert_eq!(line_col(src, src.len()), (3, 1));
+    }
+}
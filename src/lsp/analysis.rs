@@ -0,0 +1,135 @@
+use crate::{
+    ast::ast::{Expression, FunctionLiteral, LetStatement, Program, Span, Statement},
+    evaluator::builtin::BuiltinRegistry,
+    lexer::{lexer::Lexer, token::TokenKind},
+    linter::linter::{lint, LintConfig, Rule, Severity},
+    parser::parser::Parser,
+    resolver::resolver::{resolve, Resolution, Target},
+};
+
+pub struct Analysis {
+    pub program: Program,
+    pub resolution: Resolution,
+}
+
+// A diagnostic of the lexer, the parser, the resolver or a lint rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub span: Span,
+    pub severity: Severity,
+    pub message: String,
+    pub rule: Option<Rule>,
+}
+
+impl Problem {
+    fn error(span: Span, message: String) -> Self {
+        Self {
+            span,
+            severity: Severity::Error,
+            message,
+            rule: None,
+        }
+    }
+}
+
+// Everything wrong with the text, and its analysis when it parses
+pub fn analyze(text: &str, builtins: &BuiltinRegistry) -> (Option<Analysis>, Vec<Problem>) {
+    let mut problems = vec![];
+    let mut lexer = Lexer::new(text);
+    loop {
+        let lexeme = lexer.next_lexeme();
+        match lexeme.kind {
+            TokenKind::EOF => break,
+            TokenKind::ILLEGAL if lexeme.text.starts_with('"') => {
+                problems.push(Problem::error(lexeme.span, "unterminated string".into()));
+            }
+            TokenKind::ILLEGAL => {
+                let message = format!("illegal character `{}`", lexeme.text);
+                problems.push(Problem::error(lexeme.span, message));
+            }
+            _ => {}
+        }
+    }
+
+    let mut parser = Parser::new(Lexer::new(text));
+    let program = match parser.parse_program() {
+        Ok(program) => program,
+        Err(err) => {
+            // a lexer error before where parsing stopped already explains it
+            let span = parser.cur_token.span;
+            if !problems.iter().any(|x| x.span.start <= span.start) {
+                problems.push(Problem::error(span, err.to_string()));
+            }
+            return (None, problems);
+        }
+    };
+    let resolution = resolve(&program, builtins);
+    for reference in resolution.references.iter() {
+        // writes are reported by the undeclared-assign rule
+        if reference.target == Target::Unresolved && !reference.write {
+            let message = format!("`{}` is not defined", reference.name);
+            problems.push(Problem::error(reference.span, message));
+        }
+    }
+    for diagnostic in lint(&program, builtins, &LintConfig::default()) {
+        problems.push(Problem {
+            span: diagnostic.span,
+            severity: diagnostic.rule.severity(),
+            message: diagnostic.message,
+            rule: Some(diagnostic.rule),
+        });
+    }
+    problems.sort_by_key(|x| (x.span.start, x.span.end));
+    (
+        Some(Analysis {
+            program,
+            resolution,
+        }),
+        problems,
+    )
+}
+
+impl Analysis {
+    // The `let` binding the name at `span`
+    pub fn let_statement(&self, span: Span) -> Option<&LetStatement> {
+        find_let(&self.program.statements, span)
+    }
+}
+
+fn find_let(statements: &[Statement], span: Span) -> Option<&LetStatement> {
+    statements.iter().find_map(|stmt| match stmt {
+        Statement::Let(stmt) if stmt.name.1 == span => Some(stmt),
+        Statement::Let(stmt) => find_let_in(&stmt.value, span),
+        Statement::Reassign(stmt) => find_let_in(&stmt.value, span),
+        Statement::Return(stmt) => stmt.expression.as_ref().and_then(|x| find_let_in(x, span)),
+        Statement::Expression(stmt) => stmt.expression.as_ref().and_then(|x| find_let_in(x, span)),
+        Statement::Program(program) => find_let(&program.statements, span),
+        Statement::Block(block) => find_let(&block.statements, span),
+    })
+}
+
+fn find_let_in(expression: &Expression, span: Span) -> Option<&LetStatement> {
+    match expression {
+        Expression::Function(function) => find_let(&function.body.statements, span),
+        Expression::If(if_exp) => find_let(&if_exp.consequence.statements, span).or_else(|| {
+            let alternative = if_exp.alternative.as_ref()?;
+            find_let(&alternative.statements, span)
+        }),
+        Expression::Call(call) => std::iter::once(&call.function)
+            .chain(call.arguments.iter())
+            .find_map(|x| find_let_in(x, span)),
+        Expression::ArrayLiteral(arr) => arr.elements.iter().find_map(|x| find_let_in(x, span)),
+        Expression::HashLiteral(hash) => hash.pairs.iter().find_map(|(_, v)| find_let_in(v, span)),
+        _ => None,
+    }
+}
+
+// `fn(a, b)`
+pub fn function_signature(function: &FunctionLiteral) -> String {
+    let params = function
+        .parameters
+        .iter()
+        .map(|x| x.0.as_str())
+        .collect::<Vec<&str>>();
+    format!("fn({})", params.join(", "))
+}
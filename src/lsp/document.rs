@@ -0,0 +1,94 @@
+use serde_json::{json, Value};
+
+use crate::{ast::ast::Span, evaluator::builtin::BuiltinRegistry};
+
+use super::analysis::{analyze, Analysis, Problem};
+
+// An open text document. The analysis is kept from the last version that
+// parsed, so editing a broken file still has names to offer.
+pub struct Document {
+    pub text: String,
+    pub version: i64,
+    pub analysis: Option<Analysis>,
+    pub fresh: bool, // the analysis is of this text
+    pub problems: Vec<Problem>,
+}
+
+impl Document {
+    pub fn new(text: String, version: i64, builtins: &BuiltinRegistry) -> Self {
+        let mut document = Document {
+            text: String::new(),
+            version,
+            analysis: None,
+            fresh: false,
+            problems: vec![],
+        };
+        document.update(text, version, builtins);
+        document
+    }
+
+    pub fn update(&mut self, text: String, version: i64, builtins: &BuiltinRegistry) {
+        let (analysis, problems) = analyze(&text, builtins);
+        self.fresh = analysis.is_some();
+        if analysis.is_some() {
+            self.analysis = analysis;
+        }
+        self.text = text;
+        self.version = version;
+        self.problems = problems;
+    }
+
+    // The analysis when it matches the text, spans of a stale one may point
+    // anywhere
+    pub fn current(&self) -> Option<&Analysis> {
+        match self.fresh {
+            true => self.analysis.as_ref(),
+            false => None,
+        }
+    }
+
+    pub fn position(&self, offset: usize) -> Value {
+        let (line, character) = position(&self.text, offset);
+        json!({ "line": line, "character": character })
+    }
+
+    pub fn range(&self, span: Span) -> Value {
+        json!({ "start": self.position(span.start), "end": self.position(span.end) })
+    }
+
+    // The byte offset of an LSP position, clamped to the text
+    pub fn offset(&self, position: &Value) -> usize {
+        let line = position["line"].as_u64().unwrap_or(0) as usize;
+        let character = position["character"].as_u64().unwrap_or(0) as usize;
+        offset(&self.text, line, character)
+    }
+}
+
+// Line and UTF-16 character of a byte offset, as LSP counts them
+pub fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |x| x + 1);
+    let line = before.matches('\n').count();
+    (line, before[line_start..].encode_utf16().count())
+}
+
+pub fn offset(text: &str, line: usize, character: usize) -> usize {
+    let mut line_start = 0;
+    for _ in 0..line {
+        match text[line_start..].find('\n') {
+            Some(idx) => line_start += idx + 1,
+            None => return text.len(),
+        }
+    }
+    let line_end = text[line_start..]
+        .find('\n')
+        .map_or(text.len(), |x| line_start + x);
+    let mut units = 0;
+    for (idx, ch) in text[line_start..line_end].char_indices() {
+        if units >= character {
+            return line_start + idx;
+        }
+        units += ch.len_utf16();
+    }
+    line_end
+}
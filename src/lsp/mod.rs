@@ -0,0 +1,5 @@
+pub mod analysis;
+pub mod document;
+pub mod server;
+mod test;
+pub mod transport;
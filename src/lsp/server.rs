@@ -0,0 +1,626 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    ast::ast::{Expression, IfExpression, Span, Statement},
+    errors::lsp_errs::LspErr,
+    evaluator::builtin::{builtin_doc, BuiltinRegistry},
+    lexer::{
+        lexer::{comments, is_letter, Lexer},
+        token::{TokenKind, KEYWORDS},
+    },
+    linter::linter::{Rule, Severity},
+    resolver::resolver::{BindingKind, Target},
+};
+
+use super::{
+    analysis::function_signature,
+    document::{position, Document},
+    transport::{read_message, write_message},
+};
+
+// Semantic token types and modifiers, indexed by the encoded tokens
+pub const TOKEN_TYPES: [&str; 8] = [
+    "keyword",
+    "variable",
+    "parameter",
+    "function",
+    "string",
+    "number",
+    "operator",
+    "comment",
+];
+pub const TOKEN_MODIFIERS: [&str; 2] = ["declaration", "defaultLibrary"];
+
+const DECLARATION: u32 = 1;
+const DEFAULT_LIBRARY: u32 = 2;
+
+// LSP enums
+const SYMBOL_FUNCTION: u32 = 12;
+const SYMBOL_VARIABLE: u32 = 13;
+const COMPLETION_FUNCTION: u32 = 3;
+const COMPLETION_VARIABLE: u32 = 6;
+const COMPLETION_KEYWORD: u32 = 14;
+
+// A language server for r-piece scripts. Messages go in one at a time, the
+// transport is left to `run`, so the server can be driven from tests.
+pub struct Server {
+    documents: HashMap<String, Document>,
+    builtins: BuiltinRegistry,
+    initialized: bool,
+    shutdown: bool,
+    exited: bool,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self::new(BuiltinRegistry::with_defaults())
+    }
+}
+
+impl Server {
+    pub fn new(builtins: BuiltinRegistry) -> Self {
+        Self {
+            documents: HashMap::new(),
+            builtins,
+            initialized: false,
+            shutdown: false,
+            exited: false,
+        }
+    }
+
+    // The `exit` notification was received
+    pub fn exited(&self) -> bool {
+        self.exited
+    }
+
+    // The process exit code the protocol asks for
+    pub fn exit_code(&self) -> u8 {
+        match self.shutdown {
+            true => 0,
+            false => 1,
+        }
+    }
+
+    // Handles one message, returns the response to a request followed by any
+    // notifications to send
+    pub fn handle(&mut self, message: Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default().to_string();
+        let params = message["params"].clone();
+        if method == "exit" {
+            self.exited = true;
+            return vec![];
+        }
+        let id = match message.get("id") {
+            Some(id) => id.clone(),
+            // notifications, and responses to requests the server never makes
+            None => return self.notification(&method, &params).unwrap_or_default(),
+        };
+        if method.is_empty() {
+            return vec![];
+        }
+        let result = match (self.initialized, self.shutdown, method.as_str()) {
+            (_, _, "initialize") => Ok(self.initialize()),
+            (false, _, _) => Err(LspErr::NotInitialized),
+            (_, true, _) => Err(LspErr::InvalidRequest("the server is shut down".into())),
+            (_, _, "shutdown") => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            (_, _, method) => self.request(method, &params),
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(err) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": err.code(), "message": err.to_string() },
+            }),
+        };
+        vec![response]
+    }
+
+    fn initialize(&mut self) -> Value {
+        self.initialized = true;
+        json!({
+            "capabilities": {
+                "textDocumentSync": { "openClose": true, "change": 1 },
+                "hoverProvider": true,
+                "definitionProvider": true,
+                "referencesProvider": true,
+                "renameProvider": true,
+                "documentSymbolProvider": true,
+                "completionProvider": { "triggerCharacters": ["."] },
+                "semanticTokensProvider": {
+                    "legend": { "tokenTypes": TOKEN_TYPES, "tokenModifiers": TOKEN_MODIFIERS },
+                    "full": true,
+                },
+            },
+            "serverInfo": { "name": "r-piece", "version": env!("CARGO_PKG_VERSION") },
+        })
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> Option<Vec<Value>> {
+        let uri = params["textDocument"]["uri"].as_str()?.to_string();
+        let version = params["textDocument"]["version"].as_i64().unwrap_or(0);
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str()?.to_string();
+                let document = Document::new(text, version, &self.builtins);
+                self.documents.insert(uri.clone(), document);
+            }
+            // full sync, the last change has the whole text
+            "textDocument/didChange" => {
+                let text = params["contentChanges"].as_array()?.last()?["text"].as_str()?;
+                let document = self.documents.get_mut(&uri)?;
+                document.update(text.to_string(), version, &self.builtins);
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return Some(vec![publish_diagnostics(&uri, vec![])]);
+            }
+            _ => return None,
+        }
+        Some(vec![self.diagnostics(&uri)])
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, LspErr> {
+        match method {
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/references" => self.references(params),
+            "textDocument/rename" => self.rename(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/semanticTokens/full" => self.semantic_tokens(params),
+            method => Err(LspErr::MethodNotFound(method.to_string())),
+        }
+    }
+
+    fn document(&self, params: &Value) -> Result<(&str, &Document), LspErr> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .ok_or(LspErr::InvalidParams("missing textDocument.uri".into()))?;
+        let (uri, document) = self
+            .documents
+            .get_key_value(uri)
+            .ok_or(LspErr::UnknownDocument(uri.to_string()))?;
+        Ok((uri.as_str(), document))
+    }
+
+    fn diagnostics(&self, uri: &str) -> Value {
+        let Some(document) = self.documents.get(uri) else {
+            return publish_diagnostics(uri, vec![]);
+        };
+        let diagnostics = document
+            .problems
+            .iter()
+            .map(|problem| {
+                let mut diagnostic = json!({
+                    "range": document.range(problem.span),
+                    "severity": match problem.severity {
+                        Severity::Error => 1,
+                        Severity::Warning => 2,
+                    },
+                    "source": "r-piece",
+                    "message": problem.message,
+                });
+                if let Some(rule) = problem.rule {
+                    diagnostic["code"] = json!(rule.name());
+                }
+                // editors fade out unused and unreachable code
+                if matches!(
+                    problem.rule,
+                    Some(Rule::UnusedLet | Rule::UnusedParam | Rule::Unreachable)
+                ) {
+                    diagnostic["tags"] = json!([1]);
+                }
+                diagnostic
+            })
+            .collect();
+        let mut notification = publish_diagnostics(uri, diagnostics);
+        notification["params"]["version"] = json!(document.version);
+        notification
+    }
+
+    fn hover(&self, params: &Value) -> Result<Value, LspErr> {
+        let (_, document) = self.document(params)?;
+        let Some(analysis) = document.current() else {
+            return Ok(Value::Null);
+        };
+        let offset = document.offset(&params["position"]);
+        let resolution = &analysis.resolution;
+
+        let (span, contents) = if let Some(idx) = resolution.binding_at(offset) {
+            let binding = &resolution.bindings[idx];
+            let span = match binding.span.contains(offset) {
+                true => binding.span,
+                false => resolution
+                    .reference_at(offset)
+                    .map_or(binding.span, |x| x.span),
+            };
+            let detail =
+                match binding.kind {
+                    BindingKind::Param => format!("{} (parameter)", binding.name),
+                    BindingKind::Let => {
+                        let function = analysis.let_statement(binding.span).and_then(|x| match &x
+                            .value
+                        {
+                            Expression::Function(function) => Some(function_signature(function)),
+                            _ => None,
+                        });
+                        match (function, binding.ty) {
+                            (Some(function), _) => format!("let {} = {}", binding.name, function),
+                            (None, Some(ty)) => format!("let {}: {}", binding.name, ty),
+                            (None, None) => format!("let {}", binding.name),
+                        }
+                    }
+                };
+            (span, code_block(&detail))
+        } else {
+            let reference = resolution.reference_at(offset);
+            let Some(reference) = reference.filter(|x| x.target == Target::Builtin) else {
+                return Ok(Value::Null);
+            };
+            let builtin = self.builtins.get(&reference.name);
+            let signature = match builtin.and_then(|x| x.signature.as_ref()) {
+                Some(signature) => signature.describe(&reference.name),
+                None => format!("{}(...)", reference.name),
+            };
+            let mut contents = code_block(&format!("builtin {}", signature));
+            if let Some(doc) = builtin_doc(&reference.name) {
+                contents = format!("{}\n{}", contents, doc);
+            }
+            (reference.span, contents)
+        };
+        Ok(json!({
+            "contents": { "kind": "markdown", "value": contents },
+            "range": document.range(span),
+        }))
+    }
+
+    fn definition(&self, params: &Value) -> Result<Value, LspErr> {
+        let (uri, document) = self.document(params)?;
+        let Some(analysis) = document.current() else {
+            return Ok(Value::Null);
+        };
+        let offset = document.offset(&params["position"]);
+        let resolution = &analysis.resolution;
+        Ok(match resolution.binding_at(offset) {
+            Some(idx) => location(uri, document, resolution.bindings[idx].span),
+            None => Value::Null,
+        })
+    }
+
+    fn references(&self, params: &Value) -> Result<Value, LspErr> {
+        let (uri, document) = self.document(params)?;
+        let include_declaration = params["context"]["includeDeclaration"]
+            .as_bool()
+            .unwrap_or(true);
+        let locations = self
+            .occurrences(document, &params["position"], include_declaration)
+            .into_iter()
+            .map(|span| location(uri, document, span))
+            .collect::<Vec<Value>>();
+        Ok(json!(locations))
+    }
+
+    fn rename(&self, params: &Value) -> Result<Value, LspErr> {
+        let (uri, document) = self.document(params)?;
+        let new_name = params["newName"]
+            .as_str()
+            .ok_or(LspErr::InvalidParams("missing newName".into()))?;
+        if new_name.is_empty()
+            || !new_name.chars().all(is_letter)
+            || KEYWORDS.contains_key(new_name)
+        {
+            return Err(LspErr::Rename(format!(
+                "`{}` is not a valid name",
+                new_name
+            )));
+        }
+        let spans = self.occurrences(document, &params["position"], true);
+        if spans.is_empty() {
+            return Err(LspErr::Rename("no variable or parameter here".into()));
+        }
+        let edits = spans
+            .into_iter()
+            .map(|span| json!({ "range": document.range(span), "newText": new_name }))
+            .collect::<Vec<Value>>();
+        Ok(json!({ "changes": { uri: edits } }))
+    }
+
+    // Spans of the binding at the position and of its references
+    fn occurrences(&self, document: &Document, position: &Value, declaration: bool) -> Vec<Span> {
+        let Some(analysis) = document.current() else {
+            return vec![];
+        };
+        let resolution = &analysis.resolution;
+        let Some(idx) = resolution.binding_at(document.offset(position)) else {
+            return vec![];
+        };
+        let mut spans = resolution
+            .references_to(idx)
+            .map(|x| x.span)
+            .collect::<Vec<Span>>();
+        if declaration {
+            spans.push(resolution.bindings[idx].span);
+        }
+        spans.sort_by_key(|x| x.start);
+        spans
+    }
+
+    fn document_symbols(&self, params: &Value) -> Result<Value, LspErr> {
+        let (_, document) = self.document(params)?;
+        let Some(analysis) = document.current() else {
+            return Ok(json!([]));
+        };
+        Ok(json!(symbols(document, &analysis.program.statements)))
+    }
+
+    fn completion(&self, params: &Value) -> Result<Value, LspErr> {
+        let (_, document) = self.document(params)?;
+        let offset = document.offset(&params["position"]);
+        // `ns.` offers the builtins of the namespace
+        let word_start = document.text[..offset]
+            .rfind(|x: char| !is_letter(x) && x != '.')
+            .map_or(0, |x| x + 1);
+        let word = &document.text[word_start..offset];
+        if let Some((namespace, _)) = word.rsplit_once('.') {
+            let prefix = format!("{}.", namespace);
+            let mut items = self
+                .builtins
+                .names()
+                .filter_map(|x| x.strip_prefix(&prefix).map(|rest| (x, rest)))
+                .map(|(name, label)| self.builtin_item(name, label))
+                .collect::<Vec<Value>>();
+            items.sort_by_key(|x| x["label"].as_str().unwrap_or_default().to_string());
+            return Ok(json!(items));
+        }
+
+        let mut items = vec![];
+        let mut seen = vec![];
+        if let Some(analysis) = &document.analysis {
+            let resolution = &analysis.resolution;
+            for idx in resolution.visible(resolution.scope_at(offset)) {
+                let binding = &resolution.bindings[idx];
+                if seen.contains(&binding.name) {
+                    continue;
+                }
+                seen.push(binding.name.clone());
+                let kind = match binding.ty {
+                    Some("Function") => COMPLETION_FUNCTION,
+                    _ => COMPLETION_VARIABLE,
+                };
+                let mut item = json!({ "label": binding.name, "kind": kind });
+                if let Some(ty) = binding.ty {
+                    item["detail"] = json!(ty);
+                }
+                items.push(item);
+            }
+        }
+        let mut builtins = self
+            .builtins
+            .names()
+            .filter(|x| !x.contains('.') && !seen.iter().any(|name| name == x))
+            .collect::<Vec<&str>>();
+        builtins.sort();
+        items.extend(builtins.into_iter().map(|x| self.builtin_item(x, x)));
+        let mut keywords = KEYWORDS.keys().collect::<Vec<_>>();
+        keywords.sort();
+        items.extend(
+            keywords
+                .into_iter()
+                .map(|x| json!({ "label": x, "kind": COMPLETION_KEYWORD })),
+        );
+        Ok(json!(items))
+    }
+
+    fn builtin_item(&self, name: &str, label: &str) -> Value {
+        let mut item = json!({ "label": label, "kind": COMPLETION_FUNCTION });
+        if let Some(signature) = self.builtins.get(name).and_then(|x| x.signature.as_ref()) {
+            item["detail"] = json!(signature.describe(name));
+        }
+        if let Some(doc) = builtin_doc(name) {
+            item["documentation"] = json!(doc);
+        }
+        item
+    }
+
+    // Tokens are typed by their TOKEN kind, identifiers by what they resolve to
+    fn semantic_tokens(&self, params: &Value) -> Result<Value, LspErr> {
+        let (_, document) = self.document(params)?;
+        let text = &document.text;
+        let resolution = document.current().map(|x| &x.resolution);
+
+        let mut tokens = comments(text)
+            .into_iter()
+            .map(|span| (span, 7, 0))
+            .collect::<Vec<(Span, u32, u32)>>();
+        let mut lexer = Lexer::new(text);
+        loop {
+            let lexeme = lexer.next_lexeme();
+            let token_type = match lexeme.kind {
+                TokenKind::EOF => break,
+                TokenKind::FUNCTION
+                | TokenKind::LET
+                | TokenKind::TRUE
+                | TokenKind::FALSE
+                | TokenKind::IF
+                | TokenKind::ELSE
+                | TokenKind::RETURN => 0,
+                TokenKind::STRING => 4,
+                TokenKind::NUMBER => 5,
+                TokenKind::ASSIGN
+                | TokenKind::PLUS
+                | TokenKind::MINUS
+                | TokenKind::BANG
+                | TokenKind::ASTERISK
+                | TokenKind::SLASH
+                | TokenKind::GT
+                | TokenKind::LT
+                | TokenKind::EQ
+                | TokenKind::NotEQ => 6,
+                TokenKind::IDENT => {
+                    let Some(resolution) = resolution else {
+                        tokens.push((lexeme.span, 1, 0));
+                        continue;
+                    };
+                    let offset = lexeme.span.start;
+                    let reference = resolution.reference_at(offset);
+                    if reference.is_some_and(|x| x.target == Target::Builtin) {
+                        tokens.push((lexeme.span, 3, DEFAULT_LIBRARY));
+                        continue;
+                    }
+                    let (token_type, modifiers) = match resolution.binding_at(offset) {
+                        Some(idx) => {
+                            let binding = &resolution.bindings[idx];
+                            let token_type = match (binding.kind, binding.ty) {
+                                (BindingKind::Param, _) => 2,
+                                (_, Some("Function")) => 3,
+                                _ => 1,
+                            };
+                            let declaration = binding.span == lexeme.span;
+                            (token_type, if declaration { DECLARATION } else { 0 })
+                        }
+                        None => (1, 0),
+                    };
+                    tokens.push((lexeme.span, token_type, modifiers));
+                    continue;
+                }
+                _ => continue,
+            };
+            tokens.push((lexeme.span, token_type, 0));
+        }
+        tokens.sort_by_key(|x| x.0.start);
+        Ok(json!({ "data": encode_tokens(text, &tokens) }))
+    }
+}
+
+// Serves the protocol until `exit` or the end of input. Returns the exit
+// code, 0 only when `shutdown` came first.
+pub fn run(reader: &mut impl BufRead, writer: &mut impl Write) -> u8 {
+    let mut server = Server::default();
+    loop {
+        let message = match read_message(reader) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(err @ LspErr::Parse(_)) => {
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": { "code": err.code(), "message": err.to_string() },
+                });
+                if write_message(writer, &response).is_err() {
+                    break;
+                }
+                continue;
+            }
+            Err(_) => break,
+        };
+        for response in server.handle(message) {
+            if write_message(writer, &response).is_err() {
+                return 1;
+            }
+        }
+        if server.exited() {
+            break;
+        }
+    }
+    server.exit_code()
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn location(uri: &str, document: &Document, span: Span) -> Value {
+    json!({ "uri": uri, "range": document.range(span) })
+}
+
+fn code_block(code: &str) -> String {
+    format!("```r-piece\n{}\n```", code)
+}
+
+// `let` statements as symbols, the ones in a function body are its children
+fn symbols(document: &Document, statements: &[Statement]) -> Vec<Value> {
+    let mut found = vec![];
+    for stmt in statements {
+        match stmt {
+            Statement::Let(stmt) => {
+                let (kind, detail, children) = match &stmt.value {
+                    Expression::Function(function) => (
+                        SYMBOL_FUNCTION,
+                        function_signature(function),
+                        symbols(document, &function.body.statements),
+                    ),
+                    _ => (SYMBOL_VARIABLE, String::new(), vec![]),
+                };
+                found.push(json!({
+                    "name": stmt.name.0,
+                    "detail": detail,
+                    "kind": kind,
+                    "range": document.range(stmt.span),
+                    "selectionRange": document.range(stmt.name.1),
+                    "children": children,
+                }));
+            }
+            // blocks of an `if` share the enclosing scope
+            Statement::Expression(stmt) => {
+                if let Some(Expression::If(if_exp)) = &stmt.expression {
+                    found.extend(symbols_of_if(document, if_exp));
+                }
+            }
+            Statement::Block(block) => found.extend(symbols(document, &block.statements)),
+            _ => {}
+        }
+    }
+    found
+}
+
+fn symbols_of_if(document: &Document, if_exp: &IfExpression) -> Vec<Value> {
+    let mut found = symbols(document, &if_exp.consequence.statements);
+    if let Some(alternative) = &if_exp.alternative {
+        found.extend(symbols(document, &alternative.statements));
+    }
+    found
+}
+
+// Relative line, start, length, type and modifiers for each token, tokens on
+// several lines are split at line ends
+fn encode_tokens(text: &str, tokens: &[(Span, u32, u32)]) -> Vec<u32> {
+    let mut data = vec![];
+    let (mut prev_line, mut prev_start) = (0, 0);
+    for (span, token_type, modifiers) in tokens.iter() {
+        let mut start = span.start;
+        for piece in text[span.start..span.end].split_inclusive('\n') {
+            let (line, character) = position(text, start);
+            let length = piece.trim_end_matches(['\n', '\r']).encode_utf16().count();
+            start += piece.len();
+            if length == 0 {
+                continue;
+            }
+            let delta_start = match line == prev_line {
+                true => character - prev_start,
+                false => character,
+            };
+            data.extend([
+                (line - prev_line) as u32,
+                delta_start as u32,
+                length as u32,
+                *token_type,
+                *modifiers,
+            ]);
+            (prev_line, prev_start) = (line, character);
+        }
+    }
+    data
+}
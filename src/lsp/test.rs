@@ -0,0 +1,445 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Cursor};
+
+    use serde_json::{json, Value};
+
+    use crate::lsp::{
+        document::{offset, position},
+        server::run,
+        transport::{read_message, write_message},
+    };
+
+    const URI: &str = "file:///a.rp";
+
+    // A scripted client: messages are framed like an editor would send them,
+    // the whole script is served, then the output is decoded
+    struct Harness {
+        input: Vec<u8>,
+        next_id: i64,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let mut harness = Harness {
+                input: vec![],
+                next_id: 1,
+            };
+            harness.request("initialize", json!({ "capabilities": {} }));
+            harness.notify("initialized", json!({}));
+            harness
+        }
+
+        fn send(&mut self, message: Value) {
+            write_message(&mut self.input, &message).unwrap();
+        }
+
+        fn request(&mut self, method: &str, params: Value) -> i64 {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+            id
+        }
+
+        fn notify(&mut self, method: &str, params: Value) {
+            self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+        }
+
+        fn open(&mut self, text: &str) {
+            let document =
+                json!({ "uri": URI, "languageId": "r-piece", "version": 1, "text": text });
+            self.notify("textDocument/didOpen", json!({ "textDocument": document }));
+        }
+
+        // A request at a position of the open document
+        fn at(&mut self, method: &str, line: usize, character: usize) -> i64 {
+            let params = json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": line, "character": character },
+            });
+            self.request(method, params)
+        }
+
+        // Serves the script, shutting down cleanly after it
+        fn finish(mut self) -> Session {
+            self.request("shutdown", Value::Null);
+            self.notify("exit", Value::Null);
+            self.run()
+        }
+
+        fn run(self) -> Session {
+            let mut output = vec![];
+            let code = run(&mut Cursor::new(self.input), &mut output);
+            let mut reader = BufReader::new(output.as_slice());
+            let mut messages = vec![];
+            while let Some(message) = read_message(&mut reader).unwrap() {
+                messages.push(message);
+            }
+            Session { messages, code }
+        }
+    }
+
+    struct Session {
+        messages: Vec<Value>,
+        code: u8,
+    }
+
+    impl Session {
+        fn response(&self, id: i64) -> &Value {
+            self.messages
+                .iter()
+                .find(|x| x["id"] == json!(id))
+                .unwrap_or_else(|| panic!("no response to {}", id))
+        }
+
+        fn result(&self, id: i64) -> &Value {
+            let response = self.response(id);
+            assert!(response.get("error").is_none(), "{}", response);
+            &response["result"]
+        }
+
+        fn error_code(&self, id: i64) -> i64 {
+            self.response(id)["error"]["code"].as_i64().unwrap()
+        }
+
+        // The diagnostics of each publish, as messages
+        fn diagnostics(&self) -> Vec<Vec<String>> {
+            self.messages
+                .iter()
+                .filter(|x| x["method"] == "textDocument/publishDiagnostics")
+                .map(|x| {
+                    x["params"]["diagnostics"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|x| x["message"].as_str().unwrap().to_string())
+                        .collect()
+                })
+                .collect()
+        }
+    }
+
+    fn range(start: (usize, usize), end: (usize, usize)) -> Value {
+        json!({
+            "start": { "line": start.0, "character": start.1 },
+            "end": { "line": end.0, "character": end.1 },
+        })
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let mut harness = Harness {
+            input: vec![],
+            next_id: 1,
+        };
+        let early = harness.request("textDocument/hover", json!({}));
+        let init = harness.request("initialize", json!({}));
+        let unknown = harness.request("workspace/symbol", json!({}));
+        let missing = harness.at("textDocument/hover", 0, 0);
+        let session = harness.finish();
+        assert_eq!(session.error_code(early), -32002);
+        let capabilities = &session.result(init)["capabilities"];
+        assert_eq!(capabilities["hoverProvider"], json!(true));
+        assert_eq!(
+            capabilities["semanticTokensProvider"]["legend"]["tokenTypes"][0],
+            "keyword"
+        );
+        assert_eq!(session.error_code(unknown), -32601);
+        assert_eq!(session.error_code(missing), -32602);
+        assert_eq!(session.code, 0);
+
+        // exit without shutdown
+        let mut harness = Harness::new();
+        harness.notify("exit", Value::Null);
+        let later = harness.request("shutdown", Value::Null);
+        let session = harness.run();
+        assert_eq!(session.code, 1);
+        assert!(session.messages.iter().all(|x| x["id"] != json!(later)));
+
+        // a broken frame is answered, the next one still served
+        let mut harness = Harness::new();
+        harness.input.extend(b"Content-Length: 3\r\n\r\n{]}");
+        let id = harness.request("shutdown", Value::Null);
+        let session = harness.run();
+        assert!(session
+            .messages
+            .iter()
+            .any(|x| x["error"]["code"] == -32700));
+        assert_eq!(session.result(id), &Value::Null);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut harness = Harness::new();
+        harness.open("let x = 1;\nprint(y);\nlen();");
+        let change = |text: &str| {
+            json!({
+                "textDocument": { "uri": URI, "version": 2 },
+                "contentChanges": [{ "text": text }],
+            })
+        };
+        harness.notify("textDocument/didChange", change("let = 1"));
+        harness.notify("textDocument/didChange", change("print(\"a);"));
+        harness.notify("textDocument/didChange", change("let s = \"abc\nlet y = 1"));
+        harness.notify("textDocument/didChange", change("let s = @;\nlet = 1"));
+        harness.notify("textDocument/didChange", change("let s = ;"));
+        harness.notify("textDocument/didChange", change("let x = 1; print(x)"));
+        harness.notify(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": URI } }),
+        );
+        let session = harness.finish();
+        let diagnostics = session.diagnostics();
+        assert_eq!(
+            diagnostics[0],
+            vec![
+                "unused variable `x`",
+                "`y` is not defined",
+                "`len` takes 1 argument, got 0"
+            ]
+        );
+        assert_eq!(
+            diagnostics[1],
+            vec!["Let Error: Expected: IDENT | got ASSIGN"]
+        );
+        assert_eq!(diagnostics[2], vec!["unterminated string"]);
+        assert_eq!(diagnostics[3], vec!["unterminated string"]);
+        assert_eq!(diagnostics[4], vec!["illegal character `@`"]);
+        assert_eq!(diagnostics[5], vec!["Expected an expression"]);
+        assert!(diagnostics[6].is_empty());
+        assert!(diagnostics[7].is_empty());
+
+        let first = session
+            .messages
+            .iter()
+            .find(|x| x["method"] == "textDocument/publishDiagnostics")
+            .unwrap();
+        let unused = &first["params"]["diagnostics"][0];
+        assert_eq!(unused["range"], range((0, 4), (0, 5)));
+        assert_eq!(unused["severity"], 2);
+        assert_eq!(unused["code"], "unused-let");
+        assert_eq!(unused["tags"], json!([1]));
+        assert_eq!(
+            first["params"]["diagnostics"][1]["range"],
+            range((1, 6), (1, 7))
+        );
+        assert_eq!(first["params"]["version"], 1);
+    }
+
+    #[test]
+    fn test_number_overflow() {
+        let mut harness = Harness::new();
+        harness.open("let x = 99999999999999999999;");
+        let change = json!({
+            "textDocument": { "uri": URI, "version": 2 },
+            "contentChanges": [{ "text": "99999999999999999999" }],
+        });
+        harness.notify("textDocument/didChange", change);
+        let id = harness.at("textDocument/hover", 0, 0);
+        let session = harness.finish();
+        assert_eq!(session.code, 0);
+        assert_eq!(session.result(id), &Value::Null);
+        let message = "Number 99999999999999999999 does not fit in 64 bits";
+        assert_eq!(session.diagnostics(), vec![vec![message], vec![message]]);
+        let first = session
+            .messages
+            .iter()
+            .find(|x| x["method"] == "textDocument/publishDiagnostics")
+            .unwrap();
+        assert_eq!(
+            first["params"]["diagnostics"][0]["range"],
+            range((0, 8), (0, 28))
+        );
+    }
+
+    #[test]
+    fn test_navigation() {
+        let src = "\
+let add = fn(a, b) { a + b };
+let n = add(1, 2);
+print(n, add);";
+        let mut harness = Harness::new();
+        harness.open(src);
+        let hover_let = harness.at("textDocument/hover", 1, 4);
+        let hover_fn = harness.at("textDocument/hover", 2, 10);
+        let hover_param = harness.at("textDocument/hover", 0, 21);
+        let hover_builtin = harness.at("textDocument/hover", 2, 1);
+        let hover_none = harness.at("textDocument/hover", 1, 13);
+        let definition = harness.at("textDocument/definition", 2, 6);
+        let params = json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": 0, "character": 5 },
+            "context": { "includeDeclaration": false },
+        });
+        let references = harness.request("textDocument/references", params);
+        let rename = |name: &str| {
+            json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": 0, "character": 13 },
+                "newName": name,
+            })
+        };
+        let renamed = harness.request("textDocument/rename", rename("x"));
+        let bad_name = harness.request("textDocument/rename", rename("let"));
+        let session = harness.finish();
+
+        let hover = |id| {
+            session.result(id)["contents"]["value"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(hover(hover_let), "```r-piece\nlet n\n```");
+        assert_eq!(hover(hover_fn), "```r-piece\nlet add = fn(a, b)\n```");
+        assert_eq!(session.result(hover_fn)["range"], range((2, 9), (2, 12)));
+        assert_eq!(hover(hover_param), "```r-piece\na (parameter)\n```");
+        assert_eq!(
+            hover(hover_builtin),
+            "```r-piece\nbuiltin print(...Any)\n```\nWrites each value to stdout on its own line"
+        );
+        assert_eq!(session.result(hover_none), &Value::Null);
+
+        assert_eq!(
+            session.result(definition),
+            &json!({ "uri": URI, "range": range((1, 4), (1, 5)) })
+        );
+        let ranges = |id| {
+            session
+                .result(id)
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["range"].clone())
+                .collect::<Vec<Value>>()
+        };
+        assert_eq!(
+            ranges(references),
+            vec![range((1, 8), (1, 11)), range((2, 9), (2, 12))]
+        );
+
+        let edits = session.result(renamed)["changes"][URI].as_array().unwrap();
+        let edited = edits
+            .iter()
+            .map(|x| x["range"].clone())
+            .collect::<Vec<Value>>();
+        assert_eq!(
+            edited,
+            vec![range((0, 13), (0, 14)), range((0, 21), (0, 22))]
+        );
+        assert!(edits.iter().all(|x| x["newText"] == "x"));
+        assert_eq!(session.error_code(bad_name), -32803);
+    }
+
+    #[test]
+    fn test_symbols_and_completion() {
+        let src = "\
+let outer = 1;
+let f = fn(p) {
+    let inner = p;
+    inner
+};
+if (true) { let g = \"s\"; }
+";
+        let mut harness = Harness::new();
+        harness.open(src);
+        let symbols = harness.request(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": URI } }),
+        );
+        let in_body = harness.at("textDocument/completion", 3, 4);
+        let top = harness.at("textDocument/completion", 6, 0);
+        let session = harness.finish();
+
+        let symbols = session.result(symbols).as_array().unwrap();
+        let names = symbols
+            .iter()
+            .map(|x| x["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["outer", "f", "g"]);
+        assert_eq!(symbols[1]["kind"], 12);
+        assert_eq!(symbols[1]["detail"], "fn(p)");
+        assert_eq!(symbols[1]["range"], range((1, 0), (4, 2)));
+        assert_eq!(symbols[1]["selectionRange"], range((1, 4), (1, 5)));
+        assert_eq!(symbols[1]["children"][0]["name"], "inner");
+
+        let labels = |id| {
+            session
+                .result(id)
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["label"].as_str().unwrap().to_string())
+                .collect::<Vec<String>>()
+        };
+        let names = labels(in_body);
+        assert_eq!(names[..5], ["inner", "p", "g", "f", "outer"]);
+        assert!(names.contains(&"len".to_string()));
+        assert!(names.contains(&"return".to_string()));
+        assert_eq!(labels(top)[..3], ["g", "f", "outer"]);
+        let items = session.result(in_body).as_array().unwrap();
+        let len = items.iter().find(|x| x["label"] == "len").unwrap();
+        assert_eq!(len["kind"], 3);
+        assert_eq!(len["detail"], "len(String|Array|Hash)");
+    }
+
+    #[test]
+    fn test_namespace_completion() {
+        let mut harness = Harness {
+            input: vec![],
+            next_id: 1,
+        };
+        harness.request("initialize", json!({}));
+        harness.open("host.");
+        let id = harness.at("textDocument/completion", 0, 5);
+        let session = harness.finish();
+        // no `host.*` builtin by default
+        assert_eq!(session.result(id), &json!([]));
+    }
+
+    #[test]
+    fn test_semantic_tokens() {
+        let src = "let f = fn(a) { a + 1 }; // c\nprint(f(\"s\"), x);";
+        let mut harness = Harness::new();
+        harness.open(src);
+        let id = harness.request(
+            "textDocument/semanticTokens/full",
+            json!({ "textDocument": { "uri": URI } }),
+        );
+        let session = harness.finish();
+        let data = session.result(id)["data"].as_array().unwrap();
+        let data = data
+            .iter()
+            .map(|x| x.as_u64().unwrap())
+            .collect::<Vec<u64>>();
+        let tokens = data.chunks(5).map(|x| x.to_vec()).collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                vec![0, 0, 3, 0, 0], // let
+                vec![0, 4, 1, 3, 1], // f, declared
+                vec![0, 2, 1, 6, 0], // =
+                vec![0, 2, 2, 0, 0], // fn
+                vec![0, 3, 1, 2, 1], // a, declared
+                vec![0, 5, 1, 2, 0], // a
+                vec![0, 2, 1, 6, 0], // +
+                vec![0, 2, 1, 5, 0], // 1
+                vec![0, 5, 4, 7, 0], // comment
+                vec![1, 0, 5, 3, 2], // print, builtin
+                vec![0, 6, 1, 3, 0], // f
+                vec![0, 2, 3, 4, 0], // "s"
+                vec![0, 6, 1, 1, 0], // x
+            ]
+        );
+    }
+
+    #[test]
+    fn test_positions() {
+        let text = "a\n😀b = 1\n";
+        assert_eq!(position(text, 0), (0, 0));
+        let b = text.find('b').unwrap();
+        assert_eq!(position(text, b), (1, 2));
+        assert_eq!(offset(text, 1, 2), b);
+        assert_eq!(offset(text, 1, 100), text.len() - 1);
+        assert_eq!(offset(text, 9, 0), text.len());
+    }
+}
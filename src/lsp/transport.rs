@@ -0,0 +1,39 @@
+use std::io::{BufRead, Write};
+
+use serde_json::Value;
+
+use crate::errors::lsp_errs::LspErr;
+
+// Reads one `Content-Length` framed message, None at the end of input
+pub fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>, LspErr> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        // other headers, like Content-Type, are ignored
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                let value = value.trim().parse::<usize>();
+                length = Some(value.map_err(|err| LspErr::Parse(err.to_string()))?);
+            }
+        }
+    }
+    let length = length.ok_or(LspErr::Parse("missing Content-Length".into()))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    let message = serde_json::from_slice(&body).map_err(|err| LspErr::Parse(err.to_string()))?;
+    Ok(Some(message))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Value) -> Result<(), LspErr> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
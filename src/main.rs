@@ -1,13 +1,11 @@
-mod ast;
-mod errors;
-mod evaluator;
-mod lexer;
-mod parser;
+mod cli;
 mod repl;
-use repl::repl::run_repl;
 
-mod utils;
+use std::process::ExitCode;
 
-fn main() {
-    run_repl();
+use clap::Parser;
+use cli::cli::{run, Cli};
+
+fn main() -> ExitCode {
+    ExitCode::from(run(Cli::parse()))
 }
@@ -0,0 +1,2 @@
+pub mod optimizer;
+mod test;
@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::ast::{
+        ArrayLiteral, BlockStatement, CallExpression, Expression, ExpressionStatement,
+        FunctionLiteral, HashLiteral, IfExpression, IndexExpression, InfixExpression, LetStatement,
+        MemberExpression, PrefixExpression, Program, ReassignStatement, ReturnStatement, Statement,
+    },
+    evaluator::{
+        builtin::BuiltinRegistry,
+        eval::{eval_prefix_expression, is_truthy},
+        eval_infix::eval_infix_expression,
+        object::Object,
+        runtime::{Limits, Runtime},
+    },
+    lexer::token::TOKEN,
+    resolver::resolver::{resolve, BindingKind, Resolution, Target},
+};
+
+// Strings built by folding above this size are left to be built at runtime
+const MAX_FOLDED_ALLOC: usize = 4096;
+
+// Literal values of `let` bindings that can be inlined where they are read,
+// by binding of the resolution
+type Constants = HashMap<usize, Expression>;
+
+// Simplify a whole script before evaluation: fold constant expressions, prune
+// statically decided `if` branches, drop code after `return` and inline `let`
+// bindings of literals. Expressions that fail to evaluate are kept as they are,
+// so their errors still happen at runtime.
+pub fn optimize(program: Program) -> Program {
+    // builtins are never inlined, only the bindings matter
+    let resolution = resolve(&program, &BuiltinRegistry::new());
+    let optimizer = Optimizer::new(&resolution);
+    Program {
+        statements: optimizer.optimize_scope(program.statements, &Constants::new()),
+    }
+}
+
+struct Optimizer {
+    // `let` bindings that keep their first value, by the offset of their name
+    inlinable: HashMap<usize, usize>,
+    // the binding each name read refers to, by the offset of the name
+    reads: HashMap<usize, usize>,
+}
+
+impl Optimizer {
+    // Top level bindings stay visible to later programs of the same session,
+    // which may reassign or rebind them, so only the ones of function scopes
+    // bound once and never written are inlined. Nodes built outside of the
+    // parser have empty spans and are left alone.
+    fn new(resolution: &Resolution) -> Self {
+        let mut inlinable = HashMap::new();
+        for (idx, binding) in resolution.bindings.iter().enumerate() {
+            let bound_once = resolution.scopes[binding.scope]
+                .bindings
+                .iter()
+                .filter(|x| resolution.bindings[**x].name == binding.name)
+                .count()
+                == 1;
+            if binding.kind == BindingKind::Let
+                && binding.scope != 0
+                && bound_once
+                && !resolution.is_reassigned(idx)
+                && !binding.span.is_empty()
+            {
+                inlinable.insert(binding.span.start, idx);
+            }
+        }
+        let mut reads = HashMap::new();
+        for reference in resolution.references.iter() {
+            if let (Target::Binding(idx), false) = (reference.target, reference.write) {
+                if !reference.span.is_empty() {
+                    reads.insert(reference.span.start, idx);
+                }
+            }
+        }
+        Self { inlinable, reads }
+    }
+
+    // Statements evaluated in their own environment: the program or a function body
+    fn optimize_scope(&self, statements: Vec<Statement>, outer: &Constants) -> Vec<Statement> {
+        let mut constants = outer.clone();
+        let mut output = vec![];
+        for stmt in statements {
+            let stmt = self.optimize_statement(stmt, &constants);
+            if let Statement::Let(stmt) = &stmt {
+                if let Some(idx) = self.inlinable.get(&stmt.name.1.start) {
+                    if is_literal(&stmt.value) {
+                        constants.insert(*idx, stmt.value.clone());
+                    }
+                }
+            }
+            push_statement(&mut output, stmt);
+        }
+        clean_statements(output)
+    }
+
+    // Statements of an `if` branch, evaluated in the enclosing environment
+    fn optimize_block(&self, block: BlockStatement, constants: &Constants) -> BlockStatement {
+        let mut output = vec![];
+        for stmt in block.statements {
+            push_statement(&mut output, self.optimize_statement(stmt, constants));
+        }
+        let mut output = BlockStatement::new(clean_statements(output));
+        output.span = block.span;
+        output
+    }
+
+    // Rewritten statements keep the span of the source statement
+    fn optimize_statement(&self, stmt: Statement, constants: &Constants) -> Statement {
+        let span = stmt.span();
+        let mut output = match stmt {
+            Statement::Let(stmt) => Statement::Let(LetStatement::new(
+                stmt.name,
+                self.optimize_expression(stmt.value, constants),
+            )),
+            Statement::Reassign(stmt) => {
+                let lhs = match stmt.lhs {
+                    // the indexed identifier must stay an identifier
+                    Expression::Index(index) => Expression::Index(Box::new(IndexExpression::new(
+                        index.left,
+                        self.optimize_expression(index.index, constants),
+                    ))),
+                    lhs => lhs,
+                };
+                let value = self.optimize_expression(stmt.value, constants);
+                Statement::Reassign(ReassignStatement::new(lhs, value))
+            }
+            Statement::Return(stmt) => Statement::Return(ReturnStatement {
+                expression: stmt
+                    .expression
+                    .map(|x| self.optimize_expression(x, constants)),
+                span,
+            }),
+            Statement::Expression(stmt) => Statement::Expression(ExpressionStatement::new(
+                stmt.token,
+                stmt.expression
+                    .map(|x| self.optimize_expression(x, constants)),
+            )),
+            Statement::Program(program) => Statement::Program(Program {
+                statements: self.optimize_scope(program.statements, constants),
+            }),
+            Statement::Block(block) => Statement::Block(self.optimize_block(block, constants)),
+        };
+        output.set_span(span);
+        output
+    }
+
+    fn optimize_expression(&self, expression: Expression, constants: &Constants) -> Expression {
+        match expression {
+            Expression::Identifier(ident) => {
+                let binding = self.reads.get(&ident.1.start);
+                match binding.and_then(|x| constants.get(x)) {
+                    Some(value) => value.clone(),
+                    None => Expression::Identifier(ident),
+                }
+            }
+            Expression::ArrayLiteral(arr) => Expression::ArrayLiteral(ArrayLiteral::new(
+                arr.elements
+                    .into_iter()
+                    .map(|x| self.optimize_expression(x, constants))
+                    .collect(),
+            )),
+            Expression::HashLiteral(hash) => Expression::HashLiteral(HashLiteral::new(
+                hash.pairs
+                    .into_iter()
+                    .map(|(k, v)| {
+                        (
+                            self.optimize_expression(k, constants),
+                            self.optimize_expression(v, constants),
+                        )
+                    })
+                    .collect(),
+            )),
+            Expression::Index(index) => Expression::Index(Box::new(IndexExpression::new(
+                self.optimize_expression(index.left, constants),
+                self.optimize_expression(index.index, constants),
+            ))),
+            Expression::Member(member) => Expression::Member(Box::new(MemberExpression::new(
+                self.optimize_expression(member.object, constants),
+                member.property,
+            ))),
+            Expression::Prefix(prefix) => {
+                let right = self.optimize_expression(prefix.right, constants);
+                fold_prefix(prefix.token, right)
+            }
+            Expression::Infix(infix) => {
+                let left = self.optimize_expression(infix.left, constants);
+                let right = self.optimize_expression(infix.right, constants);
+                fold_infix(infix.operator, left, right)
+            }
+            Expression::If(if_exp) => self.optimize_if(*if_exp, constants),
+            Expression::Function(func) => {
+                let mut function = FunctionLiteral::new(func.parameters);
+                function.body =
+                    BlockStatement::new(self.optimize_scope(func.body.statements, constants));
+                function.body.span = func.body.span;
+                Expression::Function(Box::new(function))
+            }
+            Expression::Call(call) => {
+                let mut output =
+                    CallExpression::new(self.optimize_expression(call.function, constants));
+                output.arguments = call
+                    .arguments
+                    .into_iter()
+                    .map(|x| self.optimize_expression(x, constants))
+                    .collect();
+                Expression::Call(Box::new(output))
+            }
+            literal => literal,
+        }
+    }
+
+    fn optimize_if(&self, if_exp: IfExpression, constants: &Constants) -> Expression {
+        let condition = self.optimize_expression(if_exp.condition, constants);
+        let truthy = match to_object(&condition) {
+            Some(value) => is_truthy(value),
+            None => {
+                let mut output = IfExpression::new(condition);
+                output.consequence = self.optimize_block(if_exp.consequence, constants);
+                output.alternative = if_exp
+                    .alternative
+                    .map(|x| self.optimize_block(x, constants));
+                return Expression::If(Box::new(output));
+            }
+        };
+
+        let branch = if truthy {
+            self.optimize_block(if_exp.consequence, constants)
+        } else {
+            match if_exp.alternative {
+                Some(alternative) => self.optimize_block(alternative, constants),
+                None => BlockStatement::new(vec![]),
+            }
+        };
+        // a branch holding a single expression is that expression
+        if let [Statement::Expression(ExpressionStatement {
+            expression: Some(expression),
+            ..
+        })] = branch.statements.as_slice()
+        {
+            return expression.clone();
+        }
+        // otherwise keep an always taken `if`, which push_statement can splice
+        let mut output = IfExpression::new(Expression::Bool(true));
+        output.consequence = branch;
+        Expression::If(Box::new(output))
+    }
+}
+
+// `if` blocks share the enclosing environment, so the statements of an
+// always taken branch can replace the `if` statement itself
+fn push_statement(output: &mut Vec<Statement>, stmt: Statement) {
+    match stmt {
+        Statement::Expression(ExpressionStatement {
+            expression: Some(Expression::If(if_exp)),
+            ..
+        }) if matches!(if_exp.condition, Expression::Bool(true))
+            && if_exp.alternative.is_none() =>
+        {
+            if if_exp.consequence.statements.is_empty() {
+                // keep the Null value of an empty branch
+                output.push(Statement::Expression(ExpressionStatement::new(
+                    TOKEN::IF,
+                    None,
+                )));
+            }
+            output.extend(if_exp.consequence.statements);
+        }
+        stmt => output.push(stmt),
+    }
+}
+
+// Drop unreachable statements after a `return` and the empty statements
+// whose value is discarded anyway
+fn clean_statements(mut statements: Vec<Statement>) -> Vec<Statement> {
+    if let Some(idx) = statements
+        .iter()
+        .position(|x| matches!(x, Statement::Return(_)))
+    {
+        statements.truncate(idx + 1);
+    }
+    let last = statements.len().saturating_sub(1);
+    statements
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, stmt)| {
+            *idx == last
+                || !matches!(
+                    stmt,
+                    Statement::Expression(ExpressionStatement {
+                        expression: None,
+                        ..
+                    })
+                )
+        })
+        .map(|(_, stmt)| stmt)
+        .collect()
+}
+
+fn fold_prefix(operator: TOKEN, right: Expression) -> Expression {
+    let overflows = matches!((&operator, &right), (TOKEN::MINUS, Expression::Number(n)) if n.checked_neg().is_none());
+    if let (Some(value), false) = (to_object(&right), overflows) {
+        if let Some(folded) = eval_prefix_expression(operator.clone(), value)
+            .ok()
+            .and_then(to_literal)
+        {
+            return folded;
+        }
+    }
+    Expression::Prefix(Box::new(PrefixExpression::new(operator, right)))
+}
+
+fn fold_infix(operator: TOKEN, left: Expression, right: Expression) -> Expression {
+    if let (Some(l), Some(r)) = (to_object(&left), to_object(&right)) {
+        if !overflows(&operator, &left, &right) {
+            // bound the work done at optimization time, bigger strings fail
+            // here and are built by the evaluator instead
+            let mut rt = Runtime::with_limits(Limits {
+                max_alloc: Some(MAX_FOLDED_ALLOC),
+                ..Limits::default()
+            });
+            if let Some(folded) = eval_infix_expression(operator.clone(), l, r, &mut rt)
+                .ok()
+                .and_then(to_literal)
+            {
+                return folded;
+            }
+        }
+    }
+    Expression::Infix(Box::new(InfixExpression::new(left, operator, right)))
+}
+
+// Integer overflow panics in the evaluator, so it is never folded
+fn overflows(operator: &TOKEN, left: &Expression, right: &Expression) -> bool {
+    let (Expression::Number(l), Expression::Number(r)) = (left, right) else {
+        return false;
+    };
+    let result = match operator {
+        TOKEN::PLUS => l.checked_add(*r),
+        TOKEN::MINUS => l.checked_sub(*r),
+        TOKEN::ASTERISK => l.checked_mul(*r),
+        TOKEN::SLASH if *r != 0 => l.checked_div(*r),
+        _ => Some(0),
+    };
+    result.is_none()
+}
+
+fn is_literal(expression: &Expression) -> bool {
+    to_object(expression).is_some()
+}
+
+fn to_object(expression: &Expression) -> Option<Object> {
+    match expression {
+        Expression::Number(n) => Some(Object::Number(*n)),
+        Expression::String(s) => Some(Object::String(s.clone())),
+        Expression::Bool(b) => Some(Object::Boolean(*b)),
+        _ => None,
+    }
+}
+
+fn to_literal(object: Object) -> Option<Expression> {
+    match object {
+        Object::Number(n) => Some(Expression::Number(n)),
+        Object::String(s) => Some(Expression::String(s)),
+        Object::Boolean(b) => Some(Expression::Bool(b)),
+        _ => None,
+    }
+}
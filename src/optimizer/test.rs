@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        ast::ast::{stringnify_stmt, Node, Program, Statement},
+        errors::eval_errs::EvalErr,
+        evaluator::{environment::Environment, eval::eval, object::Object, runtime::Runtime},
+        lexer::lexer::Lexer,
+        optimizer::optimizer::optimize,
+        parser::parser::Parser,
+        utils::shared::{Rc, RefCell},
+    };
+
+    fn parse(input: &str) -> Program {
+        let l = Lexer::new(input);
+        let mut p = Parser::new(l);
+        p.parse_program().unwrap()
+    }
+
+    fn run(program: Program) -> Result<Object, EvalErr> {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        eval(
+            Node::Statement(Statement::Program(program)),
+            env,
+            &mut Runtime::new(),
+        )
+    }
+
+    #[test]
+    fn test_optimize() {
+        let tests = vec![
+            ("2 * (5 + 10)", "30"),
+            ("-(3 - 5) * 2", "4"),
+            ("!true", "false"),
+            (r#""ab" + "cd""#, r#""abcd""#),
+            (r#""ab" * 2"#, r#""abab""#),
+            ("1 < 2 == true", "true"),
+            ("if (true) { 5 } else { 6 }", "5"),
+            ("if (1 > 2) { 5 } else { 6 }", "6"),
+            ("if (1 > 2) { 10 } else if (2 > 1) { 15 } else { 20 }", "15"),
+            (
+                "fn() { let a = 2; let b = a * 3; b + 1 }",
+                "fn() {let a = 2; let b = 6; 7}",
+            ),
+            (
+                "fn() { let a = 2; let f = fn(a) { a }; f(1) }",
+                "fn() {let a = 2; let f = fn(a) {a}; f(1)}",
+            ),
+            (
+                "fn() { let a = 2; fn(x) { x + a } }",
+                "fn() {let a = 2; fn(x) {(x + 2)}}",
+            ),
+            // later programs of a session may reassign or rebind top level names
+            (
+                "let a = 2; let b = a * 3; let f = fn(x) { x + a }",
+                "let a = 2; let b = (a * 3); let f = fn(x) {(x + a)};",
+            ),
+            // only bindings that keep their first value are inlined
+            ("fn() { let a = 1; a = 2; a }", "fn() {let a = 1; a = 2; a}"),
+            (
+                "fn() { let a = 1; let a = 2; a }",
+                "fn() {let a = 1; let a = 2; a}",
+            ),
+            ("fn(x) { return x; x + 1 }", "fn(x) {return x;}"),
+            ("fn(x) { if (true) { return x; } 5 }", "fn(x) {return x;}"),
+            (
+                "fn(x) { let y = 1; if (x) { return y; } 5 }",
+                "fn(x) {let y = 1; if x {return 1;} 5}",
+            ),
+            // runtime errors are left to the evaluator
+            ("1 / 0", "(1 / 0)"),
+            (r#"1 + "a""#, r#"(1 + "a")"#),
+            ("9223372036854775807 + 1", "(9223372036854775807 + 1)"),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let program = optimize(parse(input));
+            assert_eq!(stringnify_stmt(&program.statements), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_optimize_keeps_semantics() {
+        let tests = vec![
+            "2 * (5 + 10)",
+            "if (false) { 5 }",
+            "let x = 5; if (x > 1) { x * 2 } else { 0 }",
+            "let a = [1, 2, 3]; a[1 + 1] = 2 * 5; a[2]",
+            "let f = fn(n) { if (n == 0) { return 0; } n + f(n - 1) }; f(10)",
+            "let x = 3; let add = fn(y) { let x = 10; x + y }; add(x)",
+            "9;return 2*5;9",
+            "let a = 2; a = 3; a",
+        ];
+        for input in tests.into_iter() {
+            let expected = run(parse(input)).unwrap();
+            let optimized = run(optimize(parse(input))).unwrap();
+            assert_eq!(optimized, expected, "{}", input);
+        }
+
+        let tests = vec![
+            ("1 / 0", EvalErr::DivideByZero),
+            (
+                "if (true) { let x = 1 } let x = 2",
+                EvalErr::AlreadyInitialized("".into()),
+            ),
+            (
+                "if (false) { let x = 1 } x",
+                EvalErr::IdentifierNotFound("".into()),
+            ),
+        ];
+        for (input, expected) in tests.into_iter() {
+            let err = run(optimize(parse(input))).unwrap_err();
+            assert!(err.match_err(expected), "{}", input);
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{ast::ast::Expression, errors::parser_errs::ParseErr, lexer::token::TOKEN};
+use crate::{
+    ast::ast::{Expression, Span},
+    errors::parser_errs::ParseErr,
+    lexer::token::TokenKind,
+};
 
 use super::parser::{Parser, Precedence};
 
@@ -6,31 +10,32 @@ pub fn parse_expression<'a>(
     parser: &mut Parser<'a>,
     precedence: Precedence,
 ) -> Result<Expression, ParseErr> {
-    let prefix = parser
-        .prefix_parse_fns
-        .get(&parser.cur_token.to_type_name());
-    if prefix.is_none() {
-        return Err(ParseErr::None);
-    }
+    let prefix = match parser.prefix_parse_fn(parser.cur_token.kind) {
+        Some(prefix) => prefix,
+        None => return Err(ParseErr::None),
+    };
 
-    let mut left_exp = prefix.unwrap()(parser)?;
+    let start = parser.cur_token.span.start;
+    let mut left_exp = prefix(parser)?;
 
-    while !parser.peek_token.is_same_with(TOKEN::SEMICOLON)
+    while !parser.peek_token.is(TokenKind::SEMICOLON)
         && (precedence.order() < parser.peek_precedence().order())
     {
-        let infix = parser
-            .infix_parse_fns
-            .get(&parser.peek_token.to_type_name());
-        if infix.is_none() {
-            return Err(ParseErr::INFIX("INFIX".into(), parser.cur_token.clone()));
-        }
+        let infix = match parser.infix_parse_fn(parser.peek_token.kind) {
+            Some(infix) => infix,
+            None => {
+                return Err(ParseErr::INFIX("INFIX".into(), parser.cur_token.to_token()));
+            }
+        };
         parser.next_token();
-        //cannot use infix.unwrap()(parser,left_exp) here because of mutable borrow
-        //after next_token, peek_token become cur_token.
-        left_exp = parser
-            .infix_parse_fns
-            .get(&parser.cur_token.to_type_name())
-            .unwrap()(parser, left_exp)?;
+        left_exp = infix(parser, left_exp)?;
+        // operators and calls are located for diagnostics
+        let span = Span::new(start, parser.cur_token.span.end);
+        match &mut left_exp {
+            Expression::Infix(infix) => infix.span = span,
+            Expression::Call(call) => call.span = span,
+            _ => {}
+        }
     }
 
     return Ok(left_exp);
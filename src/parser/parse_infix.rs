@@ -1,7 +1,9 @@
 use crate::{
-    ast::ast::{CallExpression, Expression, IndexExpression, InfixExpression},
+    ast::ast::{
+        CallExpression, Expression, Identifier, IndexExpression, InfixExpression, MemberExpression,
+    },
     errors::parser_errs::ParseErr,
-    lexer::token::TOKEN,
+    lexer::token::TokenKind,
 };
 
 use super::{
@@ -13,7 +15,7 @@ pub fn parse_infix_expression<'a>(
     parser: &mut Parser<'a>,
     left: Expression,
 ) -> Result<Expression, ParseErr> {
-    let operator = parser.cur_token.clone();
+    let operator = parser.cur_token.to_token();
     let precedence = parser.cur_precedence();
     parser.next_token();
     let right = parse_expression(parser, precedence)?;
@@ -27,7 +29,7 @@ pub fn parse_call_expression<'a>(
 ) -> Result<Expression, ParseErr> {
     let mut call = CallExpression::new(function);
     call.arguments = parse_call_args(parser)?;
-    if parser.peek_token.is_same_with(TOKEN::RPAREN) {
+    if parser.peek_token.is(TokenKind::RPAREN) {
         parser.next_token();
     }
     return Ok(Expression::Call(Box::new(call)));
@@ -36,9 +38,12 @@ pub fn parse_call_expression<'a>(
 pub fn parse_call_args<'a>(parser: &mut Parser<'a>) -> Result<Vec<Expression>, ParseErr> {
     let mut args = vec![];
 
-    while !parser.peek_token.is_same_with(TOKEN::RPAREN) {
-        if parser.peek_token.is_same_with(TOKEN::EOF) {
-            return Err(ParseErr::CALL("RPAREN".into(), parser.peek_token.clone()));
+    while !parser.peek_token.is(TokenKind::RPAREN) {
+        if parser.peek_token.is(TokenKind::EOF) {
+            return Err(ParseErr::CALL(
+                "RPAREN".into(),
+                parser.peek_token.to_token(),
+            ));
         }
         parser.next_token();
         let expression = parse_expression(parser, Precedence::LOWEST);
@@ -58,9 +63,29 @@ pub fn parse_arr_index_expression<'a>(
     let index_exp = IndexExpression::new(left, parse_expression(parser, Precedence::LOWEST)?);
     parser.next_token(); // move to ']'
 
-    if !parser.cur_token.is_same_with(TOKEN::RBRACKET) {
-        return Err(ParseErr::INDEX("RPAREN".into(), parser.cur_token.clone()));
+    if !parser.cur_token.is(TokenKind::RBRACKET) {
+        return Err(ParseErr::INDEX(
+            "RPAREN".into(),
+            parser.cur_token.to_token(),
+        ));
     }
 
     Ok(Expression::Index(Box::new(index_exp)))
 }
+
+pub fn parse_member_expression<'a>(
+    parser: &mut Parser<'a>,
+    object: Expression,
+) -> Result<Expression, ParseErr> {
+    if !parser.peek_token.is(TokenKind::IDENT) {
+        return Err(ParseErr::MEMBER(
+            "IDENT".into(),
+            parser.peek_token.to_token(),
+        ));
+    }
+    parser.next_token(); // move on from '.'
+    let property = Identifier(parser.cur_token.text.to_string(), parser.cur_token.span);
+    Ok(Expression::Member(Box::new(MemberExpression::new(
+        object, property,
+    ))))
+}
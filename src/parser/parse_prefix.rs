@@ -1,10 +1,13 @@
 use crate::{
     ast::ast::{
-        ArrayLiteral, BlockStatement, Expression, ExpressionStatement, FunctionLiteral, Identifier,
-        IfExpression, PrefixExpression, Statement,
+        ArrayLiteral, BlockStatement, Expression, ExpressionStatement, FunctionLiteral,
+        HashLiteral, Identifier, IfExpression, PrefixExpression, Span, Statement,
     },
     errors::parser_errs::ParseErr,
-    lexer::token::TOKEN,
+    lexer::{
+        lexer::unescape,
+        token::{TokenKind, TOKEN},
+    },
 };
 
 use super::{
@@ -15,30 +18,31 @@ use super::{
 
 pub fn parse_identifier<'a>(parser: &mut Parser<'a>) -> Result<Expression, ParseErr> {
     Ok(Expression::Identifier(Identifier(
-        parser.cur_token.literal(),
+        parser.cur_token.text.to_string(),
+        parser.cur_token.span,
     )))
 }
 
 pub fn parse_string<'a>(parser: &mut Parser<'a>) -> Result<Expression, ParseErr> {
-    Ok(Expression::String(parser.cur_token.literal()))
+    Ok(Expression::String(unescape(parser.cur_token.text)))
 }
 
 pub fn parse_int_literal<'a>(parser: &mut Parser<'a>) -> Result<Expression, ParseErr> {
-    Ok(Expression::Number(
-        parser.cur_token.literal().parse::<i64>().unwrap(),
-    ))
+    let token = &parser.cur_token;
+    match token.text.parse::<i64>() {
+        Ok(number) => Ok(Expression::Number(number)),
+        Err(_) => Err(ParseErr::NumberOverflow(token.text.to_string(), token.span)),
+    }
 }
 
 pub fn parse_boolean_literal<'a>(parser: &mut Parser<'a>) -> Result<Expression, ParseErr> {
-    Ok(Expression::Bool(
-        parser.cur_token.literal().parse::<bool>().unwrap(),
-    ))
+    Ok(Expression::Bool(parser.cur_token.is(TokenKind::TRUE)))
 }
 
 pub fn parse_array_literal<'a>(parser: &mut Parser<'a>) -> Result<Expression, ParseErr> {
     let mut elements: Vec<Expression> = Vec::new();
     parser.next_token(); // move on from '['
-    if parser.cur_token.is_same_with(TOKEN::RBRACKET) {
+    if parser.cur_token.is(TokenKind::RBRACKET) {
         return Ok(Expression::ArrayLiteral(ArrayLiteral::new(elements)));
     }
 
@@ -46,20 +50,52 @@ pub fn parse_array_literal<'a>(parser: &mut Parser<'a>) -> Result<Expression, Pa
         elements.push(parse_expression(parser, Precedence::LOWEST)?);
         parser.next_token();
 
-        if parser.cur_token.is_same_with(TOKEN::COMMA) {
+        if parser.cur_token.is(TokenKind::COMMA) {
             parser.next_token();
-        } else if parser.cur_token.is_same_with(TOKEN::RBRACKET) {
+        } else if parser.cur_token.is(TokenKind::RBRACKET) {
             break;
         } else {
-            return Err(ParseErr::ARRAY("] or ,".into(), parser.cur_token.clone()));
+            return Err(ParseErr::ARRAY(
+                "] or ,".into(),
+                parser.cur_token.to_token(),
+            ));
         }
     }
 
     return Ok(Expression::ArrayLiteral(ArrayLiteral::new(elements)));
 }
 
+pub fn parse_hash_literal<'a>(parser: &mut Parser<'a>) -> Result<Expression, ParseErr> {
+    let mut pairs = Vec::new();
+    parser.next_token(); // move on from '{'
+    if parser.cur_token.is(TokenKind::RBRACE) {
+        return Ok(Expression::HashLiteral(HashLiteral::new(pairs)));
+    }
+
+    loop {
+        let key = parse_expression(parser, Precedence::LOWEST)?;
+        parser.next_token();
+        if !parser.cur_token.is(TokenKind::COLON) {
+            return Err(ParseErr::HASH(":".into(), parser.cur_token.to_token()));
+        }
+        parser.next_token();
+        pairs.push((key, parse_expression(parser, Precedence::LOWEST)?));
+        parser.next_token();
+
+        if parser.cur_token.is(TokenKind::COMMA) {
+            parser.next_token();
+        } else if parser.cur_token.is(TokenKind::RBRACE) {
+            break;
+        } else {
+            return Err(ParseErr::HASH("} or ,".into(), parser.cur_token.to_token()));
+        }
+    }
+
+    Ok(Expression::HashLiteral(HashLiteral::new(pairs)))
+}
+
 pub fn parse_prefix_expression<'a>(parser: &mut Parser<'a>) -> Result<Expression, ParseErr> {
-    let token = parser.cur_token.clone();
+    let token = parser.cur_token.to_token();
     parser.next_token();
     let right_exp = parse_expression(parser, Precedence::PREFIX)?;
     let expression = PrefixExpression::new(token, right_exp);
@@ -70,8 +106,11 @@ pub fn parse_group_expression<'a>(parser: &mut Parser<'a>) -> Result<Expression,
     parser.next_token(); // to move on from "("
     let expression = parse_expression(parser, Precedence::LOWEST);
 
-    if !parser.peek_token.is_same_with(TOKEN::RPAREN) {
-        return Err(ParseErr::GROUP("RPAREN".into(), parser.peek_token.clone()));
+    if !parser.peek_token.is(TokenKind::RPAREN) {
+        return Err(ParseErr::GROUP(
+            "RPAREN".into(),
+            parser.peek_token.to_token(),
+        ));
     }
     parser.next_token(); // to move on from ")"
     return expression;
@@ -82,27 +121,32 @@ pub fn parse_if_expression<'a>(parser: &mut Parser<'a>) -> Result<Expression, Pa
     let condition = parse_expression(parser, Precedence::LOWEST)?;
     let mut expression = IfExpression::new(condition);
 
-    if !parser.peek_token.is_same_with(TOKEN::LBRACE) {
-        return Err(ParseErr::IF("LBRACE".into(), parser.peek_token.clone()));
+    if !parser.peek_token.is(TokenKind::LBRACE) {
+        return Err(ParseErr::IF("LBRACE".into(), parser.peek_token.to_token()));
     };
     parser.next_token();
-    expression.consequence = BlockStatement::new(parse_block_statement(parser)?);
+    expression.consequence = parse_block_statement(parser)?;
 
-    if parser.peek_token.is_same_with(TOKEN::ELSE) {
+    if parser.peek_token.is(TokenKind::ELSE) {
         parser.next_token(); // move to ELSE
 
-        expression.alternative = if parser.peek_token.is_same_with(TOKEN::LBRACE) {
+        expression.alternative = if parser.peek_token.is(TokenKind::LBRACE) {
             parser.next_token(); // move on from ELSE
-            Some(BlockStatement::new(parse_block_statement(parser)?))
-        } else if parser.peek_token.is_same_with(TOKEN::IF) {
+            Some(parse_block_statement(parser)?)
+        } else if parser.peek_token.is(TokenKind::IF) {
             parser.next_token(); // move on from ELSE
+            let start = parser.cur_token.span.start;
             let if_exp = parse_if_expression(parser)?;
-            let stmt_exp = ExpressionStatement::new(TOKEN::IF, Some(if_exp));
-            let block_stmt = BlockStatement::new(vec![Statement::Expression(stmt_exp)]);
+            // `else if` is a block holding the `if`, both span the `if`
+            let span = Span::new(start, parser.cur_token.span.end);
+            let mut stmt_exp = ExpressionStatement::new(TOKEN::IF, Some(if_exp));
+            stmt_exp.span = span;
+            let mut block_stmt = BlockStatement::new(vec![Statement::Expression(stmt_exp)]);
+            block_stmt.span = span;
             Some(block_stmt)
         } else {
             let expect = "IF or LBRACE";
-            return Err(ParseErr::ELSE(expect.into(), parser.peek_token.clone()));
+            return Err(ParseErr::ELSE(expect.into(), parser.peek_token.to_token()));
         }
     }
 
@@ -113,7 +157,7 @@ pub fn parse_if_expression<'a>(parser: &mut Parser<'a>) -> Result<Expression, Pa
 pub fn parse_function_literal<'a>(parser: &mut Parser<'a>) -> Result<Expression, ParseErr> {
     parser.next_token();
     let mut function = FunctionLiteral::new(parse_fn_parameters(parser)?);
-    function.body = BlockStatement::new(parse_block_statement(parser)?);
+    function.body = parse_block_statement(parser)?;
     return Ok(Expression::Function(Box::new(function)));
 }
 
@@ -121,17 +165,18 @@ pub fn parse_function_literal<'a>(parser: &mut Parser<'a>) -> Result<Expression,
 pub fn parse_fn_parameters<'a>(parser: &mut Parser<'a>) -> Result<Vec<Identifier>, ParseErr> {
     let mut identifiers = Vec::new();
     parser.next_token(); // move on from '(',
-    while !parser.cur_token.is_same_with(TOKEN::RPAREN) {
-        match parser.cur_token {
-            TOKEN::IDENT(ref name) => {
-                identifiers.push(Identifier(name.0.clone()));
+    while !parser.cur_token.is(TokenKind::RPAREN) {
+        match parser.cur_token.kind {
+            TokenKind::IDENT => {
+                let name = parser.cur_token.text.to_string();
+                identifiers.push(Identifier(name, parser.cur_token.span));
                 parser.next_token()
             }
             _ => {
-                return Err(ParseErr::FN("IDENT".into(), parser.cur_token.clone()));
+                return Err(ParseErr::FN("IDENT".into(), parser.cur_token.to_token()));
             }
         }
-        if parser.cur_token.is_same_with(TOKEN::COMMA) {
+        if parser.cur_token.is(TokenKind::COMMA) {
             parser.next_token()
         }
     }
@@ -1,10 +1,10 @@
 use crate::{
     ast::ast::{
-        Expression, ExpressionStatement, Identifier, LetStatement, ReassignStatement,
-        ReturnStatement, Statement,
+        BlockStatement, Expression, ExpressionStatement, Identifier, LetStatement,
+        ReassignStatement, ReturnStatement, Span, Statement,
     },
     errors::parser_errs::ParseErr,
-    lexer::token::TOKEN,
+    lexer::token::TokenKind,
 };
 
 use super::{
@@ -12,15 +12,25 @@ use super::{
     parser::{Parser, Precedence},
 };
 
+// The statement spans from its first token to its last one, `;` included
 pub fn parse_statement<'a>(parser: &mut Parser<'a>) -> Result<Statement, ParseErr> {
-    match parser.cur_token {
-        TOKEN::LET => parse_let_statement(parser),
-        TOKEN::RETURN => parse_return_statement(parser),
-        TOKEN::IDENT(_) if parser.peek_token == TOKEN::ASSIGN => parse_reassign_statement(parser),
+    let start = parser.cur_token.span.start;
+    let mut stmt = parse_statement_kind(parser)?;
+    stmt.set_span(Span::new(start, parser.cur_token.span.end));
+    Ok(stmt)
+}
+
+fn parse_statement_kind<'a>(parser: &mut Parser<'a>) -> Result<Statement, ParseErr> {
+    match parser.cur_token.kind {
+        TokenKind::LET => parse_let_statement(parser),
+        TokenKind::RETURN => parse_return_statement(parser),
+        TokenKind::IDENT if parser.peek_token.is(TokenKind::ASSIGN) => {
+            parse_reassign_statement(parser)
+        }
         _ => {
             let stmt = parse_expression_statement(parser)?;
-            match parser.peek_token {
-                TOKEN::ASSIGN => {
+            match parser.peek_token.kind {
+                TokenKind::ASSIGN => {
                     let idx_infix = Box::new(stmt.to_exp_stmt()?.expression.unwrap().to_index()?);
                     parse_reassign_exp_statement(parser, Expression::Index(idx_infix))
                 }
@@ -31,27 +41,24 @@ pub fn parse_statement<'a>(parser: &mut Parser<'a>) -> Result<Statement, ParseEr
 }
 
 pub fn parse_let_statement<'a>(parser: &mut Parser<'a>) -> Result<Statement, ParseErr> {
-    if !parser
-        .peek_token
-        .is_same_with(TOKEN::IDENT(Identifier(String::new())))
-    {
-        return Err(ParseErr::LET("IDENT".into(), parser.peek_token.clone()));
+    if !parser.peek_token.is(TokenKind::IDENT) {
+        return Err(ParseErr::LET("IDENT".into(), parser.peek_token.to_token()));
     }
     parser.next_token(); // to ident token
-    let name = parser.cur_token.literal();
+    let name = Identifier(parser.cur_token.text.to_string(), parser.cur_token.span);
 
     parser.next_token(); //to assign token
-    if !parser.cur_token.is_same_with(TOKEN::ASSIGN) {
-        return Err(ParseErr::LET("ASSIGN".into(), parser.cur_token.clone()));
+    if !parser.cur_token.is(TokenKind::ASSIGN) {
+        return Err(ParseErr::LET("ASSIGN".into(), parser.cur_token.to_token()));
     }
 
     parser.next_token(); //to expression
     let value = parse_expression(parser, Precedence::LOWEST)?;
-    if parser.peek_token.is_same_with(TOKEN::SEMICOLON) {
+    if parser.peek_token.is(TokenKind::SEMICOLON) {
         parser.next_token();
     }
 
-    let stmt = LetStatement::new(Identifier(name), value);
+    let stmt = LetStatement::new(name, value);
 
     return Ok(Statement::Let(stmt));
 }
@@ -63,16 +70,22 @@ pub fn parse_reassign_exp_statement<'a>(
     parser.next_token(); //to assign token
     parser.next_token(); //to expression
     let value = parse_expression(parser, Precedence::LOWEST)?;
+    if parser.peek_token.is(TokenKind::SEMICOLON) {
+        parser.next_token();
+    }
     let stmt = ReassignStatement::new(lhs, value);
     return Ok(Statement::Reassign(stmt));
 }
 pub fn parse_reassign_statement<'a>(parser: &mut Parser<'a>) -> Result<Statement, ParseErr> {
-    let name = parser.cur_token.literal();
+    let name = Identifier(parser.cur_token.text.to_string(), parser.cur_token.span);
     //Dont need to check error because match case in parse_statement've already done it
     parser.next_token(); //to assign token
     parser.next_token(); //to expression
     let value = parse_expression(parser, Precedence::LOWEST)?;
-    let stmt = ReassignStatement::new(Expression::Identifier(Identifier(name)), value);
+    if parser.peek_token.is(TokenKind::SEMICOLON) {
+        parser.next_token();
+    }
+    let stmt = ReassignStatement::new(Expression::Identifier(name), value);
     return Ok(Statement::Reassign(stmt));
 }
 
@@ -82,34 +95,47 @@ pub fn parse_return_statement<'a>(parser: &mut Parser<'a>) -> Result<Statement,
     parser.next_token();
     let expression = parse_expression(parser, Precedence::LOWEST);
     stmt.expression = parser.result_to_option(expression)?;
-    if parser.peek_token.is_same_with(TOKEN::SEMICOLON) {
+    if parser.peek_token.is(TokenKind::SEMICOLON) {
         parser.next_token();
     }
     return Ok(Statement::Return(stmt));
 }
 
 pub fn parse_expression_statement<'a>(parser: &mut Parser<'a>) -> Result<Statement, ParseErr> {
-    let mut stmt = ExpressionStatement::new(parser.cur_token.clone(), None);
+    let mut stmt = ExpressionStatement::new(parser.cur_token.to_token(), None);
     let expression = parse_expression(parser, Precedence::LOWEST);
     stmt.expression = parser.result_to_option(expression)?;
 
-    if parser.peek_token.is_same_with(TOKEN::SEMICOLON) {
+    if parser.peek_token.is(TokenKind::SEMICOLON) {
         parser.next_token();
     }
 
     return Ok(Statement::Expression(stmt));
 }
 
-pub fn parse_block_statement<'a>(parser: &mut Parser<'a>) -> Result<Vec<Statement>, ParseErr> {
-    if parser.cur_token.is_same_with(TOKEN::RBRACE) {
-        return Err(ParseErr::BLOCK("RBRACE".into(), parser.cur_token.clone()));
+// call this when current token is "{", it stops on the matching "}"
+pub fn parse_block_statement<'a>(parser: &mut Parser<'a>) -> Result<BlockStatement, ParseErr> {
+    if parser.cur_token.is(TokenKind::RBRACE) {
+        return Err(ParseErr::BLOCK(
+            "RBRACE".into(),
+            parser.cur_token.to_token(),
+        ));
     }
+    let start = parser.cur_token.span.start;
     let mut block_stmts = Vec::new();
 
     parser.next_token(); // to move on from "{"
-    while !parser.cur_token.is_same_with(TOKEN::RBRACE) {
-        if parser.cur_token.is_same_with(TOKEN::EOF) {
-            return Err(ParseErr::BLOCK("LBRACE".into(), parser.cur_token.clone()));
+    while !parser.cur_token.is(TokenKind::RBRACE) {
+        if parser.cur_token.is(TokenKind::EOF) {
+            return Err(ParseErr::BLOCK(
+                "LBRACE".into(),
+                parser.cur_token.to_token(),
+            ));
+        }
+
+        if parser.cur_token.is(TokenKind::SEMICOLON) {
+            parser.next_token();
+            continue;
         }
 
         let stmt = parse_statement(parser)?;
@@ -117,5 +143,7 @@ pub fn parse_block_statement<'a>(parser: &mut Parser<'a>) -> Result<Vec<Statemen
         parser.next_token();
     }
 
-    return Ok(block_stmts);
+    let mut block = BlockStatement::new(block_stmts);
+    block.span = Span::new(start, parser.cur_token.span.end);
+    return Ok(block);
 }
@@ -1,23 +1,29 @@
 use crate::{
-    ast::ast::{Expression, Identifier, Program},
+    ast::ast::{Expression, Program, Span},
     errors::parser_errs::ParseErr,
-    lexer::{lexer::Lexer, token::TOKEN},
+    lexer::{
+        lexer::{Lexeme, Lexer},
+        token::TokenKind,
+    },
 };
 
-use std::collections::HashMap;
-
 use super::{
-    parse_infix::{parse_arr_index_expression, parse_call_expression, parse_infix_expression},
+    parse_infix::{
+        parse_arr_index_expression, parse_call_expression, parse_infix_expression,
+        parse_member_expression,
+    },
     parse_prefix::{
         parse_array_literal, parse_boolean_literal, parse_function_literal, parse_group_expression,
-        parse_identifier, parse_if_expression, parse_int_literal, parse_prefix_expression,
-        parse_string,
+        parse_hash_literal, parse_identifier, parse_if_expression, parse_int_literal,
+        parse_prefix_expression, parse_string,
     },
     parse_statement::parse_statement,
 };
 
 type PrefixParseFn = fn(&mut Parser) -> Result<Expression, ParseErr>;
 type InfixParseFn = fn(&mut Parser, Expression) -> Result<Expression, ParseErr>;
+// Parse functions indexed by `TokenKind as usize`
+type ParseTable<F> = [Option<F>; TokenKind::COUNT];
 pub enum Precedence {
     LOWEST,
     EQUALS,
@@ -29,14 +35,14 @@ pub enum Precedence {
     CALL,
 }
 impl Precedence {
-    pub fn from_token(token: TOKEN) -> Self {
-        match token {
-            TOKEN::EQ | TOKEN::NotEQ => Self::EQUALS,
-            TOKEN::LT | TOKEN::GT => Self::LESSGREATER,
-            TOKEN::PLUS | TOKEN::MINUS => Self::SUM,
-            TOKEN::SLASH | TOKEN::ASTERISK => Self::PRODUCT,
-            TOKEN::LBRACKET => Self::INDEX,
-            TOKEN::LPAREN => Self::CALL,
+    pub fn from_kind(kind: TokenKind) -> Self {
+        match kind {
+            TokenKind::EQ | TokenKind::NotEQ => Self::EQUALS,
+            TokenKind::LT | TokenKind::GT => Self::LESSGREATER,
+            TokenKind::PLUS | TokenKind::MINUS => Self::SUM,
+            TokenKind::SLASH | TokenKind::ASTERISK => Self::PRODUCT,
+            TokenKind::LBRACKET | TokenKind::DOT => Self::INDEX,
+            TokenKind::LPAREN => Self::CALL,
             _ => Self::LOWEST,
         }
     }
@@ -57,73 +63,87 @@ impl Precedence {
 #[derive(Debug)]
 pub struct Parser<'a> {
     l: Lexer<'a>,
-    pub cur_token: TOKEN,
-    pub peek_token: TOKEN,
-    pub prefix_parse_fns: HashMap<String, PrefixParseFn>,
-    pub infix_parse_fns: HashMap<String, InfixParseFn>,
+    pub cur_token: Lexeme<'a>,
+    pub peek_token: Lexeme<'a>,
+    pub prefix_parse_fns: ParseTable<PrefixParseFn>,
+    pub infix_parse_fns: ParseTable<InfixParseFn>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(l: Lexer<'a>) -> Parser<'a> {
         let mut p = Parser {
             l,
-            cur_token: TOKEN::EOF,
-            peek_token: TOKEN::EOF,
-            prefix_parse_fns: HashMap::new(),
-            infix_parse_fns: HashMap::new(),
+            cur_token: Lexeme::new(TokenKind::EOF, "", Span::default()),
+            peek_token: Lexeme::new(TokenKind::EOF, "", Span::default()),
+            prefix_parse_fns: [None; TokenKind::COUNT],
+            infix_parse_fns: [None; TokenKind::COUNT],
         };
         // PREFIX PARSERS
-        p.register_prefix(TOKEN::IDENT(Identifier("".into())), parse_identifier);
-        p.register_prefix(TOKEN::STRING("".into()), parse_string);
-        p.register_prefix(TOKEN::NUMBER(0), parse_int_literal);
-        p.register_prefix(TOKEN::TRUE, parse_boolean_literal);
-        p.register_prefix(TOKEN::FALSE, parse_boolean_literal);
-        p.register_prefix(TOKEN::BANG, parse_prefix_expression);
-        p.register_prefix(TOKEN::MINUS, parse_prefix_expression);
-        p.register_prefix(TOKEN::LPAREN, parse_group_expression);
-        p.register_prefix(TOKEN::LBRACKET, parse_array_literal);
-        p.register_prefix(TOKEN::IF, parse_if_expression);
-        p.register_prefix(TOKEN::FUNCTION, parse_function_literal);
+        p.register_prefix(TokenKind::IDENT, parse_identifier);
+        p.register_prefix(TokenKind::STRING, parse_string);
+        p.register_prefix(TokenKind::NUMBER, parse_int_literal);
+        p.register_prefix(TokenKind::TRUE, parse_boolean_literal);
+        p.register_prefix(TokenKind::FALSE, parse_boolean_literal);
+        p.register_prefix(TokenKind::BANG, parse_prefix_expression);
+        p.register_prefix(TokenKind::MINUS, parse_prefix_expression);
+        p.register_prefix(TokenKind::LPAREN, parse_group_expression);
+        p.register_prefix(TokenKind::LBRACKET, parse_array_literal);
+        p.register_prefix(TokenKind::LBRACE, parse_hash_literal);
+        p.register_prefix(TokenKind::IF, parse_if_expression);
+        p.register_prefix(TokenKind::FUNCTION, parse_function_literal);
         // INFIX PARSERS
-        p.register_infix(TOKEN::PLUS, parse_infix_expression);
-        p.register_infix(TOKEN::MINUS, parse_infix_expression);
-        p.register_infix(TOKEN::SLASH, parse_infix_expression);
-        p.register_infix(TOKEN::ASTERISK, parse_infix_expression);
-        p.register_infix(TOKEN::EQ, parse_infix_expression);
-        p.register_infix(TOKEN::NotEQ, parse_infix_expression);
-        p.register_infix(TOKEN::LT, parse_infix_expression);
-        p.register_infix(TOKEN::GT, parse_infix_expression);
-        p.register_infix(TOKEN::LPAREN, parse_call_expression);
-        p.register_infix(TOKEN::LBRACKET, parse_arr_index_expression);
+        p.register_infix(TokenKind::PLUS, parse_infix_expression);
+        p.register_infix(TokenKind::MINUS, parse_infix_expression);
+        p.register_infix(TokenKind::SLASH, parse_infix_expression);
+        p.register_infix(TokenKind::ASTERISK, parse_infix_expression);
+        p.register_infix(TokenKind::EQ, parse_infix_expression);
+        p.register_infix(TokenKind::NotEQ, parse_infix_expression);
+        p.register_infix(TokenKind::LT, parse_infix_expression);
+        p.register_infix(TokenKind::GT, parse_infix_expression);
+        p.register_infix(TokenKind::LPAREN, parse_call_expression);
+        p.register_infix(TokenKind::LBRACKET, parse_arr_index_expression);
+        p.register_infix(TokenKind::DOT, parse_member_expression);
         //Read two token so current token and peek token are both set
         p.next_token();
         p.next_token();
         return p;
     }
-    fn register_prefix(&mut self, token: TOKEN, func: PrefixParseFn) {
-        self.prefix_parse_fns.insert(token.to_type_name(), func);
+    fn register_prefix(&mut self, kind: TokenKind, func: PrefixParseFn) {
+        self.prefix_parse_fns[kind as usize] = Some(func);
     }
 
-    fn register_infix(&mut self, token: TOKEN, func: InfixParseFn) {
-        self.infix_parse_fns.insert(token.to_type_name(), func);
+    fn register_infix(&mut self, kind: TokenKind, func: InfixParseFn) {
+        self.infix_parse_fns[kind as usize] = Some(func);
+    }
+
+    pub fn prefix_parse_fn(&self, kind: TokenKind) -> Option<PrefixParseFn> {
+        self.prefix_parse_fns[kind as usize]
+    }
+    pub fn infix_parse_fn(&self, kind: TokenKind) -> Option<InfixParseFn> {
+        self.infix_parse_fns[kind as usize]
     }
 
     pub fn next_token(&mut self) {
-        self.cur_token = self.peek_token.clone();
-        self.peek_token = self.l.next_token();
+        self.cur_token = self.peek_token;
+        self.peek_token = self.l.next_lexeme();
     }
 
     pub fn peek_precedence(&self) -> Precedence {
-        Precedence::from_token(self.peek_token.clone())
+        Precedence::from_kind(self.peek_token.kind)
     }
     pub fn cur_precedence(&self) -> Precedence {
-        Precedence::from_token(self.cur_token.clone())
+        Precedence::from_kind(self.cur_token.kind)
     }
 
     pub fn parse_program(&mut self) -> Result<Program, ParseErr> {
         let mut program = Program { statements: vec![] };
 
-        while self.cur_token != TOKEN::EOF {
+        while !self.cur_token.is(TokenKind::EOF) {
+            // stray semicolons are not statements
+            if self.cur_token.is(TokenKind::SEMICOLON) {
+                self.next_token();
+                continue;
+            }
             let stmt = parse_statement(self)?;
             program.statements.push(stmt);
             self.next_token();
@@ -4,7 +4,12 @@ mod tests {
 
     use std::vec;
 
-    use crate::{ast::ast::NodeTrait, lexer::lexer::Lexer, parser::parser::Parser};
+    use crate::{
+        ast::ast::NodeTrait,
+        errors::parser_errs::ParseErr,
+        lexer::{lexer::Lexer, token::TOKEN},
+        parser::parser::Parser,
+    };
 
     #[test]
     fn test_parser() {
@@ -223,6 +228,12 @@ mod tests {
                 "3 + 4 * 5 == 3 * 1 + 4 * 5",
                 "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
             ),
+            ("-host.log(a.b)[1]", "(-host.log(a.b)[1])"),
+            ("a.b.c + 1", "(a.b.c + 1)"),
+            (
+                r#"{"a": 1 + 2, k: [b]}["a"]"#,
+                r#"{"a": (1 + 2), k: [b]}["a"]"#,
+            ),
         ];
 
         for &(input, expected) in tests.iter() {
@@ -382,4 +393,57 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_statement_spans() {
+        let input = "let x = 1;\nx = x + 1\nif (x) {\n  x;\n} else if (y) { 2 }\n;; f(x);";
+        let p = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let spans = p
+            .statements
+            .iter()
+            .map(|x| x.span().text(input))
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            spans,
+            [
+                "let x = 1;",
+                "x = x + 1",
+                // the first `;` ends the `if`, the second is skipped
+                "if (x) {\n  x;\n} else if (y) { 2 }\n;",
+                "f(x);"
+            ]
+        );
+
+        let if_exp = p.statements[2].to_exp_stmt().unwrap().expression.unwrap();
+        let if_exp = if_exp.to_if().unwrap();
+        assert_eq!(if_exp.consequence.span.text(input), "{\n  x;\n}");
+        assert_eq!(if_exp.consequence.statements[0].span().text(input), "x;");
+        let alternative = if_exp.alternative.unwrap();
+        assert_eq!(alternative.span.text(input), "if (y) { 2 }");
+    }
+
+    #[test]
+    fn test_number_overflow() {
+        for input in ["99999999999999999999", "let x = [1, 99999999999999999999];"] {
+            let err = Parser::new(Lexer::new(input)).parse_program().unwrap_err();
+            let ParseErr::NumberOverflow(text, span) = err else {
+                panic!("not an overflow: {}", err);
+            };
+            assert_eq!(text, "99999999999999999999");
+            assert_eq!(span.text(input), text);
+        }
+        assert_eq!(
+            Lexer::new("9223372036854775808").next_lexeme().to_token(),
+            TOKEN::NUMBER(i64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_reassign_to_str() {
+        let p = Parser::new(Lexer::new("a[0] = 1 + 2;"))
+            .parse_program()
+            .unwrap();
+        assert_eq!(p.statements.len(), 1);
+        assert_eq!(p.statements[0].to_str(), "a[0] = (1 + 2);");
+    }
 }
@@ -0,0 +1,130 @@
+use std::time::Instant;
+
+use r_piece::{
+    lexer::{lexer::Lexer, token::TokenKind},
+    Interpreter, InterpreterErr,
+};
+
+pub const HELP: &str = "\
+:tokens <src>  show the tokens of src
+:ast <src>     show the parsed program
+:env           list the bindings and their types
+:type <expr>   evaluate expr and show its type
+:load <file>   evaluate a file into the session
+:reset         drop every binding
+:time <expr>   evaluate expr and show how long it took
+:help          show this help
+exit           leave the REPL, as does Ctrl-D";
+
+// REPL meta-commands, lines starting with `:`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command<'a> {
+    Tokens(&'a str),
+    Ast(&'a str),
+    Env,
+    Type(&'a str),
+    Load(&'a str),
+    Reset,
+    Time(&'a str),
+    Help,
+}
+
+impl<'a> Command<'a> {
+    // `None` when the line is not a command, `Err` for an unknown command or
+    // a missing argument
+    pub fn parse(line: &'a str) -> Option<Result<Self, String>> {
+        let line = line.trim().strip_prefix(':')?;
+        let (name, arg) = match line.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (line, ""),
+        };
+        let command = match name {
+            "tokens" => Command::Tokens(arg),
+            "ast" => Command::Ast(arg),
+            "env" => Command::Env,
+            "type" => Command::Type(arg),
+            "load" => Command::Load(arg),
+            "reset" => Command::Reset,
+            "time" => Command::Time(arg),
+            "help" => Command::Help,
+            name => return Some(Err(format!("Unknown command :{}, try :help", name))),
+        };
+        let needs_arg = matches!(
+            command,
+            Command::Type(_) | Command::Load(_) | Command::Time(_)
+        );
+        if needs_arg && arg.is_empty() {
+            return Some(Err(format!("Usage: :{} <{}>", name, usage_arg(name))));
+        }
+        Some(Ok(command))
+    }
+}
+
+fn usage_arg(name: &str) -> &'static str {
+    match name {
+        "load" => "file",
+        _ => "expr",
+    }
+}
+
+// Runs a command and returns what the REPL should print
+pub fn run_command(
+    command: Command,
+    interpreter: &mut Interpreter,
+) -> Result<String, InterpreterErr> {
+    match command {
+        Command::Tokens(src) => {
+            let mut lexer = Lexer::new(src);
+            let mut lines = vec![];
+            loop {
+                let lexeme = lexer.next_lexeme();
+                if lexeme.is(TokenKind::EOF) {
+                    break;
+                }
+                lines.push(format!("{:?} {:?}", lexeme.kind, lexeme.text));
+            }
+            Ok(lines.join("\n"))
+        }
+        Command::Ast(src) => {
+            let program = Interpreter::parse(src)?;
+            Ok(format!("{:#?}", program.statements))
+        }
+        Command::Env => {
+            let env = interpreter.env();
+            let env = env.borrow();
+            let lines = env
+                .names()
+                .into_iter()
+                .filter_map(|name| {
+                    let value = env.get(&name).ok()?;
+                    Some(format!("{}: {}", name, value.get_type()))
+                })
+                .collect::<Vec<String>>();
+            Ok(lines.join("\n"))
+        }
+        Command::Type(src) => Ok(interpreter.eval_str(src)?.get_type()),
+        Command::Load(path) => Ok(display(interpreter.eval_file(path)?.to_string())),
+        Command::Reset => {
+            interpreter.reset();
+            Ok(String::new())
+        }
+        Command::Time(src) => {
+            let start = Instant::now();
+            let value = interpreter.eval_str(src)?.to_string();
+            let elapsed = format!("time: {:?}", start.elapsed());
+            match display(value) {
+                value if value.is_empty() => Ok(elapsed),
+                value => Ok(format!("{}\n{}", value, elapsed)),
+            }
+        }
+        Command::Help => Ok(HELP.to_string()),
+    }
+}
+
+// Null results are not printed
+pub fn display(value: String) -> String {
+    match value.as_str() {
+        "Null" => String::new(),
+        _ => value,
+    }
+}
@@ -0,0 +1,61 @@
+use r_piece::{
+    evaluator::environment::Env,
+    lexer::{lexer::is_letter, token::KEYWORDS},
+};
+use rustyline::{completion::Completer, Context, Helper, Highlighter, Hinter, Result, Validator};
+
+// Line editor helper completing keywords, builtins and bound identifiers
+#[derive(Helper, Highlighter, Hinter, Validator)]
+pub struct ReplHelper {
+    env: Env,
+    builtins: Vec<String>,
+}
+
+impl ReplHelper {
+    pub fn new(env: Env, builtins: Vec<String>) -> Self {
+        Self { env, builtins }
+    }
+
+    pub fn set_env(&mut self, env: Env) {
+        self.env = env;
+    }
+
+    // Everything that can be completed, bindings are read at completion time
+    fn names(&self) -> Vec<String> {
+        let mut names = self.env.borrow().names();
+        names.extend(KEYWORDS.keys().map(|x| x.to_string()));
+        names.extend(self.builtins.iter().cloned());
+        names
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Result<(usize, Vec<String>)> {
+        let start = word_start(line, pos);
+        Ok((start, candidates(&line[start..pos], self.names())))
+    }
+}
+
+// Start of the identifier before the cursor, dotted paths like `host.log`
+// count as one word
+pub fn word_start(line: &str, pos: usize) -> usize {
+    line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_letter(*c) || *c == '.')
+        .last()
+        .map_or(pos, |(idx, _)| idx)
+}
+
+// Names starting with the prefix, sorted and without duplicates
+pub fn candidates(prefix: &str, names: Vec<String>) -> Vec<String> {
+    let mut matches: Vec<String> = names
+        .into_iter()
+        .filter(|x| x.starts_with(prefix))
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches
+}
@@ -0,0 +1,42 @@
+use r_piece::lexer::{lexer::Lexer, token::TokenKind};
+
+// Whether the REPL should read more lines before evaluating `src`: a
+// bracket is still open, a string is unterminated or the last token is an
+// operator. Extra closing brackets are left for the parser to report.
+pub fn is_incomplete(src: &str) -> bool {
+    let mut lexer = Lexer::new(src);
+    let mut depth = 0;
+    let mut last = TokenKind::EOF;
+    loop {
+        let lexeme = lexer.next_lexeme();
+        match lexeme.kind {
+            TokenKind::EOF => break,
+            TokenKind::LPAREN | TokenKind::LBRACE | TokenKind::LBRACKET => depth += 1,
+            TokenKind::RPAREN | TokenKind::RBRACE | TokenKind::RBRACKET => depth -= 1,
+            TokenKind::ILLEGAL if lexeme.text.starts_with('"') => return true,
+            _ => {}
+        }
+        last = lexeme.kind;
+    }
+    depth > 0 || expects_operand(last)
+}
+
+fn expects_operand(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::ASSIGN
+            | TokenKind::PLUS
+            | TokenKind::MINUS
+            | TokenKind::BANG
+            | TokenKind::ASTERISK
+            | TokenKind::SLASH
+            | TokenKind::GT
+            | TokenKind::LT
+            | TokenKind::EQ
+            | TokenKind::NotEQ
+            | TokenKind::COMMA
+            | TokenKind::COLON
+            | TokenKind::DOT
+            | TokenKind::ELSE
+    )
+}
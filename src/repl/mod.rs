@@ -1 +1,5 @@
+pub mod command;
+pub mod complete;
+pub mod input;
 pub mod repl;
+mod test;
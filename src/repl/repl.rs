@@ -1,57 +1,153 @@
-use std::{
-    cell::RefCell,
-    io::{self, Write},
-    rc::Rc,
+use std::{fs, path::PathBuf};
+
+use r_piece::{
+    defer,
+    errors::eval_errs::EvalErr,
+    evaluator::{host::ProcessHost, runtime::Rebind},
+    Interpreter, InterpreterErr,
 };
+use rustyline::{error::ReadlineError, history::DefaultHistory, Editor};
 
-#[allow(unused)]
-use crate::{ast::ast::stringnify_stmt, lexer::lexer::Lexer, parser::parser::Parser};
-use crate::{
-    ast::ast::{Node, Statement},
-    evaluator::eval::eval,
+use super::{
+    command::{display, run_command, Command},
+    complete::ReplHelper,
+    input::is_incomplete,
 };
-use crate::{defer, evaluator::environment::Environment};
 
-pub fn run_repl() {
+const PROMPT: &str = ">> ";
+// Shown while a multi-line input is pending
+const CONTINUATION_PROMPT: &str = ".. ";
+
+// Returns the exit code, non zero only when a script calls `exit`
+pub fn run_repl() -> u8 {
     defer!(println!("Exit REPL!"));
-    println!("Welcome to the REPL CLI. Type 'exit' to quit.");
-    let env = Rc::new(RefCell::new(Environment::new()));
+    println!("Welcome to the REPL CLI. Type 'exit' to quit, ':help' for commands.");
+    let mut interpreter = Interpreter::new();
+    interpreter.set_host(ProcessHost::default());
+    // Inputs can be edited and run again
+    interpreter.set_rebind(Rebind::Warn);
 
-    loop {
-        print!(">> ");
-        io::stdout().flush().expect("Failed to flush stdout");
+    // Ctrl-C stops the running evaluation instead of killing the REPL
+    let handler = interpreter.interrupt_handle();
+    if let Err(err) = ctrlc::set_handler(move || handler.interrupt()) {
+        println!("Failed to install Ctrl-C handler: {}", err);
+    }
 
-        let mut input = String::new();
+    // Line editing, Ctrl-R searches the history
+    let mut editor = match Editor::<ReplHelper, DefaultHistory>::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            println!("Failed to start the line editor: {}", err);
+            return 1;
+        }
+    };
+    let builtins = interpreter
+        .builtins()
+        .names()
+        .map(|x| x.to_string())
+        .collect();
+    editor.set_helper(Some(ReplHelper::new(interpreter.env(), builtins)));
+    let history = history_path();
+    if let Some(path) = &history {
+        let _ = editor.load_history(path);
+    }
 
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read line");
+    let code = read_eval_print(&mut editor, &mut interpreter);
 
-        if input == "exit\n" {
-            break;
+    if let Some(path) = &history {
+        if let Err(err) = editor.save_history(path) {
+            println!("Failed to save history to {}: {}", path.display(), err);
         }
+    }
+    code
+}
 
-        let l = Lexer::new(&input);
-        let mut p = Parser::new(l.clone());
+fn read_eval_print(
+    editor: &mut Editor<ReplHelper, DefaultHistory>,
+    interpreter: &mut Interpreter,
+) -> u8 {
+    let interrupt = interpreter.interrupt_handle();
+    let mut buffer = String::new();
+    loop {
+        let prompt = match buffer.is_empty() {
+            true => PROMPT,
+            false => CONTINUATION_PROMPT,
+        };
+        let input = match editor.readline(prompt) {
+            Ok(input) => input,
+            // Ctrl-C clears the line being edited
+            Err(ReadlineError::Interrupted) => {
+                buffer.clear();
+                continue;
+            }
+            // Ctrl-D drops a pending input, or leaves on an empty prompt
+            Err(ReadlineError::Eof) if !buffer.is_empty() => {
+                buffer.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => return 0,
+            Err(err) => {
+                println!("Failed to read line: {}", err);
+                return 1;
+            }
+        };
 
-        let program = p.parse_program();
-        if program.is_err() {
-            println!("{:?}", program.err().unwrap().to_string());
-            continue;
+        // `exit` alone, whatever the line ending
+        if buffer.is_empty() && input.trim() == "exit" {
+            return 0;
         }
 
-        // println!("{:?}", stringnify_stmt(&p.statements));
-        let x = eval(
-            Node::Statement(Statement::Program(program.unwrap())),
-            Rc::clone(&env),
-        );
-        if x.is_err() {
-            println!("{:?}", x.unwrap_err().to_string());
-        } else {
-            let str = x.unwrap().to_string();
-            if str != "Null" {
-                println!("{}", str);
+        if buffer.is_empty() {
+            if let Some(command) = Command::parse(&input) {
+                let _ = editor.add_history_entry(input.trim());
+                let result = command.map(|command| {
+                    interrupt.clear();
+                    let reset = command == Command::Reset;
+                    let result = run_command(command, interpreter);
+                    // completion follows the fresh environment
+                    if let (true, Some(helper)) = (reset, editor.helper_mut()) {
+                        helper.set_env(interpreter.env());
+                    }
+                    result
+                });
+                match result {
+                    Ok(Err(InterpreterErr::Eval(EvalErr::Exit(code)))) => return code,
+                    Ok(Err(err)) => println!("{:?}", err.to_string()),
+                    Ok(Ok(output)) => print_output(&output),
+                    Err(usage) => println!("{}", usage),
+                }
+                continue;
             }
         }
+
+        buffer.push_str(&input);
+        buffer.push('\n');
+        if is_incomplete(&buffer) {
+            continue;
+        }
+        let _ = editor.add_history_entry(buffer.trim_end());
+
+        interrupt.clear();
+        let x = interpreter.eval_str(&buffer);
+        buffer.clear();
+        match x {
+            Err(InterpreterErr::Eval(EvalErr::Exit(code))) => return code,
+            Err(err) => println!("{:?}", err.to_string()),
+            Ok(value) => print_output(&display(value.to_string())),
+        }
     }
 }
+
+fn print_output(output: &str) {
+    if !output.is_empty() {
+        println!("{}", output);
+    }
+}
+
+// History is kept under the user's data directory, e.g.
+// `~/.local/share/r-piece/history` on Linux
+fn history_path() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("r-piece");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("history"))
+}
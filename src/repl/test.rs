@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod tests {
+    use r_piece::{
+        evaluator::{environment::Environment, object::Object},
+        utils::shared::{Rc, RefCell},
+        Interpreter,
+    };
+    use rustyline::{completion::Completer, history::DefaultHistory, Context};
+
+    use crate::repl::{
+        command::{run_command, Command},
+        complete::{word_start, ReplHelper},
+        input::is_incomplete,
+    };
+
+    #[test]
+    fn test_is_incomplete() {
+        let tests = vec![
+            ("", false),
+            ("let x = 1;", false),
+            ("let add = fn(a, b) {", true),
+            ("let add = fn(a, b) {\n  a + b\n};", false),
+            ("[1, 2,", true),
+            ("[1, 2,\n 3]", false),
+            ("print((1 + 2)", true),
+            (r#"let s = "multi"#, true),
+            (r#"let s = "a { b";"#, false),
+            (r#"let s = "a\"b"#, true),
+            ("let x = 1 +", true),
+            ("let x =", true),
+            ("h.", true),
+            ("if (x) { 1 } else", true),
+            ("1 + 1", false),
+            ("let x = 1; }", false),
+            ("#!/usr/bin/env r-piece", false),
+        ];
+        for (input, expected) in tests.into_iter() {
+            assert_eq!(is_incomplete(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_completion() {
+        let tests = vec![
+            ("le", 0),
+            ("print(ma", 6),
+            ("x + host.lo", 4),
+            ("", 0),
+            ("f(", 2),
+        ];
+        for (line, expected) in tests.into_iter() {
+            assert_eq!(word_start(line, line.len()), expected, "{}", line);
+        }
+
+        let mut interpreter = Interpreter::new();
+        interpreter.register_builtin("host.log", None, |_, _| Ok(Object::Null));
+        interpreter
+            .eval_str("let mapping = 1; let letter = 2;")
+            .unwrap();
+        let builtins = interpreter
+            .builtins()
+            .names()
+            .map(|x| x.to_string())
+            .collect();
+        let helper = ReplHelper::new(interpreter.env(), builtins);
+        let history = DefaultHistory::new();
+        let ctx = Context::new(&history);
+        let complete = |line: &str| helper.complete(line, line.len(), &ctx).unwrap();
+
+        assert_eq!(
+            complete("le"),
+            (0, vec!["len".into(), "let".into(), "letter".into()])
+        );
+        assert_eq!(
+            complete("print(ma"),
+            (6, vec!["map".into(), "mapping".into()])
+        );
+        assert_eq!(complete("host.l"), (0, vec!["host.log".into()]));
+        assert_eq!(complete("zz"), (0, vec![]));
+
+        // bindings made after the helper was created, in an inner scope too
+        interpreter.eval_str("let zebra = 3;").unwrap();
+        assert_eq!(complete("ze"), (0, vec!["zebra".into()]));
+        let inner = Environment::new_with_outer(interpreter.env());
+        let helper = ReplHelper::new(Rc::new(RefCell::new(inner)), vec![]);
+        assert_eq!(helper.complete("zeb", 3, &ctx).unwrap().1, vec!["zebra"]);
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let tests = vec![
+            (":tokens let x", Command::Tokens("let x")),
+            (":ast  1 + 2 ", Command::Ast("1 + 2")),
+            (":env", Command::Env),
+            (":env\r", Command::Env),
+            (":type [1]", Command::Type("[1]")),
+            (":load a b.rp", Command::Load("a b.rp")),
+            (":reset", Command::Reset),
+            (":time\tf(1)", Command::Time("f(1)")),
+            (":help", Command::Help),
+        ];
+        for (line, expected) in tests.into_iter() {
+            assert_eq!(Command::parse(line), Some(Ok(expected)), "{}", line);
+        }
+        assert_eq!(Command::parse("let x = 1"), None);
+        assert_eq!(Command::parse("{\"a\": 1}"), None);
+        let err = Command::parse(":nope").unwrap().unwrap_err();
+        assert!(err.contains(":nope"));
+        let err = Command::parse(":load").unwrap().unwrap_err();
+        assert_eq!(err, "Usage: :load <file>");
+    }
+
+    #[test]
+    fn test_run_command() {
+        let mut interpreter = Interpreter::new();
+        let mut run = |line: &str| {
+            let command = Command::parse(line).unwrap().unwrap();
+            run_command(command, &mut interpreter).map_err(|err| err.to_string())
+        };
+
+        assert_eq!(
+            run(":tokens let x = \"a\"").unwrap(),
+            "LET \"let\"\nIDENT \"x\"\nASSIGN \"=\"\nSTRING \"a\""
+        );
+        let ast = run(":ast let x = 1;").unwrap();
+        assert!(ast.starts_with("[\n    Let("), "{}", ast);
+        assert!(run(":ast let = 1").is_err());
+
+        assert_eq!(run(":type [1]").unwrap(), "Array");
+        assert_eq!(run(":type fn(x) { x }").unwrap(), "Function");
+
+        let path = std::env::temp_dir().join(format!("r-piece-load-{}.rp", std::process::id()));
+        std::fs::write(&path, "let loaded = fn(x) { x * 2 }; let n = 3;").unwrap();
+        assert_eq!(run(&format!(":load {}", path.display())).unwrap(), "");
+        assert_eq!(run(":env").unwrap(), "loaded: Function\nn: Number");
+        assert!(run(":load /no/such/file.rp").is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        let timed = run(":time loaded(n)").unwrap();
+        assert!(timed.starts_with("6\ntime: "), "{}", timed);
+        assert!(run(":time let t = 1;").unwrap().starts_with("time: "));
+
+        assert_eq!(run(":reset").unwrap(), "");
+        assert_eq!(run(":env").unwrap(), "");
+        assert!(run(":help").unwrap().contains(":tokens"));
+    }
+}
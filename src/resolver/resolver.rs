@@ -0,0 +1,342 @@
+use crate::{
+    ast::ast::{Expression, FunctionLiteral, Identifier, Program, Span, Statement},
+    evaluator::builtin::BuiltinRegistry,
+    lexer::token::TOKEN,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    Let,
+    Param,
+}
+
+// A name bound by `let` or by a function parameter
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub name: String,
+    pub kind: BindingKind,
+    pub span: Span, // of the name where it is bound
+    pub scope: usize,
+    pub ty: Option<&'static str>, // type of the `let` value, when known statically
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Binding(usize),
+    Builtin,
+    Unresolved,
+}
+
+// A use of a name. Namespaced builtins like `host.log` are one reference
+// spanning the whole path.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub name: String,
+    pub span: Span,
+    pub target: Target,
+    pub write: bool, // `name = value`
+}
+
+// The environment of the program or of a function call, blocks share the
+// enclosing one
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub parent: Option<usize>,
+    pub span: Span, // the function body, the program spans everything
+    pub bindings: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    pub scopes: Vec<Scope>,
+    pub bindings: Vec<Binding>,
+    pub references: Vec<Reference>,
+}
+
+impl Resolution {
+    pub fn references_to(&self, binding: usize) -> impl Iterator<Item = &Reference> {
+        self.references
+            .iter()
+            .filter(move |x| x.target == Target::Binding(binding))
+    }
+
+    pub fn is_read(&self, binding: usize) -> bool {
+        self.references_to(binding).any(|x| !x.write)
+    }
+
+    pub fn is_reassigned(&self, binding: usize) -> bool {
+        self.references_to(binding).any(|x| x.write)
+    }
+
+    // The binding named at `offset`, where it is bound or used
+    pub fn binding_at(&self, offset: usize) -> Option<usize> {
+        if let Some(idx) = self.bindings.iter().position(|x| x.span.contains(offset)) {
+            return Some(idx);
+        }
+        self.references
+            .iter()
+            .find(|x| x.span.contains(offset))
+            .and_then(|x| match x.target {
+                Target::Binding(idx) => Some(idx),
+                _ => None,
+            })
+    }
+
+    pub fn reference_at(&self, offset: usize) -> Option<&Reference> {
+        self.references.iter().find(|x| x.span.contains(offset))
+    }
+
+    // The innermost scope around `offset`
+    pub fn scope_at(&self, offset: usize) -> usize {
+        let mut scope = 0;
+        for (idx, x) in self.scopes.iter().enumerate() {
+            if x.span.contains(offset) && self.scopes[scope].span.start <= x.span.start {
+                scope = idx;
+            }
+        }
+        scope
+    }
+
+    // Bindings of the scope and of the enclosing ones, innermost first
+    pub fn visible(&self, scope: usize) -> Vec<usize> {
+        let mut visible = vec![];
+        let mut current = Some(scope);
+        while let Some(idx) = current {
+            visible.extend(self.scopes[idx].bindings.iter().rev());
+            current = self.scopes[idx].parent;
+        }
+        visible
+    }
+}
+
+// Binds every name of the program to its `let` or parameter, following the
+// evaluator: a function sees the bindings made after it is defined, top
+// level code only the ones made before. Unbound names may be builtins.
+pub fn resolve(program: &Program, builtins: &BuiltinRegistry) -> Resolution {
+    let mut resolver = Resolver {
+        builtins,
+        resolution: Resolution::default(),
+        deferred: vec![],
+    };
+    resolver.scope(None, Span::new(0, usize::MAX), &[], &program.statements);
+    resolver.resolution
+}
+
+struct Resolver<'a> {
+    builtins: &'a BuiltinRegistry,
+    resolution: Resolution,
+    // function bodies, resolved once their enclosing scope is complete
+    deferred: Vec<&'a FunctionLiteral>,
+}
+
+impl<'a> Resolver<'a> {
+    fn scope(
+        &mut self,
+        parent: Option<usize>,
+        span: Span,
+        params: &[Identifier],
+        statements: &'a [Statement],
+    ) {
+        let scope = self.resolution.scopes.len();
+        self.resolution.scopes.push(Scope {
+            parent,
+            span,
+            bindings: vec![],
+        });
+        for param in params {
+            self.declare(scope, param, BindingKind::Param, None);
+        }
+        let mark = self.deferred.len();
+        self.statements(scope, statements);
+        for function in self.deferred.split_off(mark) {
+            let body = &function.body;
+            self.scope(
+                Some(scope),
+                body.span,
+                &function.parameters,
+                &body.statements,
+            );
+        }
+    }
+
+    fn statements(&mut self, scope: usize, statements: &'a [Statement]) {
+        for stmt in statements {
+            match stmt {
+                Statement::Let(stmt) => {
+                    self.expression(scope, &stmt.value);
+                    let ty = static_type(&stmt.value);
+                    self.declare(scope, &stmt.name, BindingKind::Let, ty);
+                }
+                Statement::Reassign(stmt) => {
+                    self.expression(scope, &stmt.value);
+                    match &stmt.lhs {
+                        Expression::Identifier(ident) => self.reference(scope, ident, true),
+                        lhs => self.expression(scope, lhs),
+                    }
+                }
+                Statement::Return(stmt) => {
+                    if let Some(expression) = &stmt.expression {
+                        self.expression(scope, expression);
+                    }
+                }
+                Statement::Expression(stmt) => {
+                    if let Some(expression) = &stmt.expression {
+                        self.expression(scope, expression);
+                    }
+                }
+                Statement::Program(program) => self.statements(scope, &program.statements),
+                Statement::Block(block) => self.statements(scope, &block.statements),
+            }
+        }
+    }
+
+    fn expression(&mut self, scope: usize, expression: &'a Expression) {
+        match expression {
+            Expression::Identifier(ident) => self.reference(scope, ident, false),
+            Expression::ArrayLiteral(arr) => {
+                arr.elements.iter().for_each(|x| self.expression(scope, x));
+            }
+            Expression::HashLiteral(hash) => hash.pairs.iter().for_each(|(k, v)| {
+                self.expression(scope, k);
+                self.expression(scope, v);
+            }),
+            Expression::Index(index) => {
+                self.expression(scope, &index.left);
+                self.expression(scope, &index.index);
+            }
+            Expression::Member(member) => {
+                // `ns.name` is a builtin when `ns` is not bound
+                if let Some((root, path)) = builtin_path(expression) {
+                    if self.lookup(scope, &root.0).is_none() && self.builtins.contains(&path) {
+                        self.resolution.references.push(Reference {
+                            name: path,
+                            span: Span::new(root.1.start, member.property.1.end),
+                            target: Target::Builtin,
+                            write: false,
+                        });
+                        return;
+                    }
+                }
+                self.expression(scope, &member.object);
+            }
+            Expression::Prefix(prefix) => self.expression(scope, &prefix.right),
+            Expression::Infix(infix) => {
+                self.expression(scope, &infix.left);
+                self.expression(scope, &infix.right);
+            }
+            Expression::If(if_exp) => {
+                self.expression(scope, &if_exp.condition);
+                self.statements(scope, &if_exp.consequence.statements);
+                if let Some(alternative) = &if_exp.alternative {
+                    self.statements(scope, &alternative.statements);
+                }
+            }
+            Expression::Function(function) => self.deferred.push(function),
+            Expression::Call(call) => {
+                self.expression(scope, &call.function);
+                call.arguments
+                    .iter()
+                    .for_each(|x| self.expression(scope, x));
+            }
+            Expression::String(_) | Expression::Number(_) | Expression::Bool(_) => {}
+        }
+    }
+
+    fn declare(
+        &mut self,
+        scope: usize,
+        name: &Identifier,
+        kind: BindingKind,
+        ty: Option<&'static str>,
+    ) {
+        let binding = self.resolution.bindings.len();
+        self.resolution.bindings.push(Binding {
+            name: name.0.clone(),
+            kind,
+            span: name.1,
+            scope,
+            ty,
+        });
+        self.resolution.scopes[scope].bindings.push(binding);
+    }
+
+    fn reference(&mut self, scope: usize, name: &Identifier, write: bool) {
+        let target = match self.lookup(scope, &name.0) {
+            Some(binding) => Target::Binding(binding),
+            None if self.builtins.contains(&name.0) => Target::Builtin,
+            None => Target::Unresolved,
+        };
+        self.resolution.references.push(Reference {
+            name: name.0.clone(),
+            span: name.1,
+            target,
+            write,
+        });
+    }
+
+    // The latest binding of the name in the scope or the enclosing ones
+    fn lookup(&self, scope: usize, name: &str) -> Option<usize> {
+        let resolution = &self.resolution;
+        let mut current = Some(scope);
+        while let Some(idx) = current {
+            let scope = &resolution.scopes[idx];
+            if let Some(binding) = scope
+                .bindings
+                .iter()
+                .rev()
+                .find(|x| resolution.bindings[**x].name == name)
+            {
+                return Some(*binding);
+            }
+            current = scope.parent;
+        }
+        None
+    }
+}
+
+// The root identifier and dotted name of a path like `a.b.c`
+fn builtin_path(expression: &Expression) -> Option<(&Identifier, String)> {
+    match expression {
+        Expression::Identifier(ident) => Some((ident, ident.0.clone())),
+        Expression::Member(member) => {
+            let (root, path) = builtin_path(&member.object)?;
+            Some((root, format!("{}.{}", path, member.property.0)))
+        }
+        _ => None,
+    }
+}
+
+// The type an expression evaluates to whatever the bindings hold, named like
+// `Object::get_type`
+pub fn static_type(expression: &Expression) -> Option<&'static str> {
+    let ty = match expression {
+        Expression::Number(_) => "Number",
+        Expression::String(_) => "String",
+        Expression::Bool(_) => "Boolean",
+        Expression::ArrayLiteral(_) => "Array",
+        Expression::HashLiteral(_) => "Hash",
+        Expression::Function(_) => "Function",
+        Expression::Prefix(prefix) => match prefix.token {
+            TOKEN::BANG => "Boolean",
+            _ => "Number",
+        },
+        Expression::Infix(infix) => match infix.operator {
+            TOKEN::EQ | TOKEN::NotEQ | TOKEN::LT | TOKEN::GT => "Boolean",
+            TOKEN::MINUS | TOKEN::SLASH => "Number",
+            TOKEN::PLUS | TOKEN::ASTERISK => {
+                let left = static_type(&infix.left)?;
+                let right = static_type(&infix.right)?;
+                match (&infix.operator, left, right) {
+                    (_, "Number", "Number") => "Number",
+                    (TOKEN::PLUS, "String", "String") => "String",
+                    (TOKEN::ASTERISK, "String", "Number") => "String",
+                    (TOKEN::ASTERISK, "Number", "String") => "String",
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(ty)
+}
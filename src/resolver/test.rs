@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::ast::Span,
+        evaluator::{
+            builtin::{ArgType, BuiltinRegistry, Signature},
+            object::Object,
+        },
+        lexer::lexer::Lexer,
+        parser::parser::Parser,
+        resolver::resolver::{resolve, BindingKind, Resolution, Target},
+    };
+
+    // The defaults and a namespaced one
+    fn builtins() -> BuiltinRegistry {
+        let mut builtins = BuiltinRegistry::with_defaults();
+        let signature = Signature::new(vec![ArgType::Any]);
+        builtins.register("host.log", Some(signature), |_, _| Ok(Object::Null));
+        builtins
+    }
+
+    fn resolve_str(src: &str) -> Resolution {
+        let program = Parser::new(Lexer::new(src)).parse_program().unwrap();
+        resolve(&program, &builtins())
+    }
+
+    // Each reference as its text and the text of the binding it resolves to
+    fn targets(src: &str) -> Vec<(String, String)> {
+        let resolution = resolve_str(src);
+        resolution
+            .references
+            .iter()
+            .map(|x| {
+                let target = match x.target {
+                    Target::Binding(idx) => {
+                        let span = resolution.bindings[idx].span;
+                        format!("{}@{}", span.text(src), span.start)
+                    }
+                    Target::Builtin => "builtin".into(),
+                    Target::Unresolved => "unresolved".into(),
+                };
+                (x.span.text(src).to_string(), target)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let tests: Vec<(&str, Vec<(&str, &str)>)> = vec![
+            ("let x = 1; x", vec![("x", "x@4")]),
+            // the value is resolved before the name is bound
+            ("let x = 1; let x = x; x", vec![("x", "x@4"), ("x", "x@15")]),
+            ("y; let y = 1", vec![("y", "unresolved")]),
+            // functions see bindings made after them
+            (
+                "let f = fn() { g() }; let g = fn() { 1 }",
+                vec![("g", "g@26")],
+            ),
+            (
+                "let f = fn(a, b) { a + b }",
+                vec![("a", "a@11"), ("b", "b@14")],
+            ),
+            (
+                "let len = 1; len; print(len)",
+                vec![("len", "len@4"), ("print", "builtin"), ("len", "len@4")],
+            ),
+            ("len; x = 1", vec![("len", "builtin"), ("x", "unresolved")]),
+            (
+                "host.log(1); let host = {}; host.x",
+                vec![("host.log", "builtin"), ("host", "host@17")],
+            ),
+            ("if (true) { let a = 1 } a", vec![("a", "a@16")]),
+        ];
+        for (src, expected) in tests.into_iter() {
+            let expected = expected
+                .into_iter()
+                .map(|(x, y)| (x.to_string(), y.to_string()))
+                .collect::<Vec<_>>();
+            assert_eq!(targets(src), expected, "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_scopes() {
+        let src = "let a = 1; let f = fn(x) { let y = x; fn(z) { y + z } }";
+        let resolution = resolve_str(src);
+        assert_eq!(resolution.scopes.len(), 3);
+        let kinds = resolution
+            .bindings
+            .iter()
+            .map(|x| (x.name.as_str(), x.kind, x.scope))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("a", BindingKind::Let, 0),
+                ("f", BindingKind::Let, 0),
+                ("x", BindingKind::Param, 1),
+                ("y", BindingKind::Let, 1),
+                ("z", BindingKind::Param, 2),
+            ]
+        );
+        assert_eq!(resolution.scopes[1].span, Span::new(25, src.len()));
+        let inner = src.find("y + z").unwrap();
+        assert_eq!(resolution.scope_at(inner), 2);
+        let names = |scope| {
+            resolution
+                .visible(scope)
+                .into_iter()
+                .map(|x| resolution.bindings[x].name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(2), vec!["z", "y", "x", "f", "a"]);
+        assert_eq!(resolution.binding_at(inner), Some(3));
+        assert!(resolution.is_read(3));
+        assert!(!resolution.is_read(1));
+        assert!(!resolution.is_reassigned(0));
+    }
+
+    #[test]
+    fn test_static_type() {
+        let tests = vec![
+            ("1", Some("Number")),
+            ("\"a\" + \"b\"", Some("String")),
+            ("\"a\" * 3", Some("String")),
+            ("\"a\" + 1", None),
+            ("1 < x", Some("Boolean")),
+            ("!x", Some("Boolean")),
+            ("-x", Some("Number")),
+            ("[1]", Some("Array")),
+            ("{}", Some("Hash")),
+            ("fn() {}", Some("Function")),
+            ("x + 1", None),
+            ("f()", None),
+        ];
+        for (src, expected) in tests.into_iter() {
+            let src = format!("let v = {}", src);
+            let resolution = resolve_str(&src);
+            let binding = resolution.bindings.last().unwrap();
+            assert_eq!(binding.ty, expected, "{}", src);
+        }
+    }
+}
@@ -1 +1,2 @@
 pub mod macros;
+pub mod shared;
@@ -0,0 +1,186 @@
+// Shared ownership used by objects and environments. By default these are
+// the single threaded `std::rc::Rc` and `std::cell::RefCell`; the `sync`
+// feature swaps in `Arc` and a lock with the same API, which makes an
+// `Interpreter` and its objects `Send`.
+
+#[cfg(not(feature = "sync"))]
+mod imp {
+    pub use std::{cell::RefCell, rc::Rc};
+
+    pub type SharedAny = dyn std::any::Any;
+
+    // Bounds that only apply to the thread-safe build
+    pub trait MaybeSend {}
+    impl<T: ?Sized> MaybeSend for T {}
+    pub trait MaybeSync {}
+    impl<T: ?Sized> MaybeSync for T {}
+}
+
+#[cfg(feature = "sync")]
+mod imp {
+    use std::{
+        cell::UnsafeCell,
+        collections::HashMap,
+        fmt::{self, Debug},
+        ops::{Deref, DerefMut},
+        sync::{Condvar, Mutex, MutexGuard, PoisonError},
+        thread::{self, ThreadId},
+    };
+
+    pub use std::sync::Arc as Rc;
+
+    pub type SharedAny = dyn std::any::Any + Send + Sync;
+
+    pub trait MaybeSend: Send {}
+    impl<T: ?Sized + Send> MaybeSend for T {}
+    pub trait MaybeSync: Sync {}
+    impl<T: ?Sized + Sync> MaybeSync for T {}
+
+    // Threads holding a borrow of a cell
+    #[derive(Default)]
+    struct Borrows {
+        readers: HashMap<ThreadId, usize>,
+        writer: Option<ThreadId>,
+    }
+
+    // RefCell API over a readers-writer lock that knows its owners. A borrow
+    // conflicting with one of the same thread panics like RefCell does, one
+    // conflicting with another thread waits for it to be released.
+    #[derive(Default)]
+    pub struct RefCell<T: ?Sized> {
+        borrows: Mutex<Borrows>,
+        released: Condvar,
+        value: UnsafeCell<T>,
+    }
+
+    // The value is only reached through `Ref` and `RefMut`, which `borrows`
+    // keeps to many readers or one writer, like `RwLock`
+    unsafe impl<T: ?Sized + Send + Sync> Sync for RefCell<T> {}
+
+    impl<T> RefCell<T> {
+        pub fn new(value: T) -> Self {
+            Self {
+                borrows: Mutex::default(),
+                released: Condvar::new(),
+                value: UnsafeCell::new(value),
+            }
+        }
+        pub fn into_inner(self) -> T {
+            self.value.into_inner()
+        }
+    }
+
+    impl<T: ?Sized> RefCell<T> {
+        pub fn borrow(&self) -> Ref<'_, T> {
+            let thread = thread::current().id();
+            let mut borrows = self.lock();
+            if borrows.writer == Some(thread) {
+                drop(borrows);
+                panic!("already mutably borrowed");
+            }
+            // a thread already reading keeps writers out and may read again
+            if !borrows.readers.contains_key(&thread) {
+                while borrows.writer.is_some() {
+                    borrows = self.wait(borrows);
+                }
+            }
+            *borrows.readers.entry(thread).or_default() += 1;
+            Ref { cell: self, thread }
+        }
+        pub fn borrow_mut(&self) -> RefMut<'_, T> {
+            let thread = thread::current().id();
+            let mut borrows = self.lock();
+            if borrows.writer == Some(thread) || borrows.readers.contains_key(&thread) {
+                drop(borrows);
+                panic!("already borrowed");
+            }
+            while borrows.writer.is_some() || !borrows.readers.is_empty() {
+                borrows = self.wait(borrows);
+            }
+            borrows.writer = Some(thread);
+            RefMut { cell: self }
+        }
+
+        fn lock(&self) -> MutexGuard<'_, Borrows> {
+            self.borrows.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+        fn wait<'a>(&self, borrows: MutexGuard<'a, Borrows>) -> MutexGuard<'a, Borrows> {
+            self.released
+                .wait(borrows)
+                .unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+
+    pub struct Ref<'a, T: ?Sized> {
+        cell: &'a RefCell<T>,
+        thread: ThreadId,
+    }
+
+    impl<T: ?Sized> Deref for Ref<'_, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            // no writer until this reader is released
+            unsafe { &*self.cell.value.get() }
+        }
+    }
+
+    impl<T: ?Sized> Drop for Ref<'_, T> {
+        fn drop(&mut self) {
+            let mut borrows = self.cell.lock();
+            let count = borrows.readers.get_mut(&self.thread).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                borrows.readers.remove(&self.thread);
+                self.cell.released.notify_all();
+            }
+        }
+    }
+
+    pub struct RefMut<'a, T: ?Sized> {
+        cell: &'a RefCell<T>,
+    }
+
+    impl<T: ?Sized> Deref for RefMut<'_, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            // no other borrow until this writer is released
+            unsafe { &*self.cell.value.get() }
+        }
+    }
+
+    impl<T: ?Sized> DerefMut for RefMut<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            unsafe { &mut *self.cell.value.get() }
+        }
+    }
+
+    impl<T: ?Sized> Drop for RefMut<'_, T> {
+        fn drop(&mut self) {
+            self.cell.lock().writer = None;
+            self.cell.released.notify_all();
+        }
+    }
+
+    impl<T: Clone> Clone for RefCell<T> {
+        fn clone(&self) -> Self {
+            Self::new(self.borrow().clone())
+        }
+    }
+
+    impl<T: ?Sized + PartialEq> PartialEq for RefCell<T> {
+        fn eq(&self, other: &Self) -> bool {
+            *self.borrow() == *other.borrow()
+        }
+    }
+
+    impl<T: ?Sized + Debug> Debug for RefCell<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            if self.lock().writer == Some(thread::current().id()) {
+                return f.write_str("RefCell(<borrowed>)");
+            }
+            f.debug_tuple("RefCell").field(&&*self.borrow()).finish()
+        }
+    }
+}
+
+pub use imp::*;